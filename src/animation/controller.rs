@@ -5,15 +5,26 @@ use super::types::*;
 
 /// Get interpolated frame data from controller
 /// Based on GetFrameData function in mdlDraw.pas (lines 776-960)
+/// Controllers bound to a global sequence ignore `frame` and use the
+/// current frame of their global sequence from `global_frames`
 pub fn get_frame_data(
     controllers: &[Controller],
     controller_idx: i32,
     frame: i32,
+    global_frames: &[i32],
 ) -> Vec<f32> {
     if controller_idx < 0 || controller_idx as usize >= controllers.len() {
         return vec![0.0; 4]; // Default values
     }
 
     let controller = &controllers[controller_idx as usize];
+    let frame = if controller.global_seq_id >= 0 {
+        global_frames
+            .get(controller.global_seq_id as usize)
+            .copied()
+            .unwrap_or(0)
+    } else {
+        frame
+    };
     controller.get_frame_data(frame)
 }
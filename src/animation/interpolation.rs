@@ -72,6 +72,11 @@ pub fn quat_slerp(q1: &glm::Quat, q2: &glm::Quat, t: f32) -> glm::Quat {
     glm::quat_slerp(q1, q2, t)
 }
 
+/// Color stored blue first, as MDX and MDL files list every color, in RGB order
+pub fn bgr_to_rgb(bgr: &[f32]) -> glm::Vec3 {
+    glm::vec3(bgr[2], bgr[1], bgr[0])
+}
+
 /// Linear interpolation for vectors
 #[allow(dead_code)]
 pub fn lerp_vec3(v1: &glm::Vec3, v2: &glm::Vec3, t: f32) -> glm::Vec3 {
@@ -3,8 +3,11 @@
 
 pub mod controller;
 pub mod interpolation;
+pub mod particles;
+pub mod ribbons;
 pub mod skeleton;
 pub mod system;
+pub mod texture_anim;
 pub mod types;
 
 pub use system::AnimationSystem;
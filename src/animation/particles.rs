@@ -0,0 +1,409 @@
+// Particle emitter (PREM) and particle emitter 2 (PRE2) simulation
+// Particles are not carried from one frame to the next: the ones alive at a frame are
+// rebuilt from the emitter states along the sequence, so a frame always looks the same
+// whether it is played, scrubbed or paused
+
+use super::interpolation::bgr_to_rgb;
+use super::system::AnimationSystem;
+use super::types::{EmitterState, ParticleEmitterState};
+use crate::model::emitter::{
+    PARTICLE2_FLAG_LINE_EMITTER, PARTICLE2_FLAG_MODEL_SPACE, ParticleEmitter2,
+};
+use crate::model::model::Model;
+use nalgebra_glm as glm;
+
+/// Time between two emissions, in frames (ms)
+pub const PARTICLE_STEP: i32 = 33;
+
+/// Particles beyond this count are not simulated
+pub const MAX_PARTICLES: usize = 20000;
+
+/// A live particle, in model space
+#[derive(Debug, Clone)]
+pub struct Particle {
+    pub emitter: usize,      // Index into `Model::particle_emitters2`
+    pub position: glm::Vec3, // Head position
+    pub velocity: glm::Vec3, // Units per second, the tail trails behind it
+    pub color: glm::Vec4,    // RGBA from the segment colors and alpha
+    pub scale: f32,          // Half size of the quad
+    pub head_cell: u32,      // Texture atlas cells
+    pub tail_cell: u32,
+}
+
+/// A live PREM particle: a copy of the emitter model or texture, in model space
+#[derive(Debug, Clone)]
+pub struct SpawnedParticle {
+    pub emitter: usize,         // Index into `Model::particle_emitters`
+    pub position: glm::Vec3,    // Origin of the copy
+    pub orientation: glm::Mat3, // Rotation and scaling of the emitter when it was emitted
+    pub age: f32,               // Seconds since it was emitted
+    pub life: f32,              // 0 when emitted, 1 at the end of the lifespan
+}
+
+impl AnimationSystem {
+    /// PREM particles alive at `frame` of a sequence that starts at `start_frame`,
+    /// stepped through the sequence like `simulate_particles`
+    pub fn simulate_spawned_particles(
+        &mut self,
+        model: &Model,
+        start_frame: u32,
+        frame: f32,
+        time: f64,
+        camera_orientation: (f32, f32),
+    ) -> Vec<SpawnedParticle> {
+        let mut particles = Vec::new();
+        if model.particle_emitters.is_empty()
+            || self.particle_emitters.len() != model.particle_emitters.len()
+        {
+            return particles;
+        }
+
+        let start = start_frame as i32;
+        let frame_int = frame as i32;
+        if frame_int < start {
+            return particles;
+        }
+
+        // The lifespan can be animated, so the window covers its longest key
+        let max_lifespan = model
+            .particle_emitters
+            .iter()
+            .map(|emitter| {
+                usize::try_from(emitter.lifespan_idx)
+                    .ok()
+                    .and_then(|idx| model.controllers.get(idx))
+                    .map_or(emitter.lifespan, |controller| {
+                        controller
+                            .keyframes
+                            .iter()
+                            .filter_map(|key| key.data.first().copied())
+                            .fold(0.0f32, f32::max)
+                    })
+            })
+            .fold(0.0f32, f32::max);
+        let first_step = first_step(max_lifespan, frame_int - start);
+        let last_step = (frame_int - start) / PARTICLE_STEP;
+
+        'steps: for step in first_step..=last_step {
+            let step_frame = start + step * PARTICLE_STEP;
+            let step_time = time - (frame_int - step_frame) as f64 / 1000.0;
+            self.update_emitters(step_frame as f32, step_time, camera_orientation);
+
+            for (idx, state) in self.particle_emitters.iter().enumerate() {
+                if !state.node.visible || state.lifespan <= 0.0 {
+                    continue;
+                }
+
+                // Salts are kept apart from the PRE2 ones by the emitter index
+                let emitter_key = usize::MAX - idx;
+                let expected = state.emission_rate.max(0.0) * PARTICLE_STEP as f32 / 1000.0;
+                let count = (expected + random(emitter_key, step, u32::MAX, 0)).floor() as u32;
+
+                for particle in 0..count {
+                    let random = |salt| random(emitter_key, step, particle, salt);
+                    let offset = random(1) * PARTICLE_STEP as f32;
+                    let age = ((frame_int - step_frame) as f32 - offset) / 1000.0;
+                    if age < 0.0 || age >= state.lifespan {
+                        continue;
+                    }
+
+                    particles.push(spawn_copy(idx, state, age, random));
+                    if particles.len() >= MAX_PARTICLES {
+                        break 'steps;
+                    }
+                }
+            }
+        }
+
+        self.update_emitters(frame, time, camera_orientation);
+        particles
+    }
+
+    /// Particles alive at `frame` of a sequence that starts at `start_frame`.
+    /// The emitters are stepped through the lifespan of the oldest particles to find
+    /// where they were emitted, and left at `frame` again afterwards.
+    /// `time` and `camera_orientation` are the ones given to `update`
+    pub fn simulate_particles(
+        &mut self,
+        model: &Model,
+        start_frame: u32,
+        frame: f32,
+        time: f64,
+        camera_orientation: (f32, f32),
+    ) -> Vec<Particle> {
+        let mut particles = Vec::new();
+        if model.particle_emitters2.is_empty()
+            || self.emitters.len() != model.particle_emitters2.len()
+        {
+            return particles;
+        }
+
+        let start = start_frame as i32;
+        let frame_int = frame as i32;
+        if frame_int < start {
+            return particles;
+        }
+
+        // Steps of the sequence whose particles can still be alive
+        let max_lifespan = model
+            .particle_emitters2
+            .iter()
+            .map(|emitter| emitter.lifespan)
+            .fold(0.0f32, f32::max);
+        let first_step = first_step(max_lifespan, frame_int - start);
+        let last_step = (frame_int - start) / PARTICLE_STEP;
+
+        // Model space particles follow the emitter as it is now
+        let current: Vec<EmitterState> = self.emitters.clone();
+
+        'steps: for step in first_step..=last_step {
+            let step_frame = start + step * PARTICLE_STEP;
+            let step_time = time - (frame_int - step_frame) as f64 / 1000.0;
+            self.update_emitters(step_frame as f32, step_time, camera_orientation);
+
+            for (idx, (emitter, state)) in model
+                .particle_emitters2
+                .iter()
+                .zip(&self.emitters)
+                .enumerate()
+            {
+                if !state.node.visible {
+                    continue;
+                }
+
+                let count = if emitter.squirt {
+                    squirt_count(model, emitter, step_frame)
+                } else {
+                    let expected = state.emission_rate.max(0.0) * PARTICLE_STEP as f32 / 1000.0;
+                    (expected + random(idx, step, u32::MAX, 0)).floor() as u32
+                };
+
+                for particle in 0..count {
+                    // Streams are spread over the step, bursts leave together
+                    let offset = if emitter.squirt {
+                        0.0
+                    } else {
+                        random(idx, step, particle, 1) * PARTICLE_STEP as f32
+                    };
+                    let age = ((frame_int - step_frame) as f32 - offset) / 1000.0;
+                    if age < 0.0 || age >= emitter.lifespan {
+                        continue;
+                    }
+
+                    particles.push(spawn(idx, emitter, state, &current[idx], age, |salt| {
+                        random(idx, step, particle, salt)
+                    }));
+                    if particles.len() >= MAX_PARTICLES {
+                        break 'steps;
+                    }
+                }
+            }
+        }
+
+        self.update_emitters(frame, time, camera_orientation);
+        particles
+    }
+}
+
+/// Particles emitted at once by a squirt emitter: the value of each emission rate key
+/// that falls within the step ending at `step_frame`
+/// First step whose particles can still be alive `elapsed` ms into the sequence.
+/// The window never reaches back past the start, however long the lifespan
+fn first_step(max_lifespan: f32, elapsed: i32) -> i32 {
+    let window = ((max_lifespan * 1000.0).ceil() as i32)
+        .saturating_add(PARTICLE_STEP)
+        .min(elapsed);
+    (elapsed - window) / PARTICLE_STEP
+}
+
+fn squirt_count(model: &Model, emitter: &ParticleEmitter2, step_frame: i32) -> u32 {
+    let Some(controller) = usize::try_from(emitter.emission_rate_idx)
+        .ok()
+        .and_then(|idx| model.controllers.get(idx))
+    else {
+        return 0;
+    };
+    controller
+        .keyframes
+        .iter()
+        .filter(|key| key.frame > step_frame - PARTICLE_STEP && key.frame <= step_frame)
+        .map(|key| key.data.first().copied().unwrap_or(0.0).max(0.0) as u32)
+        .sum()
+}
+
+/// Particle emitted `age` seconds ago by an emitter that was in `state` at the time
+fn spawn(
+    idx: usize,
+    emitter: &ParticleEmitter2,
+    state: &EmitterState,
+    current: &EmitterState,
+    age: f32,
+    random: impl Fn(u32) -> f32,
+) -> Particle {
+    let line_emitter = emitter.has_flag(PARTICLE2_FLAG_LINE_EMITTER);
+
+    // Somewhere on the emitter rectangle, which is only a line for line emitters
+    let offset = glm::vec3(
+        (random(2) - 0.5) * state.width,
+        if line_emitter {
+            0.0
+        } else {
+            (random(3) - 0.5) * state.length
+        },
+        0.0,
+    );
+
+    // Up the emitter Z axis, tilted by up to the latitude. Line emitters only
+    // spread sideways from the line
+    let tilt = state.latitude.to_radians() * (random(4) * 2.0 - 1.0);
+    let heading = if line_emitter {
+        std::f32::consts::FRAC_PI_2
+    } else {
+        std::f32::consts::PI * (random(5) * 2.0 - 1.0)
+    };
+    let direction = glm::vec3(
+        tilt.sin() * heading.cos(),
+        tilt.sin() * heading.sin(),
+        tilt.cos(),
+    );
+    let speed = state.speed * (1.0 + state.variation * (random(6) * 2.0 - 1.0));
+
+    // Emitted from where the emitter was, or from where it is now in model space
+    let node = if emitter.has_flag(PARTICLE2_FLAG_MODEL_SPACE) {
+        &current.node
+    } else {
+        &state.node
+    };
+    let world_direction = node.abs_matrix * direction;
+    let world_direction = if glm::length(&world_direction) > 0.0 {
+        glm::normalize(&world_direction)
+    } else {
+        direction
+    };
+    let start = node.abs_matrix * offset + node.abs_vector;
+    let velocity = world_direction * speed;
+
+    // Gravity pulls down along the model Z axis
+    let gravity = glm::vec3(0.0, 0.0, -state.gravity);
+    let position = start + velocity * age + gravity * (0.5 * age * age);
+    let velocity = velocity + gravity * age;
+
+    let (color, scale, head_cell, tail_cell) = segment(emitter, age / emitter.lifespan);
+    Particle {
+        emitter: idx,
+        position,
+        velocity,
+        color,
+        scale,
+        head_cell,
+        tail_cell,
+    }
+}
+
+/// PREM particle emitted `age` seconds ago by an emitter that was in `state` at the time
+fn spawn_copy(
+    idx: usize,
+    state: &ParticleEmitterState,
+    age: f32,
+    random: impl Fn(u32) -> f32,
+) -> SpawnedParticle {
+    // Up the emitter Z axis, tilted by up to the latitude and turned by up to the longitude
+    let tilt = state.latitude * (random(2) * 2.0 - 1.0);
+    let heading = state.longitude * (random(3) * 2.0 - 1.0);
+    let direction = glm::vec3(
+        tilt.sin() * heading.cos(),
+        tilt.sin() * heading.sin(),
+        tilt.cos(),
+    );
+
+    let node = &state.node;
+    let world_direction = node.abs_matrix * direction;
+    let world_direction = if glm::length(&world_direction) > 0.0 {
+        glm::normalize(&world_direction)
+    } else {
+        direction
+    };
+    let velocity = world_direction * state.speed;
+
+    // Gravity pulls down along the model Z axis
+    let gravity = glm::vec3(0.0, 0.0, -state.gravity);
+    let position = node.abs_vector + velocity * age + gravity * (0.5 * age * age);
+
+    SpawnedParticle {
+        emitter: idx,
+        position,
+        orientation: node.abs_matrix,
+        age,
+        life: age / state.lifespan,
+    }
+}
+
+/// Color, scale and atlas cells of a particle `life` (0 to 1) through its lifespan.
+/// Values go from the first segment to the second until `time`, then to the third
+fn segment(emitter: &ParticleEmitter2, life: f32) -> (glm::Vec4, f32, u32, u32) {
+    let middle = emitter.time.clamp(0.0, 1.0);
+    let (from, factor, head, tail) = if life < middle {
+        let factor = if middle > 0.0 { life / middle } else { 1.0 };
+        (0, factor, emitter.head_intervals, emitter.tail_intervals)
+    } else {
+        let factor = if middle < 1.0 {
+            (life - middle) / (1.0 - middle)
+        } else {
+            1.0
+        };
+        (
+            1,
+            factor,
+            emitter.head_decay_intervals,
+            emitter.tail_decay_intervals,
+        )
+    };
+    let factor = factor.clamp(0.0, 1.0);
+    let lerp = |a: f32, b: f32| a + (b - a) * factor;
+
+    let rgb = glm::lerp(
+        &bgr_to_rgb(&emitter.segment_colors[from]),
+        &bgr_to_rgb(&emitter.segment_colors[from + 1]),
+        factor,
+    );
+    let alpha = lerp(
+        emitter.segment_alpha[from] as f32,
+        emitter.segment_alpha[from + 1] as f32,
+    ) / 255.0;
+    let color = glm::vec4(rgb.x, rgb.y, rgb.z, alpha);
+    let scale = lerp(
+        emitter.segment_scaling[from],
+        emitter.segment_scaling[from + 1],
+    );
+
+    (
+        color,
+        scale,
+        atlas_cell(head, factor),
+        atlas_cell(tail, factor),
+    )
+}
+
+/// Atlas cell of a UV animation `[start, end, repeat]` at `factor` through it
+fn atlas_cell([start, end, repeat]: [u32; 3], factor: f32) -> u32 {
+    if end <= start {
+        return start;
+    }
+    let factor = (factor * repeat.max(1) as f32).fract();
+    let cell = start + (factor * (end - start + 1) as f32) as u32;
+    cell.min(end)
+}
+
+/// Deterministic number in [0, 1) for one random choice of one particle
+fn random(emitter: usize, step: i32, particle: u32, salt: u32) -> f32 {
+    // SplitMix64 over the particle identity
+    let mut x = (emitter as u64) << 48
+        ^ (step as u32 as u64) << 16
+        ^ (particle as u64).rotate_left(32)
+        ^ salt as u64;
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 31;
+    (x >> 40) as f32 / (1u64 << 24) as f32
+}
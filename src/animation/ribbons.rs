@@ -0,0 +1,180 @@
+// Ribbon emitter (RIBB) strips
+// Like particles, the edges alive at a frame are rebuilt from the emitter states along
+// the sequence instead of being carried over, so a frame always looks the same
+
+use super::system::AnimationSystem;
+use super::types::RibbonState;
+use crate::model::model::Model;
+use nalgebra_glm as glm;
+
+/// Time between two samples of the emitter nodes, in frames (ms). Edges emitted
+/// in between are placed along the line joining the samples
+pub const RIBBON_STEP: i32 = 25;
+
+/// Edges beyond this count are not generated
+pub const MAX_RIBBON_EDGES: usize = 20000;
+
+/// Cross-section of a ribbon, in model space
+#[derive(Debug, Clone)]
+pub struct RibbonEdge {
+    pub above: glm::Vec3, // Ends of the edge, along the emitter Y axis
+    pub below: glm::Vec3,
+    pub color: glm::Vec4, // RGBA of the emitter when the edge was emitted
+    pub life: f32,        // 0 when emitted, 1 at the end of the lifespan
+    pub texture_slot: u32,
+}
+
+/// Unbroken strip of edges left by one emitter, oldest first
+#[derive(Debug, Clone)]
+pub struct Ribbon {
+    pub emitter: usize, // Index into `Model::ribbon_emitters`
+    pub edges: Vec<RibbonEdge>,
+}
+
+/// Emitter state at one sample, None while hidden
+type Sample = Option<RibbonEdge>;
+
+impl AnimationSystem {
+    /// Ribbons alive at `frame` of a sequence that starts at `start_frame`.
+    /// The emitters are stepped through the lifespan of the oldest edges to find
+    /// where they were emitted, and left at `frame` again afterwards.
+    /// `time` and `camera_orientation` are the ones given to `update`
+    pub fn simulate_ribbons(
+        &mut self,
+        model: &Model,
+        start_frame: u32,
+        frame: f32,
+        time: f64,
+        camera_orientation: (f32, f32),
+    ) -> Vec<Ribbon> {
+        let mut ribbons = Vec::new();
+        if model.ribbon_emitters.is_empty() || self.ribbons.len() != model.ribbon_emitters.len() {
+            return ribbons;
+        }
+
+        let start = start_frame as i32;
+        let frame_int = frame as i32;
+        if frame_int < start {
+            return ribbons;
+        }
+
+        // Samples of the sequence that edges still alive were emitted between
+        let max_lifespan = model
+            .ribbon_emitters
+            .iter()
+            .map(|emitter| emitter.lifespan)
+            .fold(0.0f32, f32::max);
+        let window = (max_lifespan * 1000.0).ceil() as i32;
+        let first_step = ((frame_int - start - window) / RIBBON_STEP).max(0);
+        let last_step = (frame_int - start) / RIBBON_STEP;
+
+        let mut samples: Vec<(i32, Vec<Sample>)> = Vec::new();
+        for step in first_step..=last_step {
+            let step_frame = start + step * RIBBON_STEP;
+            let step_time = time - (frame_int - step_frame) as f64 / 1000.0;
+            self.update_emitters(step_frame as f32, step_time, camera_orientation);
+            samples.push((step_frame, self.ribbons.iter().map(sample).collect()));
+        }
+
+        // The newest edge is always where the emitter is now
+        self.update_emitters(frame, time, camera_orientation);
+        if samples.last().is_none_or(|(last, _)| *last < frame_int) {
+            samples.push((frame_int, self.ribbons.iter().map(sample).collect()));
+        }
+
+        let mut edge_count = 0;
+        for (idx, emitter) in model.ribbon_emitters.iter().enumerate() {
+            if emitter.emission_rate == 0 || emitter.lifespan <= 0.0 {
+                continue;
+            }
+
+            // Edges leave at a steady rate from the start of the sequence
+            let interval = 1000.0 / emitter.emission_rate as f32;
+            let elapsed = (frame_int - start) as f32;
+            let oldest = (elapsed - emitter.lifespan * 1000.0).max(0.0);
+            let mut times: Vec<f32> = ((oldest / interval).ceil() as u32
+                ..=(elapsed / interval).floor() as u32)
+                .map(|edge| edge as f32 * interval)
+                .collect();
+            if times.last().is_none_or(|&last| last < elapsed) {
+                times.push(elapsed);
+            }
+
+            let mut edges = Vec::new();
+            for offset in times {
+                let Some(mut edge) = edge_at(&samples, idx, start as f32 + offset) else {
+                    // Hidden emitters break the ribbon
+                    if edges.len() >= 2 {
+                        edge_count += edges.len();
+                        ribbons.push(Ribbon {
+                            emitter: idx,
+                            edges: std::mem::take(&mut edges),
+                        });
+                    } else {
+                        edges.clear();
+                    }
+                    continue;
+                };
+
+                // Gravity pulls the edge down along the model Z axis as it ages
+                let age = (elapsed - offset) / 1000.0;
+                let drop = glm::vec3(0.0, 0.0, -0.5 * emitter.gravity * age * age);
+                edge.above += drop;
+                edge.below += drop;
+                edge.life = age / emitter.lifespan;
+                edges.push(edge);
+            }
+            if edges.len() >= 2 {
+                edge_count += edges.len();
+                ribbons.push(Ribbon {
+                    emitter: idx,
+                    edges,
+                });
+            }
+            if edge_count >= MAX_RIBBON_EDGES {
+                break;
+            }
+        }
+
+        ribbons
+    }
+}
+
+/// Edge an emitter in `state` would emit now, None while it is hidden
+fn sample(state: &RibbonState) -> Sample {
+    let node = &state.node;
+    if !node.visible {
+        return None;
+    }
+    Some(RibbonEdge {
+        above: node.abs_matrix * glm::vec3(0.0, state.height_above, 0.0) + node.abs_vector,
+        below: node.abs_matrix * glm::vec3(0.0, -state.height_below, 0.0) + node.abs_vector,
+        color: glm::vec4(state.color.x, state.color.y, state.color.z, state.alpha),
+        life: 0.0,
+        texture_slot: state.texture_slot,
+    })
+}
+
+/// Edge emitted by emitter `idx` at `frame`, between the two samples around it.
+/// None when the emitter was hidden at either of them
+fn edge_at(samples: &[(i32, Vec<Sample>)], idx: usize, frame: f32) -> Option<RibbonEdge> {
+    let next = samples
+        .partition_point(|(sample_frame, _)| (*sample_frame as f32) < frame)
+        .min(samples.len() - 1);
+    let (next_frame, next_samples) = &samples[next];
+    let after = next_samples[idx].as_ref()?;
+    if next == 0 || *next_frame as f32 <= frame {
+        return Some(after.clone());
+    }
+
+    let (previous_frame, previous_samples) = &samples[next - 1];
+    let before = previous_samples[idx].as_ref()?;
+    let factor = (frame - *previous_frame as f32) / (next_frame - previous_frame) as f32;
+    Some(RibbonEdge {
+        above: glm::lerp(&before.above, &after.above, factor),
+        below: glm::lerp(&before.below, &after.below, factor),
+        color: glm::lerp(&before.color, &after.color, factor),
+        life: 0.0,
+        texture_slot: before.texture_slot,
+    })
+}
@@ -11,8 +11,10 @@ use nalgebra_glm as glm;
 pub fn interp_bone(
     bone: &mut BoneState,
     frame: i32,
+    global_frames: &[i32],
     controllers: &[Controller],
     pivot_points: &[glm::Vec3],
+    camera_dir: &glm::Vec3,
 ) {
     // Bone is ready if it has no parent
     bone.is_ready = bone.parent < 0;
@@ -30,7 +32,7 @@ pub fn interp_bone(
         bone.abs_vector = pivot;
     } else {
         // Get animated translation
-        let data = get_frame_data(controllers, bone.translation_idx, frame);
+        let data = get_frame_data(controllers, bone.translation_idx, frame, global_frames);
         bone.abs_vector = glm::vec3(
             data[0] + pivot.x,
             data[1] + pivot.y,
@@ -44,7 +46,7 @@ pub fn interp_bone(
         bone.abs_quaternion = glm::quat_identity();
     } else {
         // Get animated rotation (quaternion)
-        let data = get_frame_data(controllers, bone.rotation_idx, frame);
+        let data = get_frame_data(controllers, bone.rotation_idx, frame, global_frames);
         bone.abs_quaternion = glm::quat(data[3], data[0], data[1], data[2]);
     }
 
@@ -54,7 +56,7 @@ pub fn interp_bone(
         bone.abs_scaling = glm::vec3(1.0, 1.0, 1.0);
     } else {
         // Get animated scaling
-        let data = get_frame_data(controllers, bone.scaling_idx, frame);
+        let data = get_frame_data(controllers, bone.scaling_idx, frame, global_frames);
         bone.abs_scaling = glm::vec3(data[0], data[1], data[2]);
     }
 
@@ -62,7 +64,7 @@ pub fn interp_bone(
     if bone.visibility_idx < 0 {
         bone.visible = true;
     } else {
-        let data = get_frame_data(controllers, bone.visibility_idx, frame);
+        let data = get_frame_data(controllers, bone.visibility_idx, frame, global_frames);
         bone.visible = data[0] > 0.2; // Threshold from original code
     }
 
@@ -72,8 +74,30 @@ pub fn interp_bone(
     // Apply scaling to matrix (each column scaled by corresponding component)
     bone.abs_matrix = apply_scaling_to_matrix(&bone.abs_matrix, &bone.abs_scaling);
 
-    // TODO: Apply billboard transformation if needed
-    // if bone.is_billboarded { ... }
+    // Billboards turn their own rotation towards the camera
+    if bone.faces_camera() {
+        bone.abs_matrix = billboard_matrix(bone, camera_dir) * bone.abs_matrix;
+    }
+}
+
+/// Rotation that points the node's X axis at the camera, or as close as its lock allows.
+/// `camera_dir` is the direction from the model towards the camera, in model space
+fn billboard_matrix(bone: &BoneState, camera_dir: &glm::Vec3) -> glm::Mat3 {
+    let rotation = |angle: f32, axis: glm::Vec3| glm::mat4_to_mat3(&glm::rotation(angle, &axis));
+
+    if bone.billboard_lock_x {
+        // Spins around X, so the Z axis is the one facing the camera
+        rotation((-camera_dir.y).atan2(camera_dir.z), glm::vec3(1.0, 0.0, 0.0))
+    } else if bone.billboard_lock_y {
+        rotation(-camera_dir.z.atan2(camera_dir.x), glm::vec3(0.0, 1.0, 0.0))
+    } else if bone.billboard_lock_z {
+        rotation(camera_dir.y.atan2(camera_dir.x), glm::vec3(0.0, 0.0, 1.0))
+    } else {
+        // Full billboard: yaw then pitch, keeping Z up like the camera
+        let yaw = camera_dir.y.atan2(camera_dir.x);
+        let pitch = camera_dir.z.clamp(-1.0, 1.0).asin();
+        rotation(yaw, glm::vec3(0.0, 0.0, 1.0)) * rotation(-pitch, glm::vec3(0.0, 1.0, 0.0))
+    }
 }
 
 /// Calculate absolute transformation from parent
@@ -90,25 +114,45 @@ pub fn calc_absolute(
         glm::vec3(0.0, 0.0, 0.0)
     };
 
-    // 1. Multiply rotation matrices
-    if !child.is_billboarded {
+    // 1. Multiply rotation matrices, leaving out what the child doesn't inherit.
+    // Billboarded objects have their own rotation and only get the parent scaling
+    let inherit_rotation = !child.dont_inherit_rotation && !child.faces_camera();
+    let inherit_scaling = !child.dont_inherit_scaling;
+    if inherit_rotation && inherit_scaling {
         child.abs_matrix = mul_matrices(&parent.abs_matrix, &child.abs_matrix);
     } else {
-        // For billboarded objects, only apply parent scaling
-        let identity = glm::identity::<f32, 3>();
-        let scaled = apply_scaling_to_matrix(&identity, &parent.abs_scaling);
-        child.abs_matrix = mul_matrices(&scaled, &child.abs_matrix);
+        // Split the parent matrix into the scaling of its columns and the rest
+        let scaling = glm::vec3(
+            glm::length(&parent.abs_matrix.column(0).into_owned()),
+            glm::length(&parent.abs_matrix.column(1).into_owned()),
+            glm::length(&parent.abs_matrix.column(2).into_owned()),
+        );
+        let inherited = if inherit_rotation {
+            let unscale = scaling.map(|s| if s > 0.0 { 1.0 / s } else { 0.0 });
+            parent.abs_matrix * glm::diagonal3x3(&unscale)
+        } else if inherit_scaling {
+            glm::diagonal3x3(&scaling)
+        } else {
+            glm::identity()
+        };
+        child.abs_matrix = mul_matrices(&inherited, &child.abs_matrix);
     }
 
-    // 2. Transform child position by parent
+    // 2. Transform child position by parent: the pivot still follows
+    // the whole parent transformation
     // Subtract parent pivot
     let local_pos = child.abs_vector - parent_pivot;
 
     // Transform by parent matrix
     let transformed = parent.abs_matrix * local_pos;
 
-    // Add parent position
-    child.abs_vector = parent.abs_vector + transformed;
+    // Add parent position, or only its pivot if its translation is not inherited
+    let parent_position = if child.dont_inherit_translation {
+        parent_pivot
+    } else {
+        parent.abs_vector
+    };
+    child.abs_vector = parent_position + transformed;
 
     // 3. Combine visibility
     child.visible = child.visible && parent.visible;
@@ -1,15 +1,40 @@
+use super::controller::get_frame_data;
+use super::interpolation::bgr_to_rgb;
 use super::skeleton::*;
+use super::texture_anim::interp_texture_anim;
 use super::types::*;
+use crate::model::geoset::GEOSET_ANIM_COLOR;
 use crate::model::model::Model;
+use crate::model::skeleton::{
+    NODE_FLAG_BILLBOARDED, NODE_FLAG_BILLBOARDED_LOCK_X, NODE_FLAG_BILLBOARDED_LOCK_Y,
+    NODE_FLAG_BILLBOARDED_LOCK_Z, NODE_FLAG_CAMERA_ANCHORED, NODE_FLAG_DONT_INHERIT_ROTATION,
+    NODE_FLAG_DONT_INHERIT_SCALING, NODE_FLAG_DONT_INHERIT_TRANSLATION,
+};
+use crate::verbose;
 use nalgebra_glm as glm;
 
 /// Main animation system
 pub struct AnimationSystem {
     pub bones: Vec<BoneState>,
     pub helpers: Vec<BoneState>,
+    pub attachments: Vec<BoneState>, // Children of bones/helpers, never parents
+    pub particle_emitters: Vec<ParticleEmitterState>, // PREM, children of bones/helpers too
+    pub emitters: Vec<EmitterState>, // PRE2, children of bones/helpers too
+    pub ribbons: Vec<RibbonState>,   // RIBB, children of bones/helpers too
+    pub lights: Vec<LightState>,     // LITE, children of bones/helpers too
+    pub cameras: Vec<ModelCameraState>, // CAMS, not part of the hierarchy
+    pub collision_shapes: Vec<BoneState>, // CLID, children of bones/helpers too
     pub controllers: Vec<Controller>,
     pub pivot_points: Vec<glm::Vec3>,
+    pub global_sequences: Vec<u32>, // Durations in ms (GLBS)
+    pub texture_anims: Vec<TextureAnim>,
+    pub geoset_anims: Vec<GeosetAnim>,
+    pub layers: Vec<Vec<LayerState>>, // Per material, per layer
     current_frame: f32,
+    global_frames: Vec<i32>, // Current frame of each global sequence
+    // Bones and helpers (indexed like `BoneState::parent`) the emitters hang from,
+    // directly or not, which is all the emitter simulations need to step
+    emitter_chain: Vec<usize>,
 }
 
 impl AnimationSystem {
@@ -17,17 +42,85 @@ impl AnimationSystem {
         Self {
             bones: Vec::new(),
             helpers: Vec::new(),
+            attachments: Vec::new(),
+            particle_emitters: Vec::new(),
+            emitters: Vec::new(),
+            ribbons: Vec::new(),
+            lights: Vec::new(),
+            cameras: Vec::new(),
+            collision_shapes: Vec::new(),
             controllers: Vec::new(),
             pivot_points: Vec::new(),
+            global_sequences: Vec::new(),
+            texture_anims: Vec::new(),
+            geoset_anims: Vec::new(),
+            layers: Vec::new(),
             current_frame: 0.0,
+            global_frames: Vec::new(),
+            emitter_chain: Vec::new(),
         }
     }
 
     /// Update animation to specific frame
     /// Based on CalcAnimCoords procedure (mdlDraw.pas line 2310)
-    pub fn update(&mut self, frame: f32) {
+    /// `time` is wall-clock time in seconds: global sequences loop on it
+    /// independently of `frame`, which belongs to the selected sequence.
+    /// `camera_orientation` is the viewer's (yaw, pitch), billboards turn towards it
+    pub fn update(&mut self, frame: f32, time: f64, camera_orientation: (f32, f32)) {
         self.current_frame = frame;
         let frame_int = frame as i32;
+        let camera_dir = camera_direction(camera_orientation);
+        self.update_global_frames(time);
+
+        for anim in &mut self.geoset_anims {
+            if !anim.is_alpha_static {
+                let data = get_frame_data(
+                    &self.controllers,
+                    anim.alpha_graph,
+                    frame_int,
+                    &self.global_frames,
+                );
+                anim.alpha = data[0];
+            }
+            if !anim.is_color_static {
+                let data = get_frame_data(
+                    &self.controllers,
+                    anim.color_graph,
+                    frame_int,
+                    &self.global_frames,
+                );
+                anim.color = bgr_to_rgb(&data);
+            }
+        }
+
+        for anim in &mut self.texture_anims {
+            interp_texture_anim(anim, frame_int, &self.global_frames, &self.controllers);
+        }
+
+        // Material layers: flipbook texture, alpha and UV transform
+        for layer in self.layers.iter_mut().flatten() {
+            if layer.texture_id_idx >= 0 {
+                let data = get_frame_data(
+                    &self.controllers,
+                    layer.texture_id_idx,
+                    frame_int,
+                    &self.global_frames,
+                );
+                layer.texture_id = Some(data[0].max(0.0) as usize);
+            }
+            if layer.alpha_idx >= 0 {
+                let data = get_frame_data(
+                    &self.controllers,
+                    layer.alpha_idx,
+                    frame_int,
+                    &self.global_frames,
+                );
+                layer.alpha = data[0];
+            }
+            if let Some(anim) = layer.texture_anim.and_then(|id| self.texture_anims.get(id)) {
+                layer.uv_transform = anim.uv_transform;
+            }
+        }
 
         // Reset all "IsReady" flags
         for bone in &mut self.bones {
@@ -43,16 +136,20 @@ impl AnimationSystem {
             interp_bone(
                 &mut self.helpers[i],
                 frame_int,
+                &self.global_frames,
                 &self.controllers,
                 &self.pivot_points,
+                &camera_dir,
             );
         }
         for i in 0..self.bones.len() {
             interp_bone(
                 &mut self.bones[i],
                 frame_int,
+                &self.global_frames,
                 &self.controllers,
                 &self.pivot_points,
+                &camera_dir,
             );
         }
 
@@ -77,9 +174,263 @@ impl AnimationSystem {
                 frame_int,
             );
         }
+
+        // The other nodes hang from the (now ready) bones and helpers
+        let nodes = NodeFrame {
+            frame: frame_int,
+            camera_dir,
+            bones: &self.bones,
+            helpers: &self.helpers,
+            controllers: &self.controllers,
+            global_frames: &self.global_frames,
+            pivot_points: &self.pivot_points,
+        };
+
+        // c. Attachment points
+        for attachment in &mut self.attachments {
+            nodes.animate_node(attachment);
+        }
+
+        // d, e. Particle and ribbon emitters, with their emission tracks
+        animate_emitters(
+            &nodes,
+            &mut self.particle_emitters,
+            &mut self.emitters,
+            &mut self.ribbons,
+        );
+
+        // f. Lights, with their color and intensity tracks
+        for light in &mut self.lights {
+            nodes.animate_node(&mut light.node);
+            for (idx, field) in [
+                (light.attenuation_start_idx, &mut light.attenuation_start),
+                (light.attenuation_end_idx, &mut light.attenuation_end),
+                (light.intensity_idx, &mut light.intensity),
+                (light.amb_intensity_idx, &mut light.amb_intensity),
+            ] {
+                if idx >= 0 {
+                    *field = nodes.value(idx)[0];
+                }
+            }
+            for (idx, color) in [
+                (light.color_idx, &mut light.color),
+                (light.amb_color_idx, &mut light.amb_color),
+            ] {
+                if idx >= 0 {
+                    *color = bgr_to_rgb(&nodes.value(idx));
+                }
+            }
+        }
+
+        // g. Cameras, moved from their static place by their own tracks
+        for camera in &mut self.cameras {
+            camera.position = camera.static_position;
+            if camera.translation_idx >= 0 {
+                let data = nodes.value(camera.translation_idx);
+                camera.position += glm::vec3(data[0], data[1], data[2]);
+            }
+            camera.target = camera.static_target;
+            if camera.target_translation_idx >= 0 {
+                let data = nodes.value(camera.target_translation_idx);
+                camera.target += glm::vec3(data[0], data[1], data[2]);
+            }
+            if camera.rotation_idx >= 0 {
+                camera.roll = nodes.value(camera.rotation_idx)[0];
+            }
+        }
+
+        // h. Collision shapes
+        for shape in &mut self.collision_shapes {
+            nodes.animate_node(shape);
+        }
+    }
+}
+
+/// What the nodes hanging from bones and helpers are animated with at one frame,
+/// borrowed from the animation system once its bones and helpers are ready
+struct NodeFrame<'a> {
+    frame: i32,
+    camera_dir: glm::Vec3,
+    bones: &'a [BoneState],
+    helpers: &'a [BoneState],
+    controllers: &'a [Controller],
+    global_frames: &'a [i32],
+    pivot_points: &'a [glm::Vec3],
+}
+
+impl NodeFrame<'_> {
+    /// Interpolates `node` and places it under its parent bone or helper
+    fn animate_node(&self, node: &mut BoneState) {
+        interp_bone(
+            node,
+            self.frame,
+            self.global_frames,
+            self.controllers,
+            self.pivot_points,
+            &self.camera_dir,
+        );
+        let parent = usize::try_from(node.parent).ok().and_then(|idx| {
+            self.bones
+                .get(idx)
+                .or_else(|| self.helpers.get(idx.checked_sub(self.bones.len())?))
+        });
+        if let Some(parent) = parent {
+            calc_absolute(parent, node, self.pivot_points);
+        }
+        node.is_ready = true;
+    }
+
+    /// Value of a track at the frame
+    fn value(&self, controller_idx: i32) -> Vec<f32> {
+        get_frame_data(
+            self.controllers,
+            controller_idx,
+            self.frame,
+            self.global_frames,
+        )
+    }
+}
+
+/// Places the PREM, PRE2 and ribbon emitters and reads their emission tracks
+fn animate_emitters(
+    nodes: &NodeFrame,
+    particle_emitters: &mut [ParticleEmitterState],
+    emitters: &mut [EmitterState],
+    ribbons: &mut [RibbonState],
+) {
+    for emitter in particle_emitters {
+        nodes.animate_node(&mut emitter.node);
+        for (idx, value) in [
+            (emitter.emission_rate_idx, &mut emitter.emission_rate),
+            (emitter.gravity_idx, &mut emitter.gravity),
+            (emitter.longitude_idx, &mut emitter.longitude),
+            (emitter.latitude_idx, &mut emitter.latitude),
+            (emitter.lifespan_idx, &mut emitter.lifespan),
+            (emitter.speed_idx, &mut emitter.speed),
+        ] {
+            if idx >= 0 {
+                *value = nodes.value(idx)[0];
+            }
+        }
+    }
+
+    for emitter in emitters {
+        nodes.animate_node(&mut emitter.node);
+        for (idx, value) in [
+            (emitter.speed_idx, &mut emitter.speed),
+            (emitter.variation_idx, &mut emitter.variation),
+            (emitter.latitude_idx, &mut emitter.latitude),
+            (emitter.gravity_idx, &mut emitter.gravity),
+            (emitter.emission_rate_idx, &mut emitter.emission_rate),
+            (emitter.length_idx, &mut emitter.length),
+            (emitter.width_idx, &mut emitter.width),
+        ] {
+            if idx >= 0 {
+                *value = nodes.value(idx)[0];
+            }
+        }
+    }
+
+    for ribbon in ribbons {
+        nodes.animate_node(&mut ribbon.node);
+        if ribbon.height_above_idx >= 0 {
+            ribbon.height_above = nodes.value(ribbon.height_above_idx)[0];
+        }
+        if ribbon.height_below_idx >= 0 {
+            ribbon.height_below = nodes.value(ribbon.height_below_idx)[0];
+        }
+        if ribbon.alpha_idx >= 0 {
+            ribbon.alpha = nodes.value(ribbon.alpha_idx)[0];
+        }
+        if ribbon.color_idx >= 0 {
+            ribbon.color = bgr_to_rgb(&nodes.value(ribbon.color_idx));
+        }
+        if ribbon.texture_slot_idx >= 0 {
+            ribbon.texture_slot = nodes.value(ribbon.texture_slot_idx)[0].max(0.0) as u32;
+        }
+    }
+}
+
+impl AnimationSystem {
+    /// Moves the particle and ribbon emitters to `frame` along with the bones and helpers
+    /// they hang from, leaving the rest of the model where it was. Much cheaper than
+    /// `update` for stepping the emitter simulations through a sequence
+    pub fn update_emitters(&mut self, frame: f32, time: f64, camera_orientation: (f32, f32)) {
+        let frame_int = frame as i32;
+        let camera_dir = camera_direction(camera_orientation);
+        self.update_global_frames(time);
+
+        let bone_count = self.bones.len();
+        for &idx in &self.emitter_chain {
+            let node = match idx.checked_sub(bone_count) {
+                None => &mut self.bones[idx],
+                Some(helper) => &mut self.helpers[helper],
+            };
+            node.is_ready = false;
+            interp_bone(
+                node,
+                frame_int,
+                &self.global_frames,
+                &self.controllers,
+                &self.pivot_points,
+                &camera_dir,
+            );
+        }
+        for &idx in &self.emitter_chain {
+            calc_bone(
+                idx,
+                &mut self.bones,
+                &mut self.helpers,
+                &self.controllers,
+                &self.pivot_points,
+                frame_int,
+            );
+        }
+
+        let nodes = NodeFrame {
+            frame: frame_int,
+            camera_dir,
+            bones: &self.bones,
+            helpers: &self.helpers,
+            controllers: &self.controllers,
+            global_frames: &self.global_frames,
+            pivot_points: &self.pivot_points,
+        };
+        animate_emitters(
+            &nodes,
+            &mut self.particle_emitters,
+            &mut self.emitters,
+            &mut self.ribbons,
+        );
+    }
+
+    /// Global sequences loop on the wall-clock `time`, in seconds
+    fn update_global_frames(&mut self, time: f64) {
+        let time_ms = (time * 1000.0) as u64;
+        self.global_frames = self
+            .global_sequences
+            .iter()
+            .map(|&duration| {
+                if duration == 0 {
+                    0
+                } else {
+                    (time_ms % duration as u64) as i32
+                }
+            })
+            .collect();
     }
 }
 
+/// Direction towards the camera in model space: the shader mirrors Y
+/// between model and world space, so the yaw turns the other way
+fn camera_direction((yaw, pitch): (f32, f32)) -> glm::Vec3 {
+    glm::vec3(
+        yaw.cos() * pitch.cos(),
+        -yaw.sin() * pitch.cos(),
+        pitch.sin(),
+    )
+}
+
 impl Default for AnimationSystem {
     fn default() -> Self {
         Self::new()
@@ -90,13 +441,21 @@ impl AnimationSystem {
     /// Initialize animation system from model
     /// Parse bones, helpers, pivot points, and controllers from model data
     pub fn init_from_model(&mut self, model: &Model) {
-        use std::collections::HashMap;
+        use std::collections::{BTreeSet, HashMap};
 
         // Clear existing data
         self.bones.clear();
         self.helpers.clear();
+        self.attachments.clear();
+        self.particle_emitters.clear();
+        self.emitters.clear();
+        self.ribbons.clear();
+        self.lights.clear();
+        self.cameras.clear();
+        self.collision_shapes.clear();
         self.controllers.clear();
         self.pivot_points.clear();
+        self.global_sequences = model.global_sequences.clone();
 
         // Create ObjectID -> Index mapping
         // This is critical because parent_id is an ObjectID, not an array index
@@ -112,21 +471,10 @@ impl AnimationSystem {
             object_id_to_index.insert(helper.object_id as i32, model.bones.len() + idx);
         }
 
-        // Load pivot points from model
-        for bone in &model.bones {
-            self.pivot_points.push(glm::vec3(
-                bone.pivot_point[0],
-                bone.pivot_point[1],
-                bone.pivot_point[2],
-            ));
-        }
-
-        for helper in &model.helpers {
-            self.pivot_points.push(glm::vec3(
-                helper.pivot_point[0],
-                helper.pivot_point[1],
-                helper.pivot_point[2],
-            ));
+        // Load pivot points from model, indexed by ObjectID like in PIVT
+        for pivot in &model.pivot_points {
+            self.pivot_points
+                .push(glm::vec3(pivot[0], pivot[1], pivot[2]));
         }
 
         // Load controllers from model
@@ -157,6 +505,69 @@ impl AnimationSystem {
             self.controllers.push(controller);
         }
 
+        self.texture_anims = model
+            .texture_animations
+            .iter()
+            .map(|anim| TextureAnim {
+                translation_graph: anim.translation_idx,
+                rotation_graph: anim.rotation_idx,
+                scaling_graph: anim.scaling_idx,
+                ..Default::default()
+            })
+            .collect();
+
+        self.geoset_anims = model
+            .geoset_animations
+            .iter()
+            .map(|anim| {
+                // Color is only used with its flag
+                let color = if anim.flags & GEOSET_ANIM_COLOR != 0 || anim.color_idx >= 0 {
+                    bgr_to_rgb(&anim.color)
+                } else {
+                    glm::vec3(1.0, 1.0, 1.0)
+                };
+                GeosetAnim {
+                    geoset_id: anim.geoset_id as i32,
+                    alpha: anim.alpha,
+                    is_alpha_static: anim.alpha_idx < 0,
+                    alpha_graph: anim.alpha_idx,
+                    color,
+                    is_color_static: anim.color_idx < 0,
+                    color_graph: anim.color_idx,
+                }
+            })
+            .collect();
+
+        // Layer states start at the static values
+        self.layers = model
+            .materials
+            .iter()
+            .map(|material| {
+                material
+                    .layers
+                    .iter()
+                    .map(|layer| LayerState {
+                        texture_id_idx: layer.texture_id_idx,
+                        alpha_idx: layer.alpha_idx,
+                        texture_anim: layer.texture_animation_id,
+                        texture_id: layer.texture_id,
+                        alpha: layer.alpha,
+                        uv_transform: glm::identity(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Texture IDs switch from one key to the next, they are never blended
+        for layer in self.layers.iter().flatten() {
+            if let Some(controller) = usize::try_from(layer.texture_id_idx)
+                .ok()
+                .and_then(|idx| self.controllers.get_mut(idx))
+            {
+                controller.cont_type = ControllerType::DontInterp;
+            }
+        }
+
         // Create BoneState for each bone
         for bone in &model.bones {
             let mut bone_state = BoneState::new(bone.name.clone(), bone.object_id as i32);
@@ -175,6 +586,7 @@ impl AnimationSystem {
             bone_state.rotation_idx = bone.rotation_idx;
             bone_state.scaling_idx = bone.scaling_idx;
             bone_state.visibility_idx = bone.visibility_idx;
+            apply_node_flags(&mut bone_state, bone.flags);
             self.bones.push(bone_state);
         }
 
@@ -196,15 +608,216 @@ impl AnimationSystem {
             helper_state.rotation_idx = helper.rotation_idx;
             helper_state.scaling_idx = helper.scaling_idx;
             helper_state.visibility_idx = helper.visibility_idx;
+            apply_node_flags(&mut helper_state, helper.flags);
             self.helpers.push(helper_state);
         }
 
-        println!(
-            "Animation system initialized: {} bones, {} helpers, {} pivot points, {} controllers",
+        // Create BoneState for each attachment point
+        for attachment in &model.attachments {
+            let mut attachment_state =
+                BoneState::new(attachment.name.clone(), attachment.object_id as i32);
+            attachment_state.parent = object_id_to_index
+                .get(&attachment.parent_id)
+                .map_or(-1, |&idx| idx as i32);
+            attachment_state.translation_idx = attachment.translation_idx;
+            attachment_state.rotation_idx = attachment.rotation_idx;
+            attachment_state.scaling_idx = attachment.scaling_idx;
+            attachment_state.visibility_idx = attachment.visibility_idx;
+            apply_node_flags(&mut attachment_state, attachment.flags);
+            self.attachments.push(attachment_state);
+        }
+
+        // Create ParticleEmitterState for each PREM emitter
+        for emitter in &model.particle_emitters {
+            let mut node = BoneState::new(emitter.name.clone(), emitter.object_id as i32);
+            node.parent = object_id_to_index
+                .get(&emitter.parent_id)
+                .map_or(-1, |&idx| idx as i32);
+            node.translation_idx = emitter.translation_idx;
+            node.rotation_idx = emitter.rotation_idx;
+            node.scaling_idx = emitter.scaling_idx;
+            node.visibility_idx = emitter.visibility_idx;
+            apply_node_flags(&mut node, emitter.flags);
+            self.particle_emitters.push(ParticleEmitterState {
+                node,
+                emission_rate_idx: emitter.emission_rate_idx,
+                gravity_idx: emitter.gravity_idx,
+                longitude_idx: emitter.longitude_idx,
+                latitude_idx: emitter.latitude_idx,
+                lifespan_idx: emitter.lifespan_idx,
+                speed_idx: emitter.speed_idx,
+                emission_rate: emitter.emission_rate,
+                gravity: emitter.gravity,
+                longitude: emitter.longitude,
+                latitude: emitter.latitude,
+                lifespan: emitter.lifespan,
+                speed: emitter.speed,
+            });
+        }
+
+        // Create EmitterState for each particle emitter
+        for emitter in &model.particle_emitters2 {
+            let mut node = BoneState::new(emitter.name.clone(), emitter.object_id as i32);
+            node.parent = object_id_to_index
+                .get(&emitter.parent_id)
+                .map_or(-1, |&idx| idx as i32);
+            node.translation_idx = emitter.translation_idx;
+            node.rotation_idx = emitter.rotation_idx;
+            node.scaling_idx = emitter.scaling_idx;
+            node.visibility_idx = emitter.visibility_idx;
+            apply_node_flags(&mut node, emitter.flags);
+            self.emitters.push(EmitterState {
+                node,
+                speed_idx: emitter.speed_idx,
+                variation_idx: emitter.variation_idx,
+                latitude_idx: emitter.latitude_idx,
+                gravity_idx: emitter.gravity_idx,
+                emission_rate_idx: emitter.emission_rate_idx,
+                length_idx: emitter.length_idx,
+                width_idx: emitter.width_idx,
+                speed: emitter.speed,
+                variation: emitter.variation,
+                latitude: emitter.latitude,
+                gravity: emitter.gravity,
+                emission_rate: emitter.emission_rate,
+                length: emitter.length,
+                width: emitter.width,
+            });
+        }
+
+        // Create RibbonState for each ribbon emitter
+        for ribbon in &model.ribbon_emitters {
+            let mut node = BoneState::new(ribbon.name.clone(), ribbon.object_id as i32);
+            node.parent = object_id_to_index
+                .get(&ribbon.parent_id)
+                .map_or(-1, |&idx| idx as i32);
+            node.translation_idx = ribbon.translation_idx;
+            node.rotation_idx = ribbon.rotation_idx;
+            node.scaling_idx = ribbon.scaling_idx;
+            node.visibility_idx = ribbon.visibility_idx;
+            apply_node_flags(&mut node, ribbon.flags);
+            self.ribbons.push(RibbonState {
+                node,
+                height_above_idx: ribbon.height_above_idx,
+                height_below_idx: ribbon.height_below_idx,
+                alpha_idx: ribbon.alpha_idx,
+                color_idx: ribbon.color_idx,
+                texture_slot_idx: ribbon.texture_slot_idx,
+                height_above: ribbon.height_above,
+                height_below: ribbon.height_below,
+                alpha: ribbon.alpha,
+                color: bgr_to_rgb(&ribbon.color),
+                texture_slot: ribbon.texture_slot,
+            });
+        }
+
+        // Create LightState for each light
+        for light in &model.lights {
+            let mut node = BoneState::new(light.name.clone(), light.object_id as i32);
+            node.parent = object_id_to_index
+                .get(&light.parent_id)
+                .map_or(-1, |&idx| idx as i32);
+            node.translation_idx = light.translation_idx;
+            node.rotation_idx = light.rotation_idx;
+            node.scaling_idx = light.scaling_idx;
+            node.visibility_idx = light.visibility_idx;
+            apply_node_flags(&mut node, light.flags);
+            self.lights.push(LightState {
+                node,
+                attenuation_start_idx: light.attenuation_start_idx,
+                attenuation_end_idx: light.attenuation_end_idx,
+                color_idx: light.color_idx,
+                intensity_idx: light.intensity_idx,
+                amb_intensity_idx: light.amb_intensity_idx,
+                amb_color_idx: light.amb_color_idx,
+                attenuation_start: light.attenuation_start,
+                attenuation_end: light.attenuation_end,
+                color: bgr_to_rgb(&light.color),
+                intensity: light.intensity,
+                amb_color: bgr_to_rgb(&light.amb_color),
+                amb_intensity: light.amb_intensity,
+            });
+        }
+
+        // Create ModelCameraState for each camera
+        self.cameras = model
+            .cameras
+            .iter()
+            .map(|camera| ModelCameraState {
+                translation_idx: camera.translation_idx,
+                rotation_idx: camera.rotation_idx,
+                target_translation_idx: camera.target_translation_idx,
+                static_position: glm::make_vec3(&camera.position),
+                static_target: glm::make_vec3(&camera.target_position),
+                position: glm::make_vec3(&camera.position),
+                target: glm::make_vec3(&camera.target_position),
+                roll: 0.0,
+            })
+            .collect();
+
+        // Create BoneState for each collision shape
+        for shape in &model.collision_shapes {
+            let mut shape_state = BoneState::new(shape.name.clone(), shape.object_id as i32);
+            shape_state.parent = object_id_to_index
+                .get(&shape.parent_id)
+                .map_or(-1, |&idx| idx as i32);
+            shape_state.translation_idx = shape.translation_idx;
+            shape_state.rotation_idx = shape.rotation_idx;
+            shape_state.scaling_idx = shape.scaling_idx;
+            apply_node_flags(&mut shape_state, shape.flags);
+            self.collision_shapes.push(shape_state);
+        }
+
+        let parents = self
+            .particle_emitters
+            .iter()
+            .map(|emitter| emitter.node.parent)
+            .chain(self.emitters.iter().map(|emitter| emitter.node.parent))
+            .chain(self.ribbons.iter().map(|ribbon| ribbon.node.parent));
+        let mut chain = BTreeSet::new();
+        for mut parent in parents {
+            while let Ok(idx) = usize::try_from(parent) {
+                let Some(node) = self
+                    .bones
+                    .get(idx)
+                    .or_else(|| self.helpers.get(idx.checked_sub(self.bones.len())?))
+                else {
+                    break;
+                };
+                if !chain.insert(idx) {
+                    break;
+                }
+                parent = node.parent;
+            }
+        }
+        self.emitter_chain = chain.into_iter().collect();
+
+        verbose!(
+            "Animation system initialized: {} bones, {} helpers, {} attachments, {} particle emitters, {} particle emitters 2, {} ribbon emitters, {} lights, {} cameras, {} collision shapes, {} pivot points, {} controllers, {} global sequences",
             self.bones.len(),
             self.helpers.len(),
+            self.attachments.len(),
+            self.particle_emitters.len(),
+            self.emitters.len(),
+            self.ribbons.len(),
+            self.lights.len(),
+            self.cameras.len(),
+            self.collision_shapes.len(),
             self.pivot_points.len(),
-            self.controllers.len()
+            self.controllers.len(),
+            self.global_sequences.len()
         );
     }
 }
+
+/// Copies the billboard and inheritance node flags into the bone state
+fn apply_node_flags(state: &mut BoneState, flags: u32) {
+    state.dont_inherit_translation = flags & NODE_FLAG_DONT_INHERIT_TRANSLATION != 0;
+    state.dont_inherit_rotation = flags & NODE_FLAG_DONT_INHERIT_ROTATION != 0;
+    state.dont_inherit_scaling = flags & NODE_FLAG_DONT_INHERIT_SCALING != 0;
+    state.is_billboarded = flags & NODE_FLAG_BILLBOARDED != 0;
+    state.billboard_lock_x = flags & NODE_FLAG_BILLBOARDED_LOCK_X != 0;
+    state.billboard_lock_y = flags & NODE_FLAG_BILLBOARDED_LOCK_Y != 0;
+    state.billboard_lock_z = flags & NODE_FLAG_BILLBOARDED_LOCK_Z != 0;
+    state.camera_anchored = flags & NODE_FLAG_CAMERA_ANCHORED != 0;
+}
@@ -0,0 +1,56 @@
+// Texture (UV) animation
+// Based on TTextureAnim from mdlwork.pas
+
+use super::controller::get_frame_data;
+use super::types::*;
+use nalgebra_glm as glm;
+
+/// Interpolate texture animation to the given frame and build its UV transform:
+/// scaling and rotation around the texture center, then translation
+pub fn interp_texture_anim(
+    anim: &mut TextureAnim,
+    frame: i32,
+    global_frames: &[i32],
+    controllers: &[Controller],
+) {
+    let translation = if anim.translation_graph < 0 {
+        glm::vec3(0.0, 0.0, 0.0)
+    } else {
+        let data = get_frame_data(controllers, anim.translation_graph, frame, global_frames);
+        glm::vec3(data[0], data[1], data[2])
+    };
+
+    let rotation = if anim.rotation_graph < 0 {
+        glm::quat_identity()
+    } else {
+        // Stored as x, y, z, w
+        let data = get_frame_data(controllers, anim.rotation_graph, frame, global_frames);
+        glm::quat_normalize(&glm::quat(data[0], data[1], data[2], data[3]))
+    };
+
+    let scaling = if anim.scaling_graph < 0 {
+        glm::vec3(1.0, 1.0, 1.0)
+    } else {
+        let data = get_frame_data(controllers, anim.scaling_graph, frame, global_frames);
+        glm::vec3(data[0], data[1], data[2])
+    };
+
+    let center = glm::vec3(0.5, 0.5, 0.0);
+    let matrix = glm::translation(&(translation + center))
+        * glm::quat_to_mat4(&rotation)
+        * glm::scaling(&scaling)
+        * glm::translation(&-center);
+
+    // Only the UV plane is kept: x/y columns and the translation
+    anim.uv_transform = glm::mat3(
+        matrix[(0, 0)],
+        matrix[(0, 1)],
+        matrix[(0, 3)],
+        matrix[(1, 0)],
+        matrix[(1, 1)],
+        matrix[(1, 3)],
+        0.0,
+        0.0,
+        1.0,
+    );
+}
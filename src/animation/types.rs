@@ -163,6 +163,11 @@ pub struct BoneState {
     pub billboard_lock_z: bool,
     pub camera_anchored: bool,
 
+    // Parts of the parent transformation that are not inherited
+    pub dont_inherit_translation: bool,
+    pub dont_inherit_rotation: bool,
+    pub dont_inherit_scaling: bool,
+
     // Current animated values (computed)
     pub is_ready: bool,            // True if already calculated this frame
     pub abs_quaternion: glm::Quat, // Absolute rotation quaternion
@@ -187,6 +192,9 @@ impl Default for BoneState {
             billboard_lock_y: false,
             billboard_lock_z: false,
             camera_anchored: false,
+            dont_inherit_translation: false,
+            dont_inherit_rotation: false,
+            dont_inherit_scaling: false,
             is_ready: false,
             abs_quaternion: glm::quat_identity(),
             abs_matrix: glm::identity(),
@@ -205,16 +213,42 @@ impl BoneState {
             ..Default::default()
         }
     }
+
+    /// True if the node turns towards the camera instead of following its parent's rotation
+    pub fn faces_camera(&self) -> bool {
+        self.is_billboarded
+            || self.billboard_lock_x
+            || self.billboard_lock_y
+            || self.billboard_lock_z
+            || self.camera_anchored
+    }
+}
+
+/// Animated state of a material layer
+#[derive(Debug, Clone)]
+pub struct LayerState {
+    // Controller indices (-1 if no animation)
+    pub texture_id_idx: i32,
+    pub alpha_idx: i32,
+
+    pub texture_anim: Option<usize>, // Index into the texture animations
+
+    // Current animated values (computed), the static ones when not animated
+    pub texture_id: Option<usize>,
+    pub alpha: f32,
+    pub uv_transform: glm::Mat3, // From the texture animation, identity without one
 }
 
 /// Texture animation data
 /// From TTextureAnim in mdlwork.pas
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct TextureAnim {
     pub translation_graph: i32,
     pub rotation_graph: i32,
     pub scaling_graph: i32,
+
+    // Current UV transform (computed)
+    pub uv_transform: glm::Mat3,
 }
 
 impl Default for TextureAnim {
@@ -223,20 +257,21 @@ impl Default for TextureAnim {
             translation_graph: -1,
             rotation_graph: -1,
             scaling_graph: -1,
+            uv_transform: glm::identity(),
         }
     }
 }
 
 /// Geoset animation (color/alpha animation for geosets)
 /// From mdlwork.pas GeosetAnims
+/// `alpha` and `color` hold the current values (computed) of animated ones
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct GeosetAnim {
     pub geoset_id: i32,
     pub alpha: f32,
     pub is_alpha_static: bool,
     pub alpha_graph: i32,
-    pub color: glm::Vec3,
+    pub color: glm::Vec3, // RGB
     pub is_color_static: bool,
     pub color_graph: i32,
 }
@@ -254,3 +289,112 @@ impl Default for GeosetAnim {
         }
     }
 }
+
+/// Animated state of a particle emitter (PRE2)
+/// `node` follows the emitter node, the other values are the current ones (computed),
+/// the static ones when not animated
+#[derive(Debug, Clone)]
+pub struct EmitterState {
+    pub node: BoneState,
+
+    // Controller indices (-1 if no animation)
+    pub speed_idx: i32,
+    pub variation_idx: i32,
+    pub latitude_idx: i32,
+    pub gravity_idx: i32,
+    pub emission_rate_idx: i32,
+    pub length_idx: i32,
+    pub width_idx: i32,
+
+    pub speed: f32,
+    pub variation: f32,
+    pub latitude: f32, // Degrees
+    pub gravity: f32,
+    pub emission_rate: f32,
+    pub length: f32,
+    pub width: f32,
+}
+
+/// Animated state of a particle emitter (PREM)
+/// `node` follows the emitter node, the other values are the current ones (computed),
+/// the static ones when not animated
+#[derive(Debug, Clone)]
+pub struct ParticleEmitterState {
+    pub node: BoneState,
+
+    // Controller indices (-1 if no animation)
+    pub emission_rate_idx: i32,
+    pub gravity_idx: i32,
+    pub longitude_idx: i32,
+    pub latitude_idx: i32,
+    pub lifespan_idx: i32,
+    pub speed_idx: i32,
+
+    pub emission_rate: f32,
+    pub gravity: f32,
+    pub longitude: f32, // Radians
+    pub latitude: f32,  // Radians
+    pub lifespan: f32,
+    pub speed: f32,
+}
+
+/// Animated state of a ribbon emitter (RIBB)
+/// `node` follows the emitter node, the other values are the current ones (computed),
+/// the static ones when not animated
+#[derive(Debug, Clone)]
+pub struct RibbonState {
+    pub node: BoneState,
+
+    // Controller indices (-1 if no animation)
+    pub height_above_idx: i32,
+    pub height_below_idx: i32,
+    pub alpha_idx: i32,
+    pub color_idx: i32,
+    pub texture_slot_idx: i32,
+
+    pub height_above: f32,
+    pub height_below: f32,
+    pub alpha: f32,
+    pub color: glm::Vec3, // RGB
+    pub texture_slot: u32,
+}
+
+/// Animated state of a light (LITE)
+/// `node` follows the light node, the other values are the current ones (computed),
+/// the static ones when not animated
+#[derive(Debug, Clone)]
+pub struct LightState {
+    pub node: BoneState,
+
+    // Controller indices (-1 if no animation)
+    pub attenuation_start_idx: i32,
+    pub attenuation_end_idx: i32,
+    pub color_idx: i32,
+    pub intensity_idx: i32,
+    pub amb_intensity_idx: i32,
+    pub amb_color_idx: i32,
+
+    pub attenuation_start: f32,
+    pub attenuation_end: f32,
+    pub color: glm::Vec3, // RGB
+    pub intensity: f32,
+    pub amb_color: glm::Vec3, // RGB
+    pub amb_intensity: f32,
+}
+
+/// Animated state of a model camera (CAMS), in model space
+/// Cameras are not nodes: `position` and `target` are the static ones moved by their
+/// translation tracks
+#[derive(Debug, Clone)]
+pub struct ModelCameraState {
+    // Controller indices (-1 if no animation)
+    pub translation_idx: i32,
+    pub rotation_idx: i32,
+    pub target_translation_idx: i32,
+
+    pub static_position: glm::Vec3,
+    pub static_target: glm::Vec3,
+    pub position: glm::Vec3,
+    pub target: glm::Vec3,
+    pub roll: f32, // Radians around the view direction
+}
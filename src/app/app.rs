@@ -1,8 +1,17 @@
+use crate::animation::AnimationSystem;
 use crate::error::MdlError;
+use crate::model::emitter::{PARTICLE_FLAG_EMITTER_USES_MDL, PARTICLE_FLAG_EMITTER_USES_TGA};
+use crate::model::model::Model;
 use crate::parser::load::load;
-use crate::texture::loader::{TextureLoadResult, load_texture};
+use crate::parser::mdl::load_mdl;
+use crate::renderer::camera::CameraState;
+use crate::renderer::spawns::{SpawnSource, SpawnedModel};
+use crate::texture::loader::{TextureLoadResult, load_remote_model, load_texture};
 use crate::texture::manager::TextureStatus;
+use crate::writer::mdl::save_mdl;
+use crate::writer::save::save;
 use egui_wgpu::ScreenDescriptor;
+use nalgebra_glm as glm;
 use std::fs::File;
 
 /// Temporary helper to access the global AppHandler registered in `handler_registry`.
@@ -216,6 +225,7 @@ impl App {
         let mut show_geosets = Vec::new();
         let mut colors_changed = false;
         let mut open_model = false;
+        let mut save_model = false;
         let mut texture_load_requests: Vec<usize> = Vec::new();
         let mut use_animation = false;
 
@@ -226,6 +236,7 @@ impl App {
                 show_geosets_ui,
                 colors_changed_ui,
                 open_model_ui,
+                save_model_ui,
                 use_animation_ui,
             ) = handler.ui.show(
                 ctx,
@@ -241,6 +252,7 @@ impl App {
             show_geosets = show_geosets_ui;
             colors_changed = colors_changed_ui;
             open_model = open_model_ui;
+            save_model = save_model_ui;
             use_animation = use_animation_ui;
 
             // Show texture panel
@@ -265,7 +277,9 @@ impl App {
         // Handle Open Model button
         if open_model {
             if let Some(path) = rfd::FileDialog::new()
+                .add_filter("Warcraft III Model", &["mdx", "mdl"])
                 .add_filter("MDX Model", &["mdx"])
+                .add_filter("MDL Model", &["mdl"])
                 .pick_file()
             {
                 if let Some(path_str) = path.to_str() {
@@ -274,6 +288,46 @@ impl App {
             }
         }
 
+        // Handle the attach and detach buttons of the Model Info window
+        if let Some(attachment) = handler.ui.take_attach_request()
+            && let Some(path) = rfd::FileDialog::new()
+                .add_filter("Warcraft III Model", &["mdx", "mdl"])
+                .pick_file()
+            && let Some(path_str) = path.to_str()
+        {
+            handler.pending_attachment = Some((attachment, path_str.to_string()));
+        }
+        if handler.ui.take_detach_request() {
+            handler.pending_detach = true;
+        }
+
+        // Handle Save Model button
+        if save_model
+            && let Some(model) = handler.model.as_ref()
+            && let Some(path) = rfd::FileDialog::new()
+                .add_filter("MDX Model", &["mdx"])
+                .add_filter("MDL Model", &["mdl"])
+                .save_file()
+        {
+            // Format follows the chosen extension, which also converts between MDX and MDL
+            let is_mdl = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("mdl"));
+            let result = File::create(&path)
+                .map_err(MdlError::from)
+                .and_then(|mut file| {
+                    if is_mdl {
+                        save_mdl(&mut file, model)
+                    } else {
+                        save(&mut file, model)
+                    }
+                });
+            match result {
+                Ok(()) => println!("Saved model: {}", path.display()),
+                Err(e) => eprintln!("Failed to save model '{}': {}", path.display(), e),
+            }
+        }
+
         // Handle reset camera button
         if reset_camera {
             handler.camera_controller.reset();
@@ -300,12 +354,18 @@ impl App {
         let show_skeleton = handler.settings.display.show_skeleton;
         let show_grid = handler.settings.display.show_grid;
         let show_bounding_box = handler.settings.display.show_bounding_box;
+        let show_sequence_extent = handler.settings.display.show_sequence_extent;
         let wireframe_mode = handler.settings.display.wireframe_mode;
         let far_plane = handler.settings.display.far_plane;
 
         // Update animation ONLY if use_animation flag is enabled
-        if use_animation && handler.model.is_some() && !handler.animation_system.bones.is_empty() {
-            handler.animation_system.update(current_frame);
+        // Models without bones still get their layer tracks animated
+        if use_animation && handler.model.is_some() {
+            handler.animation_system.update(
+                current_frame,
+                current_time,
+                (camera_yaw, camera_pitch),
+            );
             handler
                 .renderer
                 .as_mut()
@@ -320,14 +380,145 @@ impl App {
                 .reset_to_original_vertices();
         }
 
-        // Sync camera state to renderer
-        handler.renderer.as_mut().unwrap().camera = handler.camera_controller.state().clone();
+        // Rebuild the sequence extent box when another sequence gets selected
+        if show_sequence_extent && let Some(model) = handler.model.as_ref() {
+            let selected_sequence = handler.ui.selected_sequence();
+            let renderer = handler.renderer.as_mut().unwrap();
+            if renderer.sequence_extent_index != Some(selected_sequence) {
+                renderer.generate_sequence_extent_lines(
+                    model,
+                    selected_sequence,
+                    handler.settings.colors.sequence_extent_color,
+                );
+            }
+        }
+
+        // Sync camera state to renderer, or look through the selected model camera
+        let model_camera = handler.ui.camera_view().and_then(|idx| {
+            let model = handler.attached_model.as_ref().or(handler.model.as_ref())?;
+            model_camera_view(
+                model,
+                idx,
+                use_animation.then_some(&handler.animation_system),
+            )
+        });
+        handler.renderer.as_mut().unwrap().camera =
+            model_camera.unwrap_or_else(|| handler.camera_controller.state().clone());
+
+        // Attachment markers follow the animated nodes, so they are rebuilt every frame
+        let renderer = handler.renderer.as_mut().unwrap();
+        if handler.settings.display.show_attachments
+            && let Some(model) = handler.model.as_ref()
+        {
+            renderer.generate_attachment_markers(
+                model,
+                use_animation.then_some(&handler.animation_system),
+            );
+        } else {
+            renderer.clear_attachment_markers();
+        }
+
+        // Lights follow the animated nodes too, their gizmos only while they are shown
+        let shown_model = handler.attached_model.as_ref().or(handler.model.as_ref());
+        let renderer = handler.renderer.as_mut().unwrap();
+        let light_animation = use_animation.then_some(&handler.animation_system);
+        renderer.update_lights(shown_model, light_animation);
+        if handler.settings.display.show_lights
+            && let Some(model) = shown_model
+        {
+            renderer.generate_light_gizmos(model, light_animation);
+        } else {
+            renderer.clear_light_gizmos();
+        }
+
+        // Collision shapes are carried by their nodes as well
+        if handler.settings.display.show_collision_shapes
+            && let Some(model) = shown_model
+        {
+            renderer.generate_collision_shapes(
+                model,
+                use_animation.then_some(&handler.animation_system),
+            );
+        } else {
+            renderer.clear_collision_shapes();
+        }
+
+        // Particles are rebuilt from the timeline of the selected sequence every frame
+        let renderer = handler.renderer.as_mut().unwrap();
+        if use_animation
+            && handler.settings.display.show_particles
+            && let Some(model) = shown_model
+            && let Some(sequence) = model.sequences.get(handler.ui.selected_sequence())
+        {
+            let particles = handler.animation_system.simulate_particles(
+                model,
+                sequence.start_frame,
+                current_frame,
+                current_time,
+                (camera_yaw, camera_pitch),
+            );
+            renderer.generate_particles(model, &particles);
+        } else {
+            renderer.clear_particles();
+        }
+
+        // Ribbons too, from the same timeline
+        let renderer = handler.renderer.as_mut().unwrap();
+        if use_animation
+            && handler.settings.display.show_ribbons
+            && let Some(model) = shown_model
+            && let Some(sequence) = model.sequences.get(handler.ui.selected_sequence())
+        {
+            let ribbons = handler.animation_system.simulate_ribbons(
+                model,
+                sequence.start_frame,
+                current_frame,
+                current_time,
+                (camera_yaw, camera_pitch),
+            );
+            renderer.generate_ribbons(model, &ribbons);
+        } else {
+            renderer.clear_ribbons();
+        }
+
+        // PREM particles as well, with the models or textures they throw
+        let renderer = handler.renderer.as_mut().unwrap();
+        if use_animation
+            && handler.settings.display.show_particles
+            && let Some(model) = shown_model
+            && let Some(sequence) = model.sequences.get(handler.ui.selected_sequence())
+        {
+            let particles = handler.animation_system.simulate_spawned_particles(
+                model,
+                sequence.start_frame,
+                current_frame,
+                current_time,
+                (camera_yaw, camera_pitch),
+            );
+            renderer.generate_spawned_particles(
+                &mut handler.spawn_sources,
+                &particles,
+                current_time,
+                (camera_yaw, camera_pitch),
+            );
+        } else {
+            renderer.clear_spawned_particles();
+        }
+
+        // A preview model attached to the model is drawn along with it,
+        // keeping the material changes made to the model in the UI
+        if let Some(model) = handler.model.as_ref()
+            && let Some(attached) = handler.attached_model.as_mut()
+        {
+            attached.materials[..model.materials.len()].clone_from_slice(&model.materials);
+        }
 
         handler.renderer.as_mut().unwrap().render(
-            handler.model.as_ref(),
+            handler.attached_model.as_ref().or(handler.model.as_ref()),
             show_skeleton,
             show_grid,
             show_bounding_box,
+            show_sequence_extent,
             wireframe_mode,
             far_plane,
             &show_geosets,
@@ -342,21 +533,88 @@ impl App {
 
         let handler = get_global_handler_mut().unwrap();
 
-        let mut file = File::open(path)?;
-
-        let model = load(&mut file)?;
+        let model = read_model_file(path)?;
 
         // Initialize texture manager with model path and textures
         handler.model_path = Some(path.to_string());
         handler
             .texture_manager
             .set_model_path(std::path::Path::new(path));
-        handler.texture_manager.init_from_model(&model);
-        handler.renderer.as_mut().unwrap().update_model(&model);
+        self.show_model(&model).await;
+
+        // Frame the camera on the model extent
+        if let Some((center, radius)) = model.bounding_sphere() {
+            handler.camera_controller.frame(center, radius);
+        }
+
+        handler.model = Some(model);
+        handler.attached_model = None;
+        handler.ui.set_attached(None);
+
+        // Reset animation state for new model
+        println!("Resetting UI animation state...");
+        handler.ui.reset_animation(&handler.model);
+        println!("UI animation state reset");
+
+        println!("Model loaded successfully");
+
+        Ok(())
+    }
+
+    /// Hang the model at `path` from an attachment point of the loaded model
+    pub async fn attach_model(&mut self, path: &str, attachment: usize) -> Result<(), MdlError> {
+        println!("Attaching model: {}", path);
+
+        let handler = get_global_handler_mut().unwrap();
+
+        let other = read_model_file(path)?;
+        let Some(merged) = handler
+            .model
+            .as_ref()
+            .and_then(|model| model.with_attached(&other, attachment))
+        else {
+            return Ok(());
+        };
+
+        self.show_model(&merged).await;
+        handler.attached_model = Some(merged);
+        handler.ui.set_attached(Some(attachment));
+
+        println!("Model attached successfully");
+
+        Ok(())
+    }
+
+    /// Go back to showing the loaded model on its own
+    pub async fn detach_model(&mut self) {
+        let handler = get_global_handler_mut().unwrap();
+
+        if handler.attached_model.take().is_none() {
+            return;
+        }
+        if let Some(model) = handler.model.clone() {
+            self.show_model(&model).await;
+        }
+        handler.ui.set_attached(None);
+    }
+
+    /// Upload a model to the renderer, start loading its textures and set up its animation
+    async fn show_model(&mut self, model: &Model) {
+        let handler = get_global_handler_mut().unwrap();
+
+        handler.texture_manager.init_from_model(model);
+        handler.renderer.as_mut().unwrap().update_model(model);
+        self.load_spawn_sources(model).await;
 
         // First, create RID textures (they are generated, not loaded)
-        for (texture_id, texture) in model.textures.iter().enumerate() {
-            if texture.replaceable_id == 1 {
+        let replaceable_ids: Vec<u32> = handler
+            .texture_manager
+            .textures
+            .iter()
+            .map(|texture| texture.replaceable_id)
+            .collect();
+        for (texture_id, replaceable_id) in replaceable_ids.into_iter().enumerate() {
+            if replaceable_id == 1 {
                 // Team color (RID 1) - create solid color texture
                 println!("Creating team color texture for texture {}", texture_id);
                 handler
@@ -370,7 +628,7 @@ impl App {
                     info.width = 4;
                     info.height = 4;
                 }
-            } else if texture.replaceable_id == 2 {
+            } else if replaceable_id == 2 {
                 // Team glow (RID 2) - create 32x32 glow texture with alpha map
                 println!("Creating team glow texture for texture {}", texture_id);
                 handler
@@ -437,9 +695,15 @@ impl App {
 
         // Start background texture loading tasks for non-RID textures
         // BUT: Skip textures that were found locally (they're already loading via start_texture_load)
-        for (texture_id, texture) in model.textures.iter().enumerate() {
+        let textures: Vec<(u32, String)> = handler
+            .texture_manager
+            .textures
+            .iter()
+            .map(|texture| (texture.replaceable_id, texture.filename.clone()))
+            .collect();
+        for (texture_id, (replaceable_id, texture_path)) in textures.into_iter().enumerate() {
             // Skip all RID textures (replaceable_id > 0) - they are already created above
-            if texture.replaceable_id > 0 {
+            if replaceable_id > 0 {
                 continue;
             }
 
@@ -454,8 +718,6 @@ impl App {
                 }
             }
 
-            let texture_path = texture.filename.clone();
-
             if texture_path.is_empty() {
                 continue; // Skip if no texture to load
             }
@@ -483,20 +745,103 @@ impl App {
             });
         }
 
-        handler.model = Some(model.clone());
-
         // Initialize animation system with bones
         println!("Initializing animation system...");
-        handler.animation_system.init_from_model(&model);
+        handler.animation_system.init_from_model(model);
         println!("Animation system initialized");
+    }
 
-        // Reset animation state for new model
-        println!("Resetting UI animation state...");
-        handler.ui.reset_animation(&handler.model);
-        println!("UI animation state reset");
+    /// Load what the PREM emitters of a model throw, registering the textures after the
+    /// model ones. Models are looked up next to the model, then downloaded like textures
+    async fn load_spawn_sources(&mut self, model: &Model) {
+        let handler = get_global_handler_mut().unwrap();
 
-        println!("Model loaded successfully");
+        handler.spawn_sources.clear();
+        for emitter in &model.particle_emitters {
+            let source = if emitter.path.is_empty() {
+                None
+            } else if emitter.has_flag(PARTICLE_FLAG_EMITTER_USES_MDL) {
+                let spawned = match handler.texture_manager.find_local_model(&emitter.path) {
+                    Some(path) => read_model_file(&path.to_string_lossy()),
+                    None => load_remote_model(&emitter.path).await,
+                };
+                match spawned {
+                    Ok(spawned) => {
+                        let texture_offset = handler.texture_manager.textures.len();
+                        for texture in &spawned.textures {
+                            handler
+                                .texture_manager
+                                .add_texture(texture.filename.clone(), texture.replaceable_id);
+                        }
+                        let mut animation = AnimationSystem::new();
+                        animation.init_from_model(&spawned);
+                        Some(SpawnSource::Model(Box::new(SpawnedModel {
+                            model: spawned,
+                            animation,
+                            texture_offset,
+                        })))
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "[mdlvis-rs] Failed to load the model of particle emitter '{}' ({}): {}",
+                            emitter.name, emitter.path, e
+                        );
+                        None
+                    }
+                }
+            } else if emitter.has_flag(PARTICLE_FLAG_EMITTER_USES_TGA) {
+                // Shipped textures are BLP whatever the path says
+                let filename = std::path::Path::new(&emitter.path).with_extension("blp");
+                Some(SpawnSource::Texture(
+                    handler
+                        .texture_manager
+                        .add_texture(filename.to_string_lossy().into_owned(), 0),
+                ))
+            } else {
+                None
+            };
+            handler.spawn_sources.push(source);
+        }
+    }
+}
 
-        Ok(())
+/// View through camera `idx` of the model (CAMS), at its animated place when an
+/// animation system is given. The camera is mirrored in Y like the mesh
+fn model_camera_view(
+    model: &Model,
+    idx: usize,
+    animation_system: Option<&AnimationSystem>,
+) -> Option<CameraState> {
+    let camera = model.cameras.get(idx)?;
+    let (position, target, roll) = match animation_system.and_then(|system| system.cameras.get(idx))
+    {
+        Some(state) => (state.position, state.target, state.roll),
+        None => (
+            glm::make_vec3(&camera.position),
+            glm::make_vec3(&camera.target_position),
+            0.0,
+        ),
+    };
+    // Mirroring turns the roll the other way
+    Some(CameraState::look_through(
+        [position.x, -position.y, position.z],
+        [target.x, -target.y, target.z],
+        -roll,
+        camera.field_of_view,
+        (camera.near_clip, camera.far_clip),
+    ))
+}
+
+/// Read an MDX or MDL file, picked by its extension
+fn read_model_file(path: &str) -> Result<Model, MdlError> {
+    let mut file = File::open(path)?;
+
+    let is_mdl = std::path::Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mdl"));
+    if is_mdl {
+        load_mdl(&mut file)
+    } else {
+        load(&mut file)
     }
 }
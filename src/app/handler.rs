@@ -3,6 +3,7 @@ use crate::app::handler_registry;
 use crate::model::model::Model;
 use crate::renderer::camera::CameraController;
 use crate::renderer::renderer::Renderer;
+use crate::renderer::spawns::SpawnSource;
 use crate::settings::Settings;
 use crate::texture::loader::TextureLoadResult;
 use crate::texture::manager::TextureManager;
@@ -25,10 +26,14 @@ pub struct AppHandler {
     pub texture_sender: mpsc::UnboundedSender<TextureLoadResult>,
     pub model: Option<Model>,
     pub pending_model_path: Option<String>,
+    pub attached_model: Option<Model>, // Model with a preview model attached, drawn instead
+    pub pending_attachment: Option<(usize, String)>, // Attachment point and model to attach
+    pub pending_detach: bool,
     pub current_cursor_pos: Option<(f64, f64)>,
     pub ui: Ui,
     pub camera_controller: CameraController,
     pub animation_system: crate::animation::AnimationSystem,
+    pub spawn_sources: Vec<Option<SpawnSource>>, // Per PREM emitter of the shown model
     pub egui_wants_pointer: bool,
     pub texture_panel: TexturePanel,
     pub texture_manager: TextureManager,
@@ -109,6 +114,15 @@ impl ApplicationHandler for AppHandler {
                 }
             }
 
+            if let Some((attachment, path)) = self.pending_attachment.take()
+                && let Err(e) = self.runtime.block_on(app.attach_model(&path, attachment))
+            {
+                eprintln!("Failed to attach model '{}': {}", path, e);
+            }
+            if std::mem::take(&mut self.pending_detach) {
+                self.runtime.block_on(app.detach_model());
+            }
+
             if let Err(e) = app.render() {
                 eprintln!("Render error: {:?}", e);
             }
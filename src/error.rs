@@ -83,6 +83,7 @@ impl From<&str> for MdlError {
     }
 }
 
+#[cfg(feature = "viewer")]
 impl From<reqwest::Error> for MdlError {
     fn from(err: reqwest::Error) -> Self {
         MdlError::new("reqwest::Error").push_std(err)
@@ -95,18 +96,21 @@ impl From<io::Error> for MdlError {
     }
 }
 
+#[cfg(feature = "viewer")]
 impl From<blp::error::error::BlpError> for MdlError {
     fn from(err: blp::error::error::BlpError) -> Self {
         MdlError::new("blp-error").push_std(err)
     }
 }
 
+#[cfg(feature = "viewer")]
 impl From<wgpu::CreateSurfaceError> for MdlError {
     fn from(err: wgpu::CreateSurfaceError) -> Self {
         MdlError::new("wgpu::CreateSurfaceError").push_std(err)
     }
 }
 
+#[cfg(feature = "viewer")]
 impl From<winit::error::EventLoopError> for MdlError {
     fn from(err: winit::error::EventLoopError) -> Self {
         MdlError::new("winit::error::EventLoopError").push_std(err)
@@ -0,0 +1,29 @@
+//! Warcraft III model library: MDX/MDL parsing and writing, the in-memory
+//! `Model` and the skeletal animation system. The viewer binary is one consumer of this crate;
+//! tools that only need the parser can depend on it with
+//! `default-features = false` to leave out the GUI stack.
+
+pub mod animation;
+pub mod error;
+pub mod log;
+pub mod material;
+pub mod model;
+pub mod parser;
+pub mod writer;
+
+pub use error::MdlError;
+pub use material::{FilterMode, Layer, Material, MaterialFlags, ShadingFlags};
+pub use model::animation::Sequence;
+pub use model::camera::Camera;
+pub use model::collision::CollisionShape;
+pub use model::emitter::{ParticleEmitter, ParticleEmitter2, RibbonEmitter};
+pub use model::event::EventObject;
+pub use model::geoset::{Face, Geoset, Normal, TexCoord, Vertex};
+pub use model::light::Light;
+pub use model::model::Model;
+pub use model::skeleton::{AnimationController, Attachment, Bone, Helper, Keyframe};
+pub use model::texture::{Texture, TextureAnimation};
+pub use parser::load::{load, load_from_bytes};
+pub use parser::mdl::{load_mdl, load_mdl_from_str};
+pub use writer::mdl::{save_mdl, save_mdl_to_string};
+pub use writer::save::{save, save_to_bytes};
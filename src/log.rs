@@ -0,0 +1,25 @@
+//! Progress messages printed to stderr while loading, off unless asked for.
+//! Warnings about data that is skipped or left out are always printed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Turn the loading and animation progress messages on or off
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// `eprintln!` that only prints when `set_verbose(true)` was called
+#[macro_export]
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::log::is_verbose() {
+            eprintln!($($arg)*);
+        }
+    };
+}
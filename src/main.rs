@@ -1,14 +1,11 @@
-mod animation;
 mod app;
-mod error;
-mod material;
-mod model;
-mod parser;
 mod renderer;
 mod settings;
 mod texture;
 mod ui;
 
+use mdlvis_rs::{animation, error, material, model, parser, writer};
+
 use crate::app::handler::AppHandler;
 use crate::app::handler_registry;
 use crate::error::MdlError;
@@ -68,6 +65,9 @@ fn main() -> Result<(), MdlError> {
         }
     }));
 
+    // Loading progress only in debug builds
+    mdlvis_rs::log::set_verbose(cfg!(debug_assertions));
+
     let event_loop = EventLoop::new()?;
     event_loop.set_control_flow(ControlFlow::Poll);
 
@@ -78,6 +78,9 @@ fn main() -> Result<(), MdlError> {
         model: None,
         pending_model_path: std::env::args().skip(1).next().map(String::from),
         model_path: None,
+        attached_model: None,
+        pending_attachment: None,
+        pending_detach: false,
         runtime: Runtime::new()?,
         window: None,
         texture_receiver,
@@ -88,6 +91,7 @@ fn main() -> Result<(), MdlError> {
         texture_manager: TextureManager::new(),
         camera_controller: CameraController::new(CameraState::default()),
         animation_system: animation::AnimationSystem::new(),
+        spawn_sources: Vec::new(),
         egui_wants_pointer: false,
         settings: Settings::load(),
         egui_state: None,
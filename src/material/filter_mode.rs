@@ -32,12 +32,25 @@ impl FilterMode {
             5 => Self::Modulate,
             6 => Self::Modulate2x,
             _ => {
-                eprintln!("Unknown filter mode: {}, defaulting to None", value);
+                eprintln!("[mdlvis-rs] Unknown filter mode {}, defaulting to None", value);
                 Self::None
             }
         }
     }
 
+    /// Convert FilterMode back to its u32 value (MDX binary format)
+    pub fn to_u32(&self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Transparent => 1,
+            Self::Blend => 2,
+            Self::Additive => 3,
+            Self::AddAlpha => 4,
+            Self::Modulate => 5,
+            Self::Modulate2x => 6,
+        }
+    }
+
     /// Convert FilterMode to f32 for shader uniform
     pub fn to_f32(&self) -> f32 {
         match self {
@@ -56,3 +56,46 @@ impl ShadingFlags {
         }
     }
 }
+
+/// Material-level flags (MTLS), stored next to the priority plane
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MaterialFlags {
+    ConstantColor = 0x1,
+    SortPrimsFarZ = 0x10,
+    FullResolution = 0x20,
+}
+
+impl MaterialFlags {
+    /// Get all flags present in the bitfield
+    pub fn from_bits(bits: u32) -> Vec<Self> {
+        let mut flags = Vec::new();
+        if bits & Self::ConstantColor as u32 != 0 {
+            flags.push(Self::ConstantColor);
+        }
+        if bits & Self::SortPrimsFarZ as u32 != 0 {
+            flags.push(Self::SortPrimsFarZ);
+        }
+        if bits & Self::FullResolution as u32 != 0 {
+            flags.push(Self::FullResolution);
+        }
+        flags
+    }
+
+    /// Convert array of flags back to bitfield
+    pub fn get_bits(flags: &[Self]) -> u32 {
+        let mut bits = 0u32;
+        for flag in flags {
+            bits |= *flag as u32;
+        }
+        bits
+    }
+
+    /// Get human-readable name (same as the MDL keyword)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ConstantColor => "ConstantColor",
+            Self::SortPrimsFarZ => "SortPrimsFarZ",
+            Self::FullResolution => "FullResolution",
+        }
+    }
+}
@@ -1,25 +1,48 @@
 use serde::{Deserialize, Serialize};
-use super::{FilterMode, ShadingFlags};
+use super::{FilterMode, MaterialFlags, ShadingFlags};
+use crate::model::raw_chunk::RawChunk;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Material {
+    pub priority_plane: i32, // Lower planes are drawn first
+    pub flags: u32,          // MTLS flags as stored, unknown bits included
     pub layers: Vec<Layer>,
 }
 
 impl Default for Material {
     fn default() -> Self {
         Self {
+            priority_plane: 0,
+            flags: 0,
             layers: Vec::new(),
         }
     }
 }
 
+impl Material {
+    pub fn has_flag(&self, flag: MaterialFlags) -> bool {
+        self.flags & flag as u32 != 0
+    }
+
+    /// Known flags set in `flags`
+    pub fn flag_list(&self) -> Vec<MaterialFlags> {
+        MaterialFlags::from_bits(self.flags)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Layer {
     pub texture_id: Option<usize>,
     pub filter_mode: FilterMode,
     pub shading_flags: Vec<ShadingFlags>,
     pub alpha: f32,
+    pub texture_animation_id: Option<usize>, // Index into TXAN
+    pub coord_id: u32,                       // UV set of the geoset to sample with
+    // Animation controller indices (-1 if not animated)
+    pub texture_id_idx: i32, // KMTF: texture flipbook
+    pub alpha_idx: i32,      // KMTA
+    pub unknown_chunks: Vec<RawChunk>, // Layer track chunks that were not parsed
+    pub track_order: Vec<[u8; 4]>,     // Track tags as loaded, empty for the usual order
     
     // Runtime overrides (not serialized, only for UI)
     #[serde(skip)]
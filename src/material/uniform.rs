@@ -8,7 +8,9 @@ use super::FilterMode;
 pub struct MaterialUniform {
     pub team_color: [f32; 4], // team_color.rgb + replaceable_id (0=none, 1=team_color, 2=team_glow)
     pub material_type_and_wireframe: [f32; 4], // filter_mode + wireframe_mode + layer_alpha + shading_flags
-    pub extra_padding: [f32; 4], // Padding for alignment
+    pub geoset_color: [f32; 4], // Geoset animation color.rgb + alpha
+    pub uv_transform: [[f32; 4]; 2], // Rows of the 2D affine UV transform (texture animation)
+    pub uv_set: [f32; 4], // x = layer CoordId (UV set to sample with)
 }
 
 impl MaterialUniform {
@@ -27,7 +29,9 @@ impl MaterialUniform {
                 layer_alpha,
                 shading_flags as f32,
             ],
-            extra_padding: [0.0, 0.0, 0.0, 0.0],
+            geoset_color: [1.0, 1.0, 1.0, 1.0],
+            uv_transform: [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0]],
+            uv_set: [0.0; 4],
         };
         
         uniform
@@ -5,8 +5,14 @@ pub struct Sequence {
     pub name: String,
     pub start_frame: u32,
     pub end_frame: u32,
-    pub rarity: Option<u32>,
+    pub move_speed: f32,
+    pub rarity: f32,
     pub non_looping: bool,
+    pub sync_point: u32, // MDX only, kept so the sequence saves back unchanged
+    // Extent of the model over this sequence
+    pub bounds_radius: f32,
+    pub minimum_extent: [f32; 3],
+    pub maximum_extent: [f32; 3],
 }
 
 impl Default for Sequence {
@@ -15,8 +21,13 @@ impl Default for Sequence {
             name: String::new(),
             start_frame: 0,
             end_frame: 0,
-            rarity: None,
+            move_speed: 0.0,
+            rarity: 0.0,
             non_looping: false,
+            sync_point: 0,
+            bounds_radius: 0.0,
+            minimum_extent: [0.0, 0.0, 0.0],
+            maximum_extent: [0.0, 0.0, 0.0],
         }
     }
 }
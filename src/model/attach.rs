@@ -0,0 +1,357 @@
+use crate::model::model::Model;
+use crate::model::skeleton::{AnimationController, Helper};
+
+impl Model {
+    /// Copy of the model with `other` hanging from one of its attachment points, the way
+    /// the game shows a buff or weapon model. Only meant for display: the attached nodes
+    /// follow the attachment node and the first "Stand" sequence of `other` loops as a
+    /// global sequence. Attachments, cameras, event objects and unparsed chunks of `other`
+    /// are left out.
+    /// Returns None if the attachment does not exist
+    pub fn with_attached(&self, other: &Model, attachment: usize) -> Option<Model> {
+        let attachment = self.attachments.get(attachment)?;
+        let mut merged = self.clone();
+
+        let texture_offset = self.textures.len();
+        let material_offset = self.materials.len();
+        let geoset_offset = self.geosets.len() as u32;
+        let texture_anim_offset = self.texture_animations.len();
+        let controller_offset = self.controllers.len() as i32;
+        let offset_idx = |idx: i32| {
+            if idx >= 0 {
+                idx + controller_offset
+            } else {
+                -1
+            }
+        };
+
+        // Matrix groups index the bones followed by the helpers, which are laid out as
+        // host bones, other bones, host helpers, the attachment proxy, other helpers
+        let host_bones = self.bones.len() as u32;
+        let other_bones = other.bones.len() as u32;
+        let proxy_index = host_bones + other_bones + self.helpers.len() as u32;
+        for geoset in &mut merged.geosets {
+            for index in geoset.matrix_groups.iter_mut().flatten() {
+                if *index >= host_bones {
+                    *index += other_bones;
+                }
+            }
+        }
+
+        // Nodes of the other model get ObjectIDs after every node of this one
+        let proxy_id = node_count(self) as u32;
+        let id_offset = proxy_id as i32 + 1;
+        let offset_parent = |parent_id: i32| {
+            if parent_id >= 0 {
+                parent_id + id_offset
+            } else {
+                proxy_id as i32
+            }
+        };
+
+        // The other model is built around its origin, which sits on the attachment pivot
+        let origin = self.pivot_point(attachment.object_id);
+        let move_to_origin = |p: [f32; 3]| std::array::from_fn(|i| p[i] + origin[i]);
+
+        merged.pivot_points.resize(proxy_id as usize, [0.0; 3]);
+        merged.pivot_points.push(origin);
+        merged
+            .pivot_points
+            .extend((0..node_count(other) as u32).map(|id| move_to_origin(other.pivot_point(id))));
+
+        merged.textures.extend(other.textures.iter().cloned());
+
+        merged
+            .materials
+            .extend(other.materials.iter().map(|material| {
+                let mut material = material.clone();
+                for layer in &mut material.layers {
+                    layer.texture_id = layer.texture_id.map(|id| id + texture_offset);
+                    layer.texture_animation_id = layer
+                        .texture_animation_id
+                        .map(|id| id + texture_anim_offset);
+                    layer.texture_id_idx = offset_idx(layer.texture_id_idx);
+                    layer.alpha_idx = offset_idx(layer.alpha_idx);
+                }
+                material
+            }));
+
+        merged.geosets.extend(other.geosets.iter().map(|geoset| {
+            let mut geoset = geoset.clone();
+            geoset.material_id = geoset.material_id.map(|id| id + material_offset);
+            for vertex in &mut geoset.vertices {
+                vertex.position = move_to_origin(vertex.position);
+            }
+            geoset.minimum_extent = move_to_origin(geoset.minimum_extent);
+            geoset.maximum_extent = move_to_origin(geoset.maximum_extent);
+            geoset.sequence_extents.clear();
+            if geoset.matrix_groups.is_empty() {
+                // Unskinned geosets move with the attachment point as a whole
+                geoset.matrix_groups = vec![vec![proxy_index]];
+                geoset.vertex_groups = vec![0; geoset.vertices.len()];
+            } else {
+                for index in geoset.matrix_groups.iter_mut().flatten() {
+                    *index = if *index < other_bones {
+                        host_bones + *index
+                    } else {
+                        proxy_index + 1 + (*index - other_bones)
+                    };
+                }
+            }
+            geoset
+        }));
+
+        merged
+            .geoset_animations
+            .extend(other.geoset_animations.iter().map(|anim| {
+                let mut anim = anim.clone();
+                anim.geoset_id += geoset_offset;
+                anim.alpha_idx = offset_idx(anim.alpha_idx);
+                anim.color_idx = offset_idx(anim.color_idx);
+                anim
+            }));
+
+        merged
+            .texture_animations
+            .extend(other.texture_animations.iter().map(|anim| {
+                let mut anim = anim.clone();
+                anim.translation_idx = offset_idx(anim.translation_idx);
+                anim.rotation_idx = offset_idx(anim.rotation_idx);
+                anim.scaling_idx = offset_idx(anim.scaling_idx);
+                anim
+            }));
+
+        merged.bones.extend(other.bones.iter().map(|bone| {
+            let mut bone = bone.clone();
+            bone.object_id += id_offset as u32;
+            bone.parent_id = offset_parent(bone.parent_id);
+            bone.geoset_id = bone.geoset_id.map(|id| id + geoset_offset);
+            bone.geoset_anim_id = bone
+                .geoset_anim_id
+                .map(|id| id + self.geoset_animations.len() as u32);
+            bone.translation_idx = offset_idx(bone.translation_idx);
+            bone.rotation_idx = offset_idx(bone.rotation_idx);
+            bone.scaling_idx = offset_idx(bone.scaling_idx);
+            bone.visibility_idx = offset_idx(bone.visibility_idx);
+            bone
+        }));
+
+        // Stand-in for the attachment point, so the other model moves with it
+        merged.helpers.push(Helper {
+            name: attachment.name.clone(),
+            object_id: proxy_id,
+            parent_id: attachment.parent_id,
+            flags: attachment.flags,
+            translation_idx: attachment.translation_idx,
+            rotation_idx: attachment.rotation_idx,
+            scaling_idx: attachment.scaling_idx,
+            visibility_idx: attachment.visibility_idx,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
+        });
+        merged.helpers.extend(other.helpers.iter().map(|helper| {
+            let mut helper = helper.clone();
+            helper.object_id += id_offset as u32;
+            helper.parent_id = offset_parent(helper.parent_id);
+            helper.translation_idx = offset_idx(helper.translation_idx);
+            helper.rotation_idx = offset_idx(helper.rotation_idx);
+            helper.scaling_idx = offset_idx(helper.scaling_idx);
+            helper.visibility_idx = offset_idx(helper.visibility_idx);
+            helper
+        }));
+
+        // Emitters, lights and collision shapes hang from the moved nodes as well
+        merged
+            .particle_emitters
+            .extend(other.particle_emitters.iter().map(|emitter| {
+                let mut emitter = emitter.clone();
+                emitter.object_id += id_offset as u32;
+                emitter.parent_id = offset_parent(emitter.parent_id);
+                emitter.translation_idx = offset_idx(emitter.translation_idx);
+                emitter.rotation_idx = offset_idx(emitter.rotation_idx);
+                emitter.scaling_idx = offset_idx(emitter.scaling_idx);
+                emitter.emission_rate_idx = offset_idx(emitter.emission_rate_idx);
+                emitter.gravity_idx = offset_idx(emitter.gravity_idx);
+                emitter.longitude_idx = offset_idx(emitter.longitude_idx);
+                emitter.latitude_idx = offset_idx(emitter.latitude_idx);
+                emitter.lifespan_idx = offset_idx(emitter.lifespan_idx);
+                emitter.speed_idx = offset_idx(emitter.speed_idx);
+                emitter.visibility_idx = offset_idx(emitter.visibility_idx);
+                emitter
+            }));
+        merged
+            .particle_emitters2
+            .extend(other.particle_emitters2.iter().map(|emitter| {
+                let mut emitter = emitter.clone();
+                emitter.object_id += id_offset as u32;
+                emitter.parent_id = offset_parent(emitter.parent_id);
+                emitter.texture_id += texture_offset as u32;
+                emitter.translation_idx = offset_idx(emitter.translation_idx);
+                emitter.rotation_idx = offset_idx(emitter.rotation_idx);
+                emitter.scaling_idx = offset_idx(emitter.scaling_idx);
+                emitter.speed_idx = offset_idx(emitter.speed_idx);
+                emitter.variation_idx = offset_idx(emitter.variation_idx);
+                emitter.latitude_idx = offset_idx(emitter.latitude_idx);
+                emitter.gravity_idx = offset_idx(emitter.gravity_idx);
+                emitter.emission_rate_idx = offset_idx(emitter.emission_rate_idx);
+                emitter.length_idx = offset_idx(emitter.length_idx);
+                emitter.width_idx = offset_idx(emitter.width_idx);
+                emitter.visibility_idx = offset_idx(emitter.visibility_idx);
+                emitter
+            }));
+        merged
+            .ribbon_emitters
+            .extend(other.ribbon_emitters.iter().map(|emitter| {
+                let mut emitter = emitter.clone();
+                emitter.object_id += id_offset as u32;
+                emitter.parent_id = offset_parent(emitter.parent_id);
+                emitter.material_id += material_offset as u32;
+                emitter.translation_idx = offset_idx(emitter.translation_idx);
+                emitter.rotation_idx = offset_idx(emitter.rotation_idx);
+                emitter.scaling_idx = offset_idx(emitter.scaling_idx);
+                emitter.height_above_idx = offset_idx(emitter.height_above_idx);
+                emitter.height_below_idx = offset_idx(emitter.height_below_idx);
+                emitter.alpha_idx = offset_idx(emitter.alpha_idx);
+                emitter.color_idx = offset_idx(emitter.color_idx);
+                emitter.texture_slot_idx = offset_idx(emitter.texture_slot_idx);
+                emitter.visibility_idx = offset_idx(emitter.visibility_idx);
+                emitter
+            }));
+        merged.lights.extend(other.lights.iter().map(|light| {
+            let mut light = light.clone();
+            light.object_id += id_offset as u32;
+            light.parent_id = offset_parent(light.parent_id);
+            light.translation_idx = offset_idx(light.translation_idx);
+            light.rotation_idx = offset_idx(light.rotation_idx);
+            light.scaling_idx = offset_idx(light.scaling_idx);
+            light.attenuation_start_idx = offset_idx(light.attenuation_start_idx);
+            light.attenuation_end_idx = offset_idx(light.attenuation_end_idx);
+            light.color_idx = offset_idx(light.color_idx);
+            light.intensity_idx = offset_idx(light.intensity_idx);
+            light.amb_intensity_idx = offset_idx(light.amb_intensity_idx);
+            light.amb_color_idx = offset_idx(light.amb_color_idx);
+            light.visibility_idx = offset_idx(light.visibility_idx);
+            light
+        }));
+        merged
+            .collision_shapes
+            .extend(other.collision_shapes.iter().map(|shape| {
+                let mut shape = shape.clone();
+                shape.object_id += id_offset as u32;
+                shape.parent_id = offset_parent(shape.parent_id);
+                for vertex in &mut shape.vertices {
+                    *vertex = move_to_origin(*vertex);
+                }
+                shape.translation_idx = offset_idx(shape.translation_idx);
+                shape.rotation_idx = offset_idx(shape.rotation_idx);
+                shape.scaling_idx = offset_idx(shape.scaling_idx);
+                shape
+            }));
+
+        // The host timeline plays the host sequences, so the attached model loops its
+        // stand animation on a global sequence of its own
+        let stand = other
+            .sequences
+            .iter()
+            .find(|s| s.name.to_lowercase().starts_with("stand"))
+            .or(other.sequences.first());
+        let stand_global_seq = self.global_sequences.len() + other.global_sequences.len();
+        merged
+            .global_sequences
+            .extend(other.global_sequences.iter().copied());
+        if let Some(stand) = stand {
+            merged
+                .global_sequences
+                .push(stand.end_frame.saturating_sub(stand.start_frame).max(1));
+        }
+
+        merged
+            .controllers
+            .extend(other.controllers.iter().map(|controller| {
+                let mut controller = controller.clone();
+                if controller.global_seq_id >= 0 {
+                    controller.global_seq_id += self.global_sequences.len() as i32;
+                } else if let Some(stand) = stand {
+                    controller = sequence_to_global(
+                        &controller,
+                        stand.start_frame as i32,
+                        stand.end_frame as i32,
+                        stand_global_seq as i32,
+                    );
+                }
+                controller
+            }));
+
+        // Flipbook keys hold texture ids
+        for layer in other.materials.iter().flat_map(|m| &m.layers) {
+            if let Some(controller) = usize::try_from(layer.texture_id_idx)
+                .ok()
+                .and_then(|idx| merged.controllers.get_mut(self.controllers.len() + idx))
+            {
+                for key in &mut controller.keyframes {
+                    for value in &mut key.data {
+                        *value += texture_offset as f32;
+                    }
+                }
+            }
+        }
+
+        Some(merged)
+    }
+}
+
+/// Number of ObjectIDs in use, PIVT may be shorter than the node list
+fn node_count(model: &Model) -> usize {
+    let node_ids = model
+        .bones
+        .iter()
+        .map(|b| b.object_id)
+        .chain(model.helpers.iter().map(|h| h.object_id))
+        .chain(model.attachments.iter().map(|a| a.object_id))
+        .chain(model.particle_emitters.iter().map(|e| e.object_id))
+        .chain(model.particle_emitters2.iter().map(|e| e.object_id))
+        .chain(model.ribbon_emitters.iter().map(|e| e.object_id))
+        .chain(model.lights.iter().map(|l| l.object_id))
+        .chain(model.event_objects.iter().map(|e| e.object_id))
+        .chain(model.collision_shapes.iter().map(|c| c.object_id));
+    node_ids
+        .map(|id| id as usize + 1)
+        .chain([model.pivot_points.len()])
+        .max()
+        .unwrap_or(0)
+}
+
+/// Keys of one sequence moved to the start of a global sequence. A track without keys
+/// in the sequence keeps the value it has when the sequence starts
+fn sequence_to_global(
+    controller: &AnimationController,
+    start: i32,
+    end: i32,
+    global_seq_id: i32,
+) -> AnimationController {
+    let mut keyframes: Vec<_> = controller
+        .keyframes
+        .iter()
+        .filter(|key| key.frame >= start && key.frame <= end)
+        .cloned()
+        .collect();
+    if keyframes.is_empty() {
+        let held = controller
+            .keyframes
+            .iter()
+            .rev()
+            .find(|key| key.frame < start)
+            .or(controller.keyframes.first());
+        keyframes.extend(held.cloned());
+        keyframes.iter_mut().for_each(|key| key.frame = start);
+    }
+    for key in &mut keyframes {
+        key.frame -= start;
+    }
+
+    AnimationController {
+        interpolation_type: controller.interpolation_type,
+        global_seq_id,
+        keyframes,
+    }
+}
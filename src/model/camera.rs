@@ -0,0 +1,38 @@
+use crate::model::raw_chunk::RawChunk;
+use serde::{Deserialize, Serialize};
+
+/// CAMS: camera the game frames the model with (unit portraits, cinematics).
+/// Not a node: its tracks move it from its static position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Camera {
+    pub name: String,
+    pub position: [f32; 3],
+    pub field_of_view: f32, // Vertical, in radians
+    pub far_clip: f32,
+    pub near_clip: f32,
+    pub target_position: [f32; 3], // Point the camera looks at
+    // Animation controller indices (-1 if not animated)
+    pub translation_idx: i32,          // KCTR
+    pub rotation_idx: i32,             // KCRL, roll around the view direction
+    pub target_translation_idx: i32,   // KTTR
+    pub unknown_chunks: Vec<RawChunk>, // Track chunks that were not parsed
+    pub track_order: Vec<[u8; 4]>,     // Track tags as loaded, empty for the usual order
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            position: [0.0; 3],
+            field_of_view: std::f32::consts::FRAC_PI_4,
+            far_clip: 10000.0,
+            near_clip: 1.0,
+            target_position: [0.0; 3],
+            translation_idx: -1,
+            rotation_idx: -1,
+            target_translation_idx: -1,
+            unknown_chunks: Vec::new(),
+            track_order: Vec::new(),
+        }
+    }
+}
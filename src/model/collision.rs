@@ -0,0 +1,65 @@
+use crate::model::raw_chunk::RawChunk;
+use serde::{Deserialize, Serialize};
+
+// Collision shape types
+pub const COLLISION_SHAPE_BOX: u32 = 0;
+pub const COLLISION_SHAPE_PLANE: u32 = 1;
+pub const COLLISION_SHAPE_SPHERE: u32 = 2;
+pub const COLLISION_SHAPE_CYLINDER: u32 = 3;
+
+/// CLID: shape the game uses to select and hit the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollisionShape {
+    pub name: String,
+    pub object_id: u32,
+    pub parent_id: i32,  // -1 means no parent
+    pub flags: u32,      // NODE_FLAG_*, without the node type bit
+    pub type_flag_missing: bool,
+    pub shape_type: u32, // COLLISION_SHAPE_*
+    // Box and plane: opposite corners, sphere: center, cylinder: centers of both ends
+    pub vertices: Vec<[f32; 3]>,
+    pub radius: f32, // Spheres and cylinders only
+    // Animation controller indices (-1 if not animated)
+    pub translation_idx: i32,
+    pub rotation_idx: i32,
+    pub scaling_idx: i32,
+    pub unknown_chunks: Vec<RawChunk>, // Node track chunks that were not parsed
+    // Node track tags as loaded, empty for the usual order
+    pub node_track_order: Vec<[u8; 4]>,
+}
+
+impl CollisionShape {
+    /// Number of vertices the shape type is stored with
+    pub fn vertex_count(shape_type: u32) -> usize {
+        if shape_type == COLLISION_SHAPE_SPHERE {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Whether the shape type is stored with a radius
+    pub fn has_radius(shape_type: u32) -> bool {
+        shape_type == COLLISION_SHAPE_SPHERE || shape_type == COLLISION_SHAPE_CYLINDER
+    }
+}
+
+impl Default for CollisionShape {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            object_id: 0,
+            parent_id: -1,
+            flags: 0,
+            type_flag_missing: false,
+            shape_type: COLLISION_SHAPE_BOX,
+            vertices: Vec::new(),
+            radius: 0.0,
+            translation_idx: -1,
+            rotation_idx: -1,
+            scaling_idx: -1,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
+        }
+    }
+}
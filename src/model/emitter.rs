@@ -0,0 +1,284 @@
+use crate::model::raw_chunk::RawChunk;
+use serde::{Deserialize, Serialize};
+
+// ParticleEmitter flags, on top of the node flags: what the path points to
+pub const PARTICLE_FLAG_EMITTER_USES_MDL: u32 = 0x8000;
+pub const PARTICLE_FLAG_EMITTER_USES_TGA: u32 = 0x10000;
+
+/// PREM: older emitter that throws copies of a model (blood, debris) or of a texture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticleEmitter {
+    pub name: String,
+    pub object_id: u32,
+    pub parent_id: i32,     // -1 means no parent
+    pub flags: u32,         // NODE_FLAG_* | PARTICLE_FLAG_*, without the node type bit
+    pub type_flag_missing: bool,
+    pub emission_rate: f32, // Particles per second
+    pub gravity: f32,
+    pub longitude: f32, // Spread around the emitter Z axis, in radians
+    pub latitude: f32,  // Spread away from the emitter Z axis, in radians
+    pub path: String,   // Model or texture of the particles
+    pub lifespan: f32,  // Seconds
+    pub speed: f32,     // Initial velocity
+    // Animation controller indices (-1 if not animated)
+    pub translation_idx: i32,
+    pub rotation_idx: i32,
+    pub scaling_idx: i32,
+    pub emission_rate_idx: i32,        // KPEE
+    pub gravity_idx: i32,              // KPEG
+    pub longitude_idx: i32,            // KPLN
+    pub latitude_idx: i32,             // KPLT
+    pub lifespan_idx: i32,             // KPEL
+    pub speed_idx: i32,                // KPES
+    pub visibility_idx: i32,           // KPEV
+    pub unknown_chunks: Vec<RawChunk>, // Node track chunks that were not parsed
+    // Node track tags as loaded, empty for the usual order
+    pub node_track_order: Vec<[u8; 4]>,
+    pub unknown_tracks: Vec<RawChunk>, // Emitter track chunks that were not parsed
+    pub track_order: Vec<[u8; 4]>,     // Emitter track tags as loaded, empty for the usual order
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            object_id: 0,
+            parent_id: -1,
+            flags: 0,
+            type_flag_missing: false,
+            emission_rate: 0.0,
+            gravity: 0.0,
+            longitude: 0.0,
+            latitude: 0.0,
+            path: String::new(),
+            lifespan: 1.0,
+            speed: 0.0,
+            translation_idx: -1,
+            rotation_idx: -1,
+            scaling_idx: -1,
+            emission_rate_idx: -1,
+            gravity_idx: -1,
+            longitude_idx: -1,
+            latitude_idx: -1,
+            lifespan_idx: -1,
+            speed_idx: -1,
+            visibility_idx: -1,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
+            unknown_tracks: Vec::new(),
+            track_order: Vec::new(),
+        }
+    }
+}
+
+impl ParticleEmitter {
+    pub fn has_flag(&self, flag: u32) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+// ParticleEmitter2 flags, on top of the node flags
+pub const PARTICLE2_FLAG_UNSHADED: u32 = 0x8000;
+pub const PARTICLE2_FLAG_SORT_PRIMS_FAR_Z: u32 = 0x10000;
+pub const PARTICLE2_FLAG_LINE_EMITTER: u32 = 0x20000;
+pub const PARTICLE2_FLAG_UNFOGGED: u32 = 0x40000;
+pub const PARTICLE2_FLAG_MODEL_SPACE: u32 = 0x80000;
+pub const PARTICLE2_FLAG_XY_QUAD: u32 = 0x100000;
+
+// ParticleEmitter2 filter modes, numbered differently from layer filter modes
+pub const PARTICLE2_FILTER_BLEND: u32 = 0;
+pub const PARTICLE2_FILTER_ADDITIVE: u32 = 1;
+pub const PARTICLE2_FILTER_MODULATE: u32 = 2;
+pub const PARTICLE2_FILTER_MODULATE_2X: u32 = 3;
+pub const PARTICLE2_FILTER_ALPHA_KEY: u32 = 4;
+
+// Parts of a particle that get drawn
+pub const PARTICLE2_HEAD: u32 = 0;
+pub const PARTICLE2_TAIL: u32 = 1;
+pub const PARTICLE2_BOTH: u32 = 2;
+
+/// PRE2: emitter of textured, camera-facing particles, most spell effects are built from them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticleEmitter2 {
+    pub name: String,
+    pub object_id: u32,
+    pub parent_id: i32, // -1 means no parent
+    pub flags: u32,     // NODE_FLAG_* | PARTICLE2_FLAG_*, without the node type bit
+    pub type_flag_missing: bool,
+    pub speed: f32,
+    pub variation: f32, // Speed varies by up to this fraction
+    pub latitude: f32,  // Spread of the emission cone in degrees
+    pub gravity: f32,
+    pub lifespan: f32,      // Seconds
+    pub emission_rate: f32, // Particles per second
+    pub length: f32,        // Size of the emission area
+    pub width: f32,
+    pub filter_mode: u32, // PARTICLE2_FILTER_*
+    pub rows: u32,        // Texture atlas layout
+    pub columns: u32,
+    pub head_or_tail: u32, // PARTICLE2_HEAD | PARTICLE2_TAIL | PARTICLE2_BOTH
+    pub tail_length: f32,
+    pub time: f32, // Fraction of the lifespan spent on the first segment
+    pub segment_colors: [[f32; 3]; 3], // Blue, green, red at birth, middle and death
+    pub segment_alpha: [u8; 3],
+    pub segment_scaling: [f32; 3],
+    // Atlas cells as start, end and repeat, for both halves of the lifespan
+    pub head_intervals: [u32; 3],
+    pub head_decay_intervals: [u32; 3],
+    pub tail_intervals: [u32; 3],
+    pub tail_decay_intervals: [u32; 3],
+    pub texture_id: u32,
+    pub squirt: bool, // Emission rate keys emit a burst instead of a stream
+    pub priority_plane: i32,
+    pub replaceable_id: u32,
+    // Animation controller indices (-1 if not animated)
+    pub translation_idx: i32,
+    pub rotation_idx: i32,
+    pub scaling_idx: i32,
+    pub speed_idx: i32,                // KP2S
+    pub variation_idx: i32,            // KP2R
+    pub latitude_idx: i32,             // KP2L
+    pub gravity_idx: i32,              // KP2G
+    pub emission_rate_idx: i32,        // KP2E
+    pub length_idx: i32,               // KP2N
+    pub width_idx: i32,                // KP2W
+    pub visibility_idx: i32,           // KP2V
+    pub unknown_chunks: Vec<RawChunk>, // Node track chunks that were not parsed
+    // Node track tags as loaded, empty for the usual order
+    pub node_track_order: Vec<[u8; 4]>,
+    pub unknown_tracks: Vec<RawChunk>, // Emitter track chunks that were not parsed
+    pub track_order: Vec<[u8; 4]>,     // Emitter track tags as loaded, empty for the usual order
+}
+
+impl Default for ParticleEmitter2 {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            object_id: 0,
+            parent_id: -1,
+            flags: 0,
+            type_flag_missing: false,
+            speed: 0.0,
+            variation: 0.0,
+            latitude: 0.0,
+            gravity: 0.0,
+            lifespan: 1.0,
+            emission_rate: 0.0,
+            length: 0.0,
+            width: 0.0,
+            filter_mode: PARTICLE2_FILTER_BLEND,
+            rows: 1,
+            columns: 1,
+            head_or_tail: PARTICLE2_HEAD,
+            tail_length: 0.0,
+            time: 0.5,
+            segment_colors: [[1.0; 3]; 3],
+            segment_alpha: [255; 3],
+            segment_scaling: [1.0; 3],
+            head_intervals: [0, 0, 1],
+            head_decay_intervals: [0, 0, 1],
+            tail_intervals: [0, 0, 1],
+            tail_decay_intervals: [0, 0, 1],
+            texture_id: 0,
+            squirt: false,
+            priority_plane: 0,
+            replaceable_id: 0,
+            translation_idx: -1,
+            rotation_idx: -1,
+            scaling_idx: -1,
+            speed_idx: -1,
+            variation_idx: -1,
+            latitude_idx: -1,
+            gravity_idx: -1,
+            emission_rate_idx: -1,
+            length_idx: -1,
+            width_idx: -1,
+            visibility_idx: -1,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
+            unknown_tracks: Vec::new(),
+            track_order: Vec::new(),
+        }
+    }
+}
+
+impl ParticleEmitter2 {
+    pub fn has_flag(&self, flag: u32) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+// Ribbon emitter type bit written by some community tools instead of the node type bit
+pub const RIBBON_FLAG_ALT_TYPE: u32 = 0x400000;
+
+/// RIBB: emitter that leaves a textured strip behind its node, used for weapon trails
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RibbonEmitter {
+    pub name: String,
+    pub object_id: u32,
+    pub parent_id: i32,    // -1 means no parent
+    pub flags: u32,        // NODE_FLAG_*, without the node type bit
+    pub type_flag_missing: bool,
+    pub height_above: f32, // Extent of the strip along the node Y axis
+    pub height_below: f32,
+    pub alpha: f32,
+    pub color: [f32; 3],    // Blue, green, red as stored in the file
+    pub lifespan: f32,      // Seconds an edge stays before it disappears
+    pub texture_slot: u32,  // Atlas cell
+    pub emission_rate: u32, // Edges per second
+    pub rows: u32,          // Texture atlas layout
+    pub columns: u32,
+    pub material_id: u32,
+    pub gravity: f32,
+    // Animation controller indices (-1 if not animated)
+    pub translation_idx: i32,
+    pub rotation_idx: i32,
+    pub scaling_idx: i32,
+    pub height_above_idx: i32,         // KRHA
+    pub height_below_idx: i32,         // KRHB
+    pub alpha_idx: i32,                // KRAL
+    pub color_idx: i32,                // KRCO
+    pub texture_slot_idx: i32,         // KRTX
+    pub visibility_idx: i32,           // KRVS
+    pub unknown_chunks: Vec<RawChunk>, // Node track chunks that were not parsed
+    // Node track tags as loaded, empty for the usual order
+    pub node_track_order: Vec<[u8; 4]>,
+    pub unknown_tracks: Vec<RawChunk>, // Emitter track chunks that were not parsed
+    pub track_order: Vec<[u8; 4]>,     // Emitter track tags as loaded, empty for the usual order
+}
+
+impl Default for RibbonEmitter {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            object_id: 0,
+            parent_id: -1,
+            flags: 0,
+            type_flag_missing: false,
+            height_above: 0.0,
+            height_below: 0.0,
+            alpha: 1.0,
+            color: [1.0; 3],
+            lifespan: 1.0,
+            texture_slot: 0,
+            emission_rate: 0,
+            rows: 1,
+            columns: 1,
+            material_id: 0,
+            gravity: 0.0,
+            translation_idx: -1,
+            rotation_idx: -1,
+            scaling_idx: -1,
+            height_above_idx: -1,
+            height_below_idx: -1,
+            alpha_idx: -1,
+            color_idx: -1,
+            texture_slot_idx: -1,
+            visibility_idx: -1,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
+            unknown_tracks: Vec::new(),
+            track_order: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::model::raw_chunk::RawChunk;
+use serde::{Deserialize, Serialize};
+
+/// EVTS: node that fires a game event (sound, footprint, blood splat...) at the frames
+/// of its track. The event is picked by the name, like "SNDxAtt1"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventObject {
+    pub name: String,
+    pub object_id: u32,
+    pub parent_id: i32, // -1 means no parent
+    pub flags: u32,     // NODE_FLAG_*, without the node type bit
+    pub type_flag_missing: bool,
+    // KEVT, kept apart from the controllers since it has frames but no values
+    pub has_track: bool,
+    pub global_seq_id: i32, // -1 when the frames belong to the sequences
+    pub frames: Vec<u32>,
+    // Animation controller indices (-1 if not animated)
+    pub translation_idx: i32,
+    pub rotation_idx: i32,
+    pub scaling_idx: i32,
+    pub unknown_chunks: Vec<RawChunk>, // Node track chunks that were not parsed
+    // Node track tags as loaded, empty for the usual order
+    pub node_track_order: Vec<[u8; 4]>,
+}
+
+impl Default for EventObject {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            object_id: 0,
+            parent_id: -1,
+            flags: 0,
+            type_flag_missing: false,
+            has_track: false,
+            global_seq_id: -1,
+            frames: Vec::new(),
+            translation_idx: -1,
+            rotation_idx: -1,
+            scaling_idx: -1,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
+        }
+    }
+}
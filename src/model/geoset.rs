@@ -1,3 +1,4 @@
+use crate::model::raw_chunk::RawChunk;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,21 +21,36 @@ pub struct Face {
     pub vertices: [u32; 3],
 }
 
+/// Extent of a geoset in one sequence (nanim entries of GEOS, `Anim` in MDL)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Extent {
+    pub bounds_radius: f32,
+    pub minimum_extent: [f32; 3],
+    pub maximum_extent: [f32; 3],
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Geoset {
     pub vertices: Vec<Vertex>,
     pub normals: Vec<Normal>,
-    pub tex_coords: Vec<TexCoord>,
-    pub faces: Vec<Face>,
+    pub tex_coords: Vec<TexCoord>,                // First UV set (CoordId 0)
+    pub secondary_tex_coords: Vec<Vec<TexCoord>>, // Further UV sets (CoordId 1, 2...)
+    pub faces: Vec<Face>, // Triangulated from the primitive groups, for rendering
+    // Primitive groups as stored, written back unchanged on save
+    pub primitive_types: Vec<u32>,   // PTYP
+    pub primitive_counts: Vec<u32>,  // PCNT: indices in each group
+    pub primitive_indices: Vec<u32>, // PVTX
     pub material_id: Option<usize>,
     pub selection_group: usize,
-    pub unselectable: bool,
+    pub selectable: u32, // GEOSET_UNSELECTABLE or 0, other values kept as stored
     pub bounds_radius: f32,
     pub minimum_extent: [f32; 3],
     pub maximum_extent: [f32; 3],
+    pub sequence_extents: Vec<Extent>, // One per sequence
     // Animation data
     pub vertex_groups: Vec<u8>,      // GNDX: Index into matrix_groups for each vertex
     pub matrix_groups: Vec<Vec<u32>>, // MTGC+MATS: Groups of bone indices
+    pub unknown_chunks: Vec<RawChunk>, // Sub-chunks that were not parsed
 }
 
 impl Default for Geoset {
@@ -43,15 +59,87 @@ impl Default for Geoset {
             vertices: Vec::new(),
             normals: Vec::new(),
             tex_coords: Vec::new(),
+            secondary_tex_coords: Vec::new(),
             faces: Vec::new(),
+            primitive_types: Vec::new(),
+            primitive_counts: Vec::new(),
+            primitive_indices: Vec::new(),
             material_id: None,
             selection_group: 0,
-            unselectable: false,
+            selectable: 0,
             bounds_radius: 0.0,
             minimum_extent: [0.0; 3],
             maximum_extent: [0.0; 3],
+            sequence_extents: Vec::new(),
             vertex_groups: Vec::new(),
             matrix_groups: Vec::new(),
+            unknown_chunks: Vec::new(),
+        }
+    }
+}
+
+impl Geoset {
+    /// UV set selected by a layer CoordId, empty if the geoset does not have it
+    pub fn tex_coord_set(&self, coord_id: usize) -> &[TexCoord] {
+        match coord_id {
+            0 => &self.tex_coords,
+            _ => self
+                .secondary_tex_coords
+                .get(coord_id - 1)
+                .map_or(&[], |set| set.as_slice()),
+        }
+    }
+
+    pub fn is_unselectable(&self) -> bool {
+        self.selectable == GEOSET_UNSELECTABLE
+    }
+}
+
+/// Selectable value of geosets that can't be picked in the editor
+pub const GEOSET_UNSELECTABLE: u32 = 4;
+
+// PTYP primitive types (see FaceTypeGroup in mdx.hexpat)
+pub const PRIMITIVE_POINTS: u32 = 0;
+pub const PRIMITIVE_LINES: u32 = 1;
+pub const PRIMITIVE_LINE_LOOP: u32 = 2;
+pub const PRIMITIVE_LINE_STRIP: u32 = 3;
+pub const PRIMITIVE_TRIANGLES: u32 = 4;
+pub const PRIMITIVE_TRIANGLE_STRIP: u32 = 5;
+pub const PRIMITIVE_TRIANGLE_FAN: u32 = 6;
+pub const PRIMITIVE_QUADS: u32 = 7;
+pub const PRIMITIVE_QUAD_STRIP: u32 = 8;
+pub const PRIMITIVE_POLYGONS: u32 = 9;
+
+/// GEOA flag: the geoset casts a drop shadow
+pub const GEOSET_ANIM_DROP_SHADOW: u32 = 0x1;
+/// GEOA flag: the geoset is tinted with the color
+pub const GEOSET_ANIM_COLOR: u32 = 0x2;
+
+/// GEOA: alpha and color of a geoset over time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeosetAnimation {
+    pub geoset_id: u32,
+    pub alpha: f32,
+    pub flags: u32,      // GEOSET_ANIM_DROP_SHADOW | GEOSET_ANIM_COLOR
+    pub color: [f32; 3], // Blue, green, red as stored in the file
+    // Animation controller indices (-1 if not animated)
+    pub alpha_idx: i32,                // KGAO
+    pub color_idx: i32,                // KGAC
+    pub unknown_chunks: Vec<RawChunk>, // Track chunks that were not parsed
+    pub track_order: Vec<[u8; 4]>,     // Track tags as loaded, empty for the usual order
+}
+
+impl Default for GeosetAnimation {
+    fn default() -> Self {
+        Self {
+            geoset_id: 0,
+            alpha: 1.0,
+            flags: 0,
+            color: [1.0, 1.0, 1.0],
+            alpha_idx: -1,
+            color_idx: -1,
+            unknown_chunks: Vec::new(),
+            track_order: Vec::new(),
         }
     }
 }
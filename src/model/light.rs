@@ -0,0 +1,74 @@
+use crate::model::raw_chunk::RawChunk;
+use serde::{Deserialize, Serialize};
+
+// Light types
+pub const LIGHT_TYPE_OMNI: u32 = 0;
+pub const LIGHT_TYPE_DIRECTIONAL: u32 = 1;
+pub const LIGHT_TYPE_AMBIENT: u32 = 2;
+
+/// LITE: light that shines on the model (torches, spell glows)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Light {
+    pub name: String,
+    pub object_id: u32,
+    pub parent_id: i32,  // -1 means no parent
+    pub flags: u32,      // NODE_FLAG_*, without the node type bit
+    pub type_flag_missing: bool,
+    pub light_type: u32, // LIGHT_TYPE_*
+    // Full intensity up to the start distance, none past the end one
+    pub attenuation_start: f32,
+    pub attenuation_end: f32,
+    pub color: [f32; 3], // Blue, green, red as stored in the file
+    pub intensity: f32,
+    pub amb_color: [f32; 3], // Blue, green, red as stored in the file
+    pub amb_intensity: f32,
+    // Animation controller indices (-1 if not animated)
+    pub translation_idx: i32,
+    pub rotation_idx: i32,
+    pub scaling_idx: i32,
+    pub attenuation_start_idx: i32,    // KLAS
+    pub attenuation_end_idx: i32,      // KLAE
+    pub color_idx: i32,                // KLAC
+    pub intensity_idx: i32,            // KLAI
+    pub amb_intensity_idx: i32,        // KLBI
+    pub amb_color_idx: i32,            // KLBC
+    pub visibility_idx: i32,           // KLAV
+    pub unknown_chunks: Vec<RawChunk>, // Node track chunks that were not parsed
+    // Node track tags as loaded, empty for the usual order
+    pub node_track_order: Vec<[u8; 4]>,
+    pub unknown_tracks: Vec<RawChunk>, // Light track chunks that were not parsed
+    pub track_order: Vec<[u8; 4]>,     // Light track tags as loaded, empty for the usual order
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            object_id: 0,
+            parent_id: -1,
+            flags: 0,
+            type_flag_missing: false,
+            light_type: LIGHT_TYPE_OMNI,
+            attenuation_start: 0.0,
+            attenuation_end: 0.0,
+            color: [1.0; 3],
+            intensity: 1.0,
+            amb_color: [1.0; 3],
+            amb_intensity: 0.0,
+            translation_idx: -1,
+            rotation_idx: -1,
+            scaling_idx: -1,
+            attenuation_start_idx: -1,
+            attenuation_end_idx: -1,
+            color_idx: -1,
+            intensity_idx: -1,
+            amb_intensity_idx: -1,
+            amb_color_idx: -1,
+            visibility_idx: -1,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
+            unknown_tracks: Vec::new(),
+            track_order: Vec::new(),
+        }
+    }
+}
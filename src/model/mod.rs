@@ -1,5 +1,12 @@
 pub mod animation;
+pub mod attach;
+pub mod camera;
+pub mod collision;
+pub mod emitter;
+pub mod event;
 pub mod geoset;
+pub mod light;
 pub mod model;
+pub mod raw_chunk;
 pub mod skeleton;
 pub mod texture;
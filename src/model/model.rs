@@ -1,33 +1,129 @@
 use crate::material::Material;
 use crate::model::animation::Sequence;
-use crate::model::geoset::Geoset;
-use crate::model::skeleton::{AnimationController, Bone, Helper};
-use crate::model::texture::Texture;
+use crate::model::camera::Camera;
+use crate::model::collision::CollisionShape;
+use crate::model::emitter::{ParticleEmitter, ParticleEmitter2, RibbonEmitter};
+use crate::model::event::EventObject;
+use crate::model::geoset::{Geoset, GeosetAnimation};
+use crate::model::light::Light;
+use crate::model::raw_chunk::RawChunk;
+use crate::model::skeleton::{AnimationController, Attachment, Bone, Helper};
+use crate::model::texture::{Texture, TextureAnimation};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Model {
+    pub version: u32,
     pub name: String,
+    pub animation_file: String,
+    pub animation_file_padding: Vec<u8>, // MDX bytes left after the name ends, usually none
+    pub blend_time: u32,
+    // Extent of the whole model
+    pub bounds_radius: f32,
+    pub minimum_extent: [f32; 3],
+    pub maximum_extent: [f32; 3],
     pub geosets: Vec<Geoset>,
+    pub geoset_animations: Vec<GeosetAnimation>, // GEOA
     pub materials: Vec<Material>,
     pub textures: Vec<Texture>,
+    pub texture_animations: Vec<TextureAnimation>, // TXAN
     pub sequences: Vec<Sequence>,
+    pub global_sequences: Vec<u32>, // GLBS: duration of each global sequence
     pub bones: Vec<Bone>,
     pub helpers: Vec<Helper>,
+    pub lights: Vec<Light>,                        // LITE
+    pub attachments: Vec<Attachment>,              // ATCH
+    pub pivot_points: Vec<[f32; 3]>,               // PIVT: pivot of every node, indexed by ObjectID
+    pub particle_emitters: Vec<ParticleEmitter>,   // PREM
+    pub particle_emitters2: Vec<ParticleEmitter2>, // PRE2
+    pub ribbon_emitters: Vec<RibbonEmitter>,       // RIBB
+    pub cameras: Vec<Camera>,                      // CAMS
+    pub event_objects: Vec<EventObject>,           // EVTS
+    pub collision_shapes: Vec<CollisionShape>,     // CLID
     pub controllers: Vec<AnimationController>,
+    pub unknown_chunks: Vec<RawChunk>, // Top-level chunks that were not parsed, in file order
+    pub chunk_order: Vec<[u8; 4]>,     // Tags of all top-level chunks as they appeared in the file
 }
 
 impl Default for Model {
     fn default() -> Self {
         Self {
+            version: 800,
             name: String::new(),
+            animation_file: String::new(),
+            animation_file_padding: Vec::new(),
+            blend_time: 150,
+            bounds_radius: 0.0,
+            minimum_extent: [0.0, 0.0, 0.0],
+            maximum_extent: [0.0, 0.0, 0.0],
             geosets: Vec::new(),
+            geoset_animations: Vec::new(),
             materials: Vec::new(),
             textures: Vec::new(),
+            texture_animations: Vec::new(),
             sequences: Vec::new(),
+            global_sequences: Vec::new(),
             bones: Vec::new(),
             helpers: Vec::new(),
+            lights: Vec::new(),
+            attachments: Vec::new(),
+            pivot_points: Vec::new(),
+            particle_emitters: Vec::new(),
+            particle_emitters2: Vec::new(),
+            ribbon_emitters: Vec::new(),
+            cameras: Vec::new(),
+            event_objects: Vec::new(),
+            collision_shapes: Vec::new(),
             controllers: Vec::new(),
+            unknown_chunks: Vec::new(),
+            chunk_order: Vec::new(),
         }
     }
 }
+
+impl Model {
+    /// Center and radius of the model from the MODL extent. Models without one
+    /// (min == max) fall back to the union of the geoset extents
+    pub fn bounding_sphere(&self) -> Option<([f32; 3], f32)> {
+        let (min, max, radius) = if self.minimum_extent != self.maximum_extent {
+            (self.minimum_extent, self.maximum_extent, self.bounds_radius)
+        } else {
+            let mut geosets = self
+                .geosets
+                .iter()
+                .filter(|g| g.minimum_extent != g.maximum_extent);
+            let first = geosets.next()?;
+            let (min, max) = geosets.fold(
+                (first.minimum_extent, first.maximum_extent),
+                |(min, max), g| {
+                    (
+                        std::array::from_fn(|i| min[i].min(g.minimum_extent[i])),
+                        std::array::from_fn(|i| max[i].max(g.maximum_extent[i])),
+                    )
+                },
+            );
+            (min, max, 0.0)
+        };
+
+        let center: [f32; 3] = std::array::from_fn(|i| (min[i] + max[i]) / 2.0);
+        let radius = if radius > 0.0 {
+            radius
+        } else {
+            // Half of the box diagonal
+            (0..3)
+                .map(|i| (max[i] - min[i]).powi(2))
+                .sum::<f32>()
+                .sqrt()
+                / 2.0
+        };
+        Some((center, radius))
+    }
+
+    /// Pivot point of the node with the given ObjectID, the origin if PIVT has no entry for it
+    pub fn pivot_point(&self, object_id: u32) -> [f32; 3] {
+        self.pivot_points
+            .get(object_id as usize)
+            .copied()
+            .unwrap_or([0.0, 0.0, 0.0])
+    }
+}
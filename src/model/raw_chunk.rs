@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// A chunk or sub-chunk the parser does not understand, kept verbatim so that
+/// saving the model writes it back unchanged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawChunk {
+    pub tag: [u8; 4],
+    pub data: Vec<u8>, // Everything after the tag (and after the size for top-level chunks)
+}
+
+impl RawChunk {
+    /// Tag as text for display, e.g. "ATCH"
+    pub fn tag_name(&self) -> String {
+        String::from_utf8_lossy(&self.tag).into_owned()
+    }
+}
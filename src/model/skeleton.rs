@@ -1,11 +1,34 @@
+use crate::model::raw_chunk::RawChunk;
 use serde::{Deserialize, Serialize};
 
+// Node flags (see NodeFlag in mdx.hexpat). Readers take the node type bit (BONE, LIGHT,
+// EVENT...) out of `flags` and writers put it back, so `flags` only holds what can be
+// edited. A node whose file left the bit out gets `type_flag_missing` and is saved
+// without it again. Helpers have no type bit.
+pub const NODE_FLAG_HELPER: u32 = 0x0;
+pub const NODE_FLAG_DONT_INHERIT_TRANSLATION: u32 = 0x1;
+pub const NODE_FLAG_DONT_INHERIT_ROTATION: u32 = 0x2;
+pub const NODE_FLAG_DONT_INHERIT_SCALING: u32 = 0x4;
+pub const NODE_FLAG_BILLBOARDED: u32 = 0x8;
+pub const NODE_FLAG_BILLBOARDED_LOCK_X: u32 = 0x10;
+pub const NODE_FLAG_BILLBOARDED_LOCK_Y: u32 = 0x20;
+pub const NODE_FLAG_BILLBOARDED_LOCK_Z: u32 = 0x40;
+pub const NODE_FLAG_CAMERA_ANCHORED: u32 = 0x80;
+pub const NODE_FLAG_BONE: u32 = 0x100;
+pub const NODE_FLAG_LIGHT: u32 = 0x200;
+pub const NODE_FLAG_EVENT: u32 = 0x400;
+pub const NODE_FLAG_ATTACHMENT: u32 = 0x800;
+pub const NODE_FLAG_PARTICLE_EMITTER: u32 = 0x1000;
+pub const NODE_FLAG_COLLISION_SHAPE: u32 = 0x2000;
+pub const NODE_FLAG_RIBBON_EMITTER: u32 = 0x4000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bone {
     pub name: String,
     pub object_id: u32,
     pub parent_id: i32, // -1 means no parent
-    pub pivot_point: [f32; 3],
+    pub flags: u32,     // NODE_FLAG_*, without the node type bit
+    pub type_flag_missing: bool,
     pub geoset_id: Option<u32>,
     pub geoset_anim_id: Option<u32>,
     // Animation controller indices (-1 if not animated)
@@ -13,6 +36,9 @@ pub struct Bone {
     pub rotation_idx: i32,
     pub scaling_idx: i32,
     pub visibility_idx: i32,
+    pub unknown_chunks: Vec<RawChunk>, // Node track chunks that were not parsed
+    // Node track tags as loaded, empty for the usual order
+    pub node_track_order: Vec<[u8; 4]>,
 }
 
 impl Default for Bone {
@@ -21,13 +47,16 @@ impl Default for Bone {
             name: String::new(),
             object_id: 0,
             parent_id: -1,
-            pivot_point: [0.0, 0.0, 0.0],
+            flags: 0,
+            type_flag_missing: false,
             geoset_id: None,
             geoset_anim_id: None,
             translation_idx: -1,
             rotation_idx: -1,
             scaling_idx: -1,
             visibility_idx: -1,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
         }
     }
 }
@@ -37,12 +66,15 @@ pub struct Helper {
     pub name: String,
     pub object_id: u32,
     pub parent_id: i32, // -1 means no parent
-    pub pivot_point: [f32; 3],
+    pub flags: u32,     // NODE_FLAG_*
     // Animation controller indices
     pub translation_idx: i32,
     pub rotation_idx: i32,
     pub scaling_idx: i32,
     pub visibility_idx: i32,
+    pub unknown_chunks: Vec<RawChunk>, // Node track chunks that were not parsed
+    // Node track tags as loaded, empty for the usual order
+    pub node_track_order: Vec<[u8; 4]>,
 }
 
 impl Default for Helper {
@@ -51,11 +83,57 @@ impl Default for Helper {
             name: String::new(),
             object_id: 0,
             parent_id: -1,
-            pivot_point: [0.0, 0.0, 0.0],
+            flags: 0,
+            translation_idx: -1,
+            rotation_idx: -1,
+            scaling_idx: -1,
+            visibility_idx: -1,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
+        }
+    }
+}
+
+/// ATCH: named point (origin, overhead, hand right...) other models get attached to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub name: String,
+    pub object_id: u32,
+    pub parent_id: i32, // -1 means no parent
+    pub flags: u32,     // NODE_FLAG_*, without the node type bit
+    pub type_flag_missing: bool,
+    pub path: String,   // Model the game attaches here, usually empty
+    pub attachment_id: u32,
+    // Animation controller indices (-1 if not animated)
+    pub translation_idx: i32,
+    pub rotation_idx: i32,
+    pub scaling_idx: i32,
+    pub visibility_idx: i32,           // KATV
+    pub unknown_chunks: Vec<RawChunk>, // Node track chunks that were not parsed
+    // Node track tags as loaded, empty for the usual order
+    pub node_track_order: Vec<[u8; 4]>,
+    pub unknown_tracks: Vec<RawChunk>, // Attachment track chunks that were not parsed
+    pub track_order: Vec<[u8; 4]>,     // Attachment track tags as loaded, empty for the usual order
+}
+
+impl Default for Attachment {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            object_id: 0,
+            parent_id: -1,
+            flags: 0,
+            type_flag_missing: false,
+            path: String::new(),
+            attachment_id: 0,
             translation_idx: -1,
             rotation_idx: -1,
             scaling_idx: -1,
             visibility_idx: -1,
+            unknown_chunks: Vec::new(),
+            node_track_order: Vec::new(),
+            unknown_tracks: Vec::new(),
+            track_order: Vec::new(),
         }
     }
 }
@@ -1,10 +1,50 @@
+use crate::model::raw_chunk::RawChunk;
 use serde::{Deserialize, Serialize};
 
+/// TEXS flag: repeat the texture horizontally instead of clamping
+pub const TEXTURE_WRAP_WIDTH: u32 = 0x1;
+/// TEXS flag: repeat the texture vertically instead of clamping
+pub const TEXTURE_WRAP_HEIGHT: u32 = 0x2;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Texture {
     pub filename: String,
     pub replaceable_id: u32, // 0 = normal texture, 1 = team color, 2 = team glow, etc.
+    pub flags: u32,          // TEXTURE_WRAP_WIDTH | TEXTURE_WRAP_HEIGHT
     pub image_data: Option<Vec<u8>>,
     pub width: u32,
     pub height: u32,
 }
+
+impl Texture {
+    pub fn wrap_width(&self) -> bool {
+        self.flags & TEXTURE_WRAP_WIDTH != 0
+    }
+
+    pub fn wrap_height(&self) -> bool {
+        self.flags & TEXTURE_WRAP_HEIGHT != 0
+    }
+}
+
+/// TXAN: UV animation, used by layers through their texture animation id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureAnimation {
+    // Animation controller indices (-1 if not animated)
+    pub translation_idx: i32,
+    pub rotation_idx: i32,
+    pub scaling_idx: i32,
+    pub unknown_chunks: Vec<RawChunk>, // Track chunks that were not parsed
+    pub track_order: Vec<[u8; 4]>,     // Track tags as loaded, empty for the usual order
+}
+
+impl Default for TextureAnimation {
+    fn default() -> Self {
+        Self {
+            translation_idx: -1,
+            rotation_idx: -1,
+            scaling_idx: -1,
+            unknown_chunks: Vec::new(),
+            track_order: Vec::new(),
+        }
+    }
+}
@@ -1,11 +1,20 @@
 use crate::error::MdlError;
-use crate::model::geoset::{Face, Geoset, Normal, TexCoord, Vertex};
+use crate::model::geoset::{
+    Extent, Face, Geoset, Normal, PRIMITIVE_POLYGONS, PRIMITIVE_QUAD_STRIP, PRIMITIVE_QUADS,
+    PRIMITIVE_TRIANGLE_FAN, PRIMITIVE_TRIANGLE_STRIP, PRIMITIVE_TRIANGLES, TexCoord, Vertex,
+};
 use crate::model::model::Model;
+use crate::model::raw_chunk::RawChunk;
+use crate::parser::parser::read_bytes;
+use crate::verbose;
 use byteorder::{LittleEndian, ReadBytesExt};
-use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 
-pub fn geoset_parse(file: &mut File, model: &mut Model, geos_size: u32) -> Result<(), MdlError> {
+pub fn geoset_parse<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    geos_size: u32,
+) -> Result<(), MdlError> {
     let start_pos = file.stream_position()?;
     let end_pos = start_pos + geos_size as u64;
 
@@ -21,7 +30,6 @@ pub fn geoset_parse(file: &mut File, model: &mut Model, geos_size: u32) -> Resul
         }
 
         let mut geoset = Geoset::default();
-        let mut indices = Vec::new();
         let mut tag = [0u8; 4];
 
         // Read all chunks within this geoset (order not guaranteed)
@@ -55,18 +63,26 @@ pub fn geoset_parse(file: &mut File, model: &mut Model, geos_size: u32) -> Resul
                     }
                 }
                 b"PTYP" => {
-                    let count = file.read_u32::<LittleEndian>()?;
-                    file.seek(SeekFrom::Current((count * 4) as i64))?;
+                    let count = file.read_u32::<LittleEndian>()? as usize;
+                    for _ in 0..count {
+                        geoset
+                            .primitive_types
+                            .push(file.read_u32::<LittleEndian>()?);
+                    }
                 }
                 b"PCNT" => {
-                    let count = file.read_u32::<LittleEndian>()?;
-                    file.seek(SeekFrom::Current((count * 4) as i64))?;
+                    let count = file.read_u32::<LittleEndian>()? as usize;
+                    for _ in 0..count {
+                        geoset
+                            .primitive_counts
+                            .push(file.read_u32::<LittleEndian>()?);
+                    }
                 }
                 b"PVTX" => {
                     let count = file.read_u32::<LittleEndian>()? as usize;
                     for _ in 0..count {
                         let index = file.read_u16::<LittleEndian>()?;
-                        indices.push(index as u32);
+                        geoset.primitive_indices.push(index as u32);
                     }
                 }
                 b"GNDX" => {
@@ -122,8 +138,7 @@ pub fn geoset_parse(file: &mut File, model: &mut Model, geos_size: u32) -> Resul
                     geoset.selection_group = _selection_group as usize;
 
                     // Read Selectable
-                    let selectable = file.read_u32::<LittleEndian>()?;
-                    geoset.unselectable = selectable == 4; // 4 = Unselectable
+                    geoset.selectable = file.read_u32::<LittleEndian>()?;
 
                     // Read BoundsRadius
                     geoset.bounds_radius = file.read_f32::<LittleEndian>()?;
@@ -137,55 +152,66 @@ pub fn geoset_parse(file: &mut File, model: &mut Model, geos_size: u32) -> Resul
                     geoset.maximum_extent[0] = file.read_f32::<LittleEndian>()?;
                     geoset.maximum_extent[1] = file.read_f32::<LittleEndian>()?;
                     geoset.maximum_extent[2] = file.read_f32::<LittleEndian>()?;
-                    // Skip nanim and animations array
+                    // Extent of the geoset in each sequence
                     let nanim = file.read_u32::<LittleEndian>()?;
-                    file.seek(SeekFrom::Current((nanim * 28) as i64))?; // Each animation is 7 floats
+                    for _ in 0..nanim {
+                        let bounds_radius = file.read_f32::<LittleEndian>()?;
+                        let mut minimum_extent = [0.0; 3];
+                        file.read_f32_into::<LittleEndian>(&mut minimum_extent)?;
+                        let mut maximum_extent = [0.0; 3];
+                        file.read_f32_into::<LittleEndian>(&mut maximum_extent)?;
+                        geoset.sequence_extents.push(Extent {
+                            bounds_radius,
+                            minimum_extent,
+                            maximum_extent,
+                        });
+                    }
                 }
                 b"UVAS" => {
                     let uvas_count = file.read_u32::<LittleEndian>()?;
 
-                    // Read first UVBS set (primary texture coordinates)
-                    if uvas_count > 0 {
+                    // One UVBS per set: the first is the primary texture coordinates,
+                    // the others are picked by layers through their CoordId
+                    for set_index in 0..uvas_count {
                         file.read_exact(&mut tag)?;
-                        if &tag == b"UVBS" {
-                            let uvbs_count = file.read_u32::<LittleEndian>()? as usize;
-                            for _ in 0..uvbs_count {
-                                let u = file.read_f32::<LittleEndian>()?;
-                                let v = file.read_f32::<LittleEndian>()?;
-                                geoset.tex_coords.push(TexCoord { uv: [u, v] });
-                            }
+                        if &tag != b"UVBS" {
+                            // Fewer sets than announced, the tag belongs to the next sub-chunk
+                            file.seek(SeekFrom::Current(-4))?;
+                            break;
                         }
-
-                        // Skip remaining UVAS sets (secondary UVs)
-                        for _ in 1..uvas_count {
-                            file.read_exact(&mut tag)?;
-                            if &tag == b"UVBS" {
-                                let count = file.read_u32::<LittleEndian>()?;
-                                file.seek(SeekFrom::Current((count * 8) as i64))?;
-                            }
+                        let uvbs_count = file.read_u32::<LittleEndian>()? as usize;
+                        let mut tex_coords = Vec::new();
+                        for _ in 0..uvbs_count {
+                            let u = file.read_f32::<LittleEndian>()?;
+                            let v = file.read_f32::<LittleEndian>()?;
+                            tex_coords.push(TexCoord { uv: [u, v] });
+                        }
+                        if set_index == 0 {
+                            geoset.tex_coords = tex_coords;
+                        } else {
+                            geoset.secondary_tex_coords.push(tex_coords);
                         }
                     }
                 }
                 _ => {
-                    // Not a known chunk tag - could be materialId, selectionGroup, etc.
-                    // Treat as u32 value
-                    file.seek(SeekFrom::Current(-4))?; // Go back
-                    let _val = file.read_u32::<LittleEndian>()?;
+                    // Unknown sub-chunk: its size can't be known, so keep the rest
+                    // of the geoset as is and stop parsing it
+                    let rest = geoset_end.saturating_sub(file.stream_position()?);
+                    let data = read_bytes(file, rest)?;
+                    verbose!(
+                        "  Kept unparsed geoset sub-chunk {} ({} bytes)",
+                        String::from_utf8_lossy(&tag),
+                        data.len()
+                    );
+                    geoset.unknown_chunks.push(RawChunk { tag, data });
                 }
             }
         }
 
-        // Group indices into triangles
-        for chunk in indices.chunks(3) {
-            if chunk.len() == 3 {
-                geoset.faces.push(Face {
-                    vertices: [chunk[0], chunk[1], chunk[2]],
-                });
-            }
-        }
+        build_faces(&mut geoset, model.geosets.len());
 
         if !geoset.vertices.is_empty() {
-            println!(
+            verbose!(
                 "  Geoset {}: {} vertices, {} faces, {} vertex groups, {} matrix groups",
                 model.geosets.len(),
                 geoset.vertices.len(),
@@ -202,3 +228,98 @@ pub fn geoset_parse(file: &mut File, model: &mut Model, geos_size: u32) -> Resul
 
     Ok(())
 }
+
+/// Fills `faces` from the primitive groups of a geoset.
+/// PVTX is split into groups by PCNT, each with its PTYP. Without them the
+/// indices are a plain triangle list
+pub(crate) fn build_faces(geoset: &mut Geoset, geoset_index: usize) {
+    let indices = &geoset.primitive_indices;
+    if geoset.primitive_types.is_empty() && geoset.primitive_counts.is_empty() {
+        triangulate(PRIMITIVE_TRIANGLES, indices, &mut geoset.faces);
+        return;
+    }
+
+    if geoset.primitive_types.len() != geoset.primitive_counts.len() {
+        eprintln!(
+            "[mdlvis-rs] Geoset {}: {} primitive types for {} primitive counts",
+            geoset_index,
+            geoset.primitive_types.len(),
+            geoset.primitive_counts.len()
+        );
+    }
+    let mut start = 0;
+    for (&primitive_type, &count) in geoset.primitive_types.iter().zip(&geoset.primitive_counts) {
+        let count = count as usize;
+        let Some(group) = indices.get(start..start + count) else {
+            eprintln!(
+                "[mdlvis-rs] Geoset {}: primitive group of {} indices runs past PVTX ({})",
+                geoset_index,
+                count,
+                indices.len()
+            );
+            break;
+        };
+        if !triangulate(primitive_type, group, &mut geoset.faces) {
+            eprintln!(
+                "[mdlvis-rs] Geoset {}: skipped {} indices of unsupported primitive type {}",
+                geoset_index, count, primitive_type
+            );
+        }
+        start += count;
+    }
+}
+
+/// Appends the triangles of one primitive group to `faces`.
+/// Returns false for primitive types without faces (points and lines) or unknown ones
+pub(crate) fn triangulate(primitive_type: u32, indices: &[u32], faces: &mut Vec<Face>) -> bool {
+    let mut push = |a: u32, b: u32, c: u32| {
+        faces.push(Face {
+            vertices: [a, b, c],
+        });
+    };
+
+    match primitive_type {
+        PRIMITIVE_TRIANGLES => {
+            for triangle in indices.chunks_exact(3) {
+                push(triangle[0], triangle[1], triangle[2]);
+            }
+        }
+        PRIMITIVE_TRIANGLE_STRIP => {
+            // Every other triangle is flipped to keep the winding
+            for (i, window) in indices.windows(3).enumerate() {
+                // Degenerate triangles only join the runs of the strip
+                if window[0] == window[1] || window[1] == window[2] || window[0] == window[2] {
+                    continue;
+                }
+                if i % 2 == 0 {
+                    push(window[0], window[1], window[2]);
+                } else {
+                    push(window[1], window[0], window[2]);
+                }
+            }
+        }
+        PRIMITIVE_TRIANGLE_FAN | PRIMITIVE_POLYGONS => {
+            // A polygon is a single convex outline, split like a fan
+            if let Some((&center, rest)) = indices.split_first() {
+                for edge in rest.windows(2) {
+                    push(center, edge[0], edge[1]);
+                }
+            }
+        }
+        PRIMITIVE_QUADS => {
+            for quad in indices.chunks_exact(4) {
+                push(quad[0], quad[1], quad[2]);
+                push(quad[0], quad[2], quad[3]);
+            }
+        }
+        PRIMITIVE_QUAD_STRIP => {
+            // Quad i is made of vertices 2i, 2i+1, 2i+3, 2i+2
+            for window in indices.windows(4).step_by(2) {
+                push(window[0], window[1], window[3]);
+                push(window[0], window[3], window[2]);
+            }
+        }
+        _ => return false,
+    }
+    true
+}
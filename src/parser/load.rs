@@ -1,11 +1,13 @@
 use crate::error::MdlError;
 use crate::model::model::Model;
+use crate::model::raw_chunk::RawChunk;
 use crate::parser::geoset::geoset_parse;
+use crate::verbose;
 use byteorder::{LittleEndian, ReadBytesExt};
-use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Cursor, Read, Seek, SeekFrom};
 
-pub fn load(file: &mut File) -> Result<Model, MdlError> {
+/// Parse an MDX model from any seekable reader (file, buffer, archive entry)
+pub fn load<R: Read + Seek>(file: &mut R) -> Result<Model, MdlError> {
     let mut model = Model::default();
     model.name = "MDX Model".to_string();
 
@@ -19,38 +21,46 @@ pub fn load(file: &mut File) -> Result<Model, MdlError> {
 
         let size = file.read_u32::<LittleEndian>()?;
         let start_pos = file.seek(SeekFrom::Current(0))?;
+        model.chunk_order.push(chunk_type);
 
         match &chunk_type {
             b"VERS" => {
                 // Version chunk
-                let version = file.read_u32::<LittleEndian>()?;
-                println!("MDX Version: {}", version);
+                model.version = file.read_u32::<LittleEndian>()?;
+                verbose!("MDX Version: {}", model.version);
             }
             b"MODL" => {
-                // Model header - skip 8 bytes, then read 336 bytes for name
-                file.seek(SeekFrom::Current(8))?;
-                let mut name_bytes = [0u8; 336];
-                file.read_exact(&mut name_bytes)?;
-                model.name =
-                    String::from_utf8(name_bytes.into_iter().take_while(|&b| b != 0).collect())
-                        .unwrap_or_else(|_| "Unknown".to_string());
-                println!("Model name: {}", model.name.trim());
+                crate::parser::parser::read_model_info(file, &mut model)?;
+                verbose!("Model name: {}", model.name.trim());
             }
             b"GEOS" => {
                 // Geosets - this chunk contains multiple geosets
-                println!("Reading GEOS chunk, size: {}", size);
+                verbose!("Reading GEOS chunk, size: {}", size);
                 geoset_parse(file, &mut model, size)?;
-                println!("Loaded {} geosets", model.geosets.len());
+                verbose!("Loaded {} geosets", model.geosets.len());
             }
             b"SEQS" => {
                 // Sequences
                 crate::parser::parser::read_sequences(file, &mut model, size)?;
-                println!("Loaded {} sequences", model.sequences.len());
+                verbose!("Loaded {} sequences", model.sequences.len());
+            }
+            b"GLBS" => {
+                // Global sequences
+                crate::parser::parser::read_global_sequences(file, &mut model, size)?;
+                verbose!("Loaded {} global sequences", model.global_sequences.len());
             }
             b"TEXS" => {
                 // Textures
                 crate::parser::parser::read_textures(file, &mut model, size)?;
-                println!("Loaded {} textures", model.textures.len());
+                verbose!("Loaded {} textures", model.textures.len());
+            }
+            b"GEOA" => {
+                // Geoset animations
+                crate::parser::parser::read_geoset_animations(file, &mut model, size)?;
+            }
+            b"TXAN" => {
+                // Texture animations
+                crate::parser::parser::read_texture_animations(file, &mut model, size)?;
             }
             b"BONE" => {
                 // Bones
@@ -60,17 +70,58 @@ pub fn load(file: &mut File) -> Result<Model, MdlError> {
                 // Helpers
                 crate::parser::parser::read_helpers(file, &mut model, size)?;
             }
+            b"LITE" => {
+                // Lights
+                crate::parser::parser::read_lights(file, &mut model, size)?;
+            }
+            b"ATCH" => {
+                // Attachment points
+                crate::parser::parser::read_attachments(file, &mut model, size)?;
+            }
             b"PIVT" => {
                 // Pivot points
                 crate::parser::parser::read_pivots(file, &mut model, size)?;
             }
+            b"PREM" => {
+                // Particle emitters
+                crate::parser::parser::read_particle_emitters(file, &mut model, size)?;
+            }
+            b"PRE2" => {
+                // Particle emitters 2
+                crate::parser::parser::read_particle_emitters2(file, &mut model, size)?;
+            }
+            b"RIBB" => {
+                // Ribbon emitters
+                crate::parser::parser::read_ribbon_emitters(file, &mut model, size)?;
+            }
+            b"CAMS" => {
+                // Cameras
+                crate::parser::parser::read_cameras(file, &mut model, size)?;
+            }
+            b"EVTS" => {
+                // Event objects
+                crate::parser::parser::read_event_objects(file, &mut model, size)?;
+            }
+            b"CLID" => {
+                // Collision shapes
+                crate::parser::parser::read_collision_shapes(file, &mut model, size)?;
+            }
             b"MTLS" => {
                 // Materials
                 crate::parser::parser::read_materials(file, &mut model, size)?;
             }
             _ => {
-                // Skip unknown chunk
-                file.seek(SeekFrom::Current(size as i64))?;
+                // Keep unknown chunk as is so it can be saved back
+                let data = crate::parser::parser::read_bytes(file, size as u64)?;
+                verbose!(
+                    "Kept unparsed chunk {} ({} bytes)",
+                    String::from_utf8_lossy(&chunk_type),
+                    size
+                );
+                model.unknown_chunks.push(RawChunk {
+                    tag: chunk_type,
+                    data,
+                });
             }
         }
 
@@ -84,3 +135,8 @@ pub fn load(file: &mut File) -> Result<Model, MdlError> {
 
     Ok(model)
 }
+
+/// Parse an MDX model from an in-memory buffer
+pub fn load_from_bytes(bytes: &[u8]) -> Result<Model, MdlError> {
+    load(&mut Cursor::new(bytes))
+}
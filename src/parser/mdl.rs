@@ -0,0 +1,1836 @@
+use crate::error::MdlError;
+use crate::material::{FilterMode, Layer, Material, MaterialFlags, ShadingFlags};
+use crate::model::animation::Sequence;
+use crate::model::camera::Camera;
+use crate::model::collision::{
+    COLLISION_SHAPE_BOX, COLLISION_SHAPE_CYLINDER, COLLISION_SHAPE_PLANE, COLLISION_SHAPE_SPHERE,
+    CollisionShape,
+};
+use crate::model::emitter::{
+    PARTICLE_FLAG_EMITTER_USES_MDL, PARTICLE_FLAG_EMITTER_USES_TGA, PARTICLE2_BOTH,
+    PARTICLE2_FILTER_ADDITIVE, PARTICLE2_FILTER_ALPHA_KEY, PARTICLE2_FILTER_BLEND,
+    PARTICLE2_FILTER_MODULATE, PARTICLE2_FILTER_MODULATE_2X, PARTICLE2_FLAG_LINE_EMITTER,
+    PARTICLE2_FLAG_MODEL_SPACE, PARTICLE2_FLAG_SORT_PRIMS_FAR_Z, PARTICLE2_FLAG_UNFOGGED,
+    PARTICLE2_FLAG_UNSHADED, PARTICLE2_FLAG_XY_QUAD, PARTICLE2_HEAD, PARTICLE2_TAIL,
+    ParticleEmitter, ParticleEmitter2, RibbonEmitter,
+};
+use crate::model::event::EventObject;
+use crate::model::geoset::{
+    Extent, GEOSET_ANIM_COLOR, GEOSET_ANIM_DROP_SHADOW, GEOSET_UNSELECTABLE, Geoset,
+    GeosetAnimation, Normal, PRIMITIVE_LINE_LOOP, PRIMITIVE_LINE_STRIP, PRIMITIVE_LINES,
+    PRIMITIVE_POINTS, PRIMITIVE_POLYGONS, PRIMITIVE_QUAD_STRIP, PRIMITIVE_QUADS,
+    PRIMITIVE_TRIANGLE_FAN, PRIMITIVE_TRIANGLE_STRIP, PRIMITIVE_TRIANGLES, TexCoord, Vertex,
+};
+use crate::model::light::{LIGHT_TYPE_AMBIENT, LIGHT_TYPE_DIRECTIONAL, LIGHT_TYPE_OMNI, Light};
+use crate::model::model::Model;
+use crate::model::skeleton::{
+    AnimationController, Attachment, Bone, Helper, Keyframe, NODE_FLAG_BILLBOARDED,
+    NODE_FLAG_BILLBOARDED_LOCK_X, NODE_FLAG_BILLBOARDED_LOCK_Y, NODE_FLAG_BILLBOARDED_LOCK_Z,
+    NODE_FLAG_CAMERA_ANCHORED, NODE_FLAG_DONT_INHERIT_ROTATION, NODE_FLAG_DONT_INHERIT_SCALING,
+    NODE_FLAG_DONT_INHERIT_TRANSLATION,
+};
+use crate::model::texture::{TEXTURE_WRAP_HEIGHT, TEXTURE_WRAP_WIDTH, Texture, TextureAnimation};
+use crate::parser::geoset::build_faces;
+use crate::parser::tokenizer::{Token, Tokenizer};
+use crate::verbose;
+use std::io::Read;
+
+/// Parse an MDL (text) model from any reader
+pub fn load_mdl<R: Read>(file: &mut R) -> Result<Model, MdlError> {
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    load_mdl_from_str(&text)
+}
+
+/// Parse an MDL (text) model from a string
+pub fn load_mdl_from_str(text: &str) -> Result<Model, MdlError> {
+    let mut tok = Tokenizer::new(text)?;
+    let mut model = Model {
+        name: "MDL Model".to_string(),
+        ..Default::default()
+    };
+
+    while tok.peek().is_some() {
+        let key = tok.read_word()?;
+        match key.as_str() {
+            "Version" => read_version(&mut tok, &mut model)?,
+            "Model" => {
+                read_model_info(&mut tok, &mut model)?;
+                verbose!("Model name: {}", model.name);
+            }
+            "Sequences" => {
+                read_sequences(&mut tok, &mut model)?;
+                verbose!("Loaded {} sequences", model.sequences.len());
+            }
+            "GlobalSequences" => {
+                read_global_sequences(&mut tok, &mut model)?;
+                verbose!("Loaded {} global sequences", model.global_sequences.len());
+            }
+            "Textures" => {
+                read_textures(&mut tok, &mut model)?;
+                verbose!("Loaded {} textures", model.textures.len());
+            }
+            "Materials" => {
+                read_materials(&mut tok, &mut model)?;
+                verbose!("Loaded {} materials", model.materials.len());
+            }
+            "TextureAnims" => {
+                read_texture_animations(&mut tok, &mut model)?;
+                verbose!(
+                    "Loaded {} texture animations",
+                    model.texture_animations.len()
+                );
+            }
+            "Geoset" => read_geoset(&mut tok, &mut model)?,
+            "GeosetAnim" => read_geoset_animation(&mut tok, &mut model)?,
+            "Bone" => read_bone(&mut tok, &mut model)?,
+            "Helper" => read_helper(&mut tok, &mut model)?,
+            "Light" => read_light(&mut tok, &mut model)?,
+            "Attachment" => read_attachment(&mut tok, &mut model)?,
+            "ParticleEmitter" => read_particle_emitter(&mut tok, &mut model)?,
+            "ParticleEmitter2" => read_particle_emitter2(&mut tok, &mut model)?,
+            "RibbonEmitter" => read_ribbon_emitter(&mut tok, &mut model)?,
+            "Camera" => read_camera(&mut tok, &mut model)?,
+            "EventObject" => read_event_object(&mut tok, &mut model)?,
+            "CollisionShape" => read_collision_shape(&mut tok, &mut model)?,
+            "PivotPoints" => {
+                tok.read_u32()?;
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    model.pivot_points.push(tok.read_floats::<3>()?);
+                    tok.skip_comma();
+                }
+            }
+            _ => {
+                // Skip unknown block
+                tok.skip_value()?;
+            }
+        }
+    }
+
+    verbose!(
+        "Loaded {} geosets, {} bones, {} helpers, {} lights, {} attachments, {} particle emitters, {} particle emitters 2, {} ribbon emitters, {} cameras, {} event objects, {} collision shapes, {} controllers",
+        model.geosets.len(),
+        model.bones.len(),
+        model.helpers.len(),
+        model.lights.len(),
+        model.attachments.len(),
+        model.particle_emitters.len(),
+        model.particle_emitters2.len(),
+        model.ribbon_emitters.len(),
+        model.cameras.len(),
+        model.event_objects.len(),
+        model.collision_shapes.len(),
+        model.controllers.len()
+    );
+
+    Ok(model)
+}
+
+fn read_version(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    tok.expect(Token::OpenBrace)?;
+    while !tok.close_brace() {
+        match tok.read_word()?.as_str() {
+            "FormatVersion" => {
+                model.version = tok.read_u32()?;
+                tok.skip_comma();
+                verbose!("MDL Version: {}", model.version);
+            }
+            _ => tok.skip_value()?,
+        }
+    }
+    Ok(())
+}
+
+fn read_model_info(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    model.name = tok.read_string()?;
+    tok.expect(Token::OpenBrace)?;
+
+    while !tok.close_brace() {
+        match tok.read_word()?.as_str() {
+            "AnimationFile" => {
+                model.animation_file = tok.read_string()?;
+                tok.skip_comma();
+            }
+            "BlendTime" => {
+                model.blend_time = tok.read_u32()?;
+                tok.skip_comma();
+            }
+            "MinimumExtent" => {
+                model.minimum_extent = tok.read_floats::<3>()?;
+                tok.skip_comma();
+            }
+            "MaximumExtent" => {
+                model.maximum_extent = tok.read_floats::<3>()?;
+                tok.skip_comma();
+            }
+            "BoundsRadius" => {
+                model.bounds_radius = tok.read_f32()?;
+                tok.skip_comma();
+            }
+            // Counts (NumGeosets, NumBones...) are derived from the data
+            _ => tok.skip_value()?,
+        }
+    }
+
+    Ok(())
+}
+
+fn read_sequences(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    tok.read_u32()?;
+    tok.expect(Token::OpenBrace)?;
+
+    while !tok.close_brace() {
+        let key = tok.read_word()?;
+        if key != "Anim" {
+            tok.skip_value()?;
+            continue;
+        }
+
+        let mut sequence = Sequence {
+            name: tok.read_string()?,
+            ..Default::default()
+        };
+        tok.expect(Token::OpenBrace)?;
+        while !tok.close_brace() {
+            match tok.read_word()?.as_str() {
+                "Interval" => {
+                    let interval = tok.read_int_list()?;
+                    sequence.start_frame = interval.first().copied().unwrap_or(0) as u32;
+                    sequence.end_frame = interval.get(1).copied().unwrap_or(0) as u32;
+                    tok.skip_comma();
+                }
+                "NonLooping" => {
+                    sequence.non_looping = true;
+                    tok.skip_comma();
+                }
+                "MoveSpeed" => {
+                    sequence.move_speed = tok.read_f32()?;
+                    tok.skip_comma();
+                }
+                "Rarity" => {
+                    sequence.rarity = tok.read_f32()?;
+                    tok.skip_comma();
+                }
+                "MinimumExtent" => {
+                    sequence.minimum_extent = tok.read_floats::<3>()?;
+                    tok.skip_comma();
+                }
+                "MaximumExtent" => {
+                    sequence.maximum_extent = tok.read_floats::<3>()?;
+                    tok.skip_comma();
+                }
+                "BoundsRadius" => {
+                    sequence.bounds_radius = tok.read_f32()?;
+                    tok.skip_comma();
+                }
+                _ => tok.skip_value()?,
+            }
+        }
+
+        model.sequences.push(sequence);
+    }
+
+    Ok(())
+}
+
+fn read_global_sequences(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    tok.read_u32()?;
+    tok.expect(Token::OpenBrace)?;
+    while !tok.close_brace() {
+        match tok.read_word()?.as_str() {
+            "Duration" => {
+                model.global_sequences.push(tok.read_u32()?);
+                tok.skip_comma();
+            }
+            _ => tok.skip_value()?,
+        }
+    }
+    Ok(())
+}
+
+fn read_textures(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    tok.read_u32()?;
+    tok.expect(Token::OpenBrace)?;
+
+    while !tok.close_brace() {
+        let key = tok.read_word()?;
+        if key != "Bitmap" {
+            tok.skip_value()?;
+            continue;
+        }
+
+        let mut texture = Texture {
+            filename: String::new(),
+            replaceable_id: 0,
+            flags: 0,
+            image_data: None, // Will be loaded later if needed
+            width: 0,
+            height: 0,
+        };
+
+        tok.expect(Token::OpenBrace)?;
+        while !tok.close_brace() {
+            match tok.read_word()?.as_str() {
+                "Image" => {
+                    texture.filename = tok.read_string()?;
+                    tok.skip_comma();
+                }
+                "ReplaceableId" => {
+                    texture.replaceable_id = tok.read_u32()?;
+                    tok.skip_comma();
+                }
+                "WrapWidth" => {
+                    texture.flags |= TEXTURE_WRAP_WIDTH;
+                    tok.skip_comma();
+                }
+                "WrapHeight" => {
+                    texture.flags |= TEXTURE_WRAP_HEIGHT;
+                    tok.skip_comma();
+                }
+                _ => tok.skip_value()?,
+            }
+        }
+
+        model.textures.push(texture);
+    }
+
+    Ok(())
+}
+
+fn read_texture_animations(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    tok.read_u32()?;
+    tok.expect(Token::OpenBrace)?;
+
+    while !tok.close_brace() {
+        let key = tok.read_word()?;
+        if key != "TVertexAnim" {
+            tok.skip_value()?;
+            continue;
+        }
+
+        let mut texture_animation = TextureAnimation::default();
+        tok.expect(Token::OpenBrace)?;
+        while !tok.close_brace() {
+            match tok.read_word()?.as_str() {
+                "Translation" => texture_animation.translation_idx = read_track(tok, model, 3)?,
+                "Rotation" => texture_animation.rotation_idx = read_track(tok, model, 4)?,
+                "Scaling" => texture_animation.scaling_idx = read_track(tok, model, 3)?,
+                _ => tok.skip_value()?,
+            }
+        }
+
+        model.texture_animations.push(texture_animation);
+    }
+
+    Ok(())
+}
+
+fn read_materials(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    tok.read_u32()?;
+    tok.expect(Token::OpenBrace)?;
+
+    while !tok.close_brace() {
+        let key = tok.read_word()?;
+        if key != "Material" {
+            tok.skip_value()?;
+            continue;
+        }
+
+        let mut material = Material::default();
+        let mut flags = 0;
+        tok.expect(Token::OpenBrace)?;
+        while !tok.close_brace() {
+            match tok.read_word()?.as_str() {
+                "Layer" => material.layers.push(read_layer(tok, model)?),
+                "PriorityPlane" => {
+                    material.priority_plane = tok.read_i32()?;
+                    tok.skip_comma();
+                }
+                "ConstantColor" => {
+                    flags |= MaterialFlags::ConstantColor as u32;
+                    tok.skip_comma();
+                }
+                "SortPrimsFarZ" => {
+                    flags |= MaterialFlags::SortPrimsFarZ as u32;
+                    tok.skip_comma();
+                }
+                "FullResolution" => {
+                    flags |= MaterialFlags::FullResolution as u32;
+                    tok.skip_comma();
+                }
+                _ => tok.skip_value()?,
+            }
+        }
+        material.flags = flags;
+
+        model.materials.push(material);
+    }
+
+    Ok(())
+}
+
+fn read_layer(tok: &mut Tokenizer, model: &mut Model) -> Result<Layer, MdlError> {
+    let mut layer = Layer {
+        texture_id: Some(0),
+        filter_mode: FilterMode::None,
+        shading_flags: Vec::new(),
+        alpha: 1.0,
+        texture_animation_id: None,
+        coord_id: 0,
+        texture_id_idx: -1,
+        alpha_idx: -1,
+        unknown_chunks: Vec::new(),
+        track_order: Vec::new(),
+        // Initialize runtime fields
+        enabled: true,
+        alpha_override: None,
+        filter_mode_override: None,
+        shading_flags_override: None,
+    };
+
+    // Collected as bits so the flags come out in the same order as from MDX
+    let mut shading_bits = 0u32;
+
+    tok.expect(Token::OpenBrace)?;
+    while !tok.close_brace() {
+        let mut key = tok.read_word()?;
+        if key == "static" {
+            key = tok.read_word()?;
+        }
+
+        match key.as_str() {
+            "FilterMode" => {
+                let line = tok.line();
+                let name = tok.read_word()?;
+                layer.filter_mode = filter_mode_from_name(&name).ok_or_else(|| {
+                    MdlError::new("mdl-unknown-filter-mode")
+                        .with_arg("line", line)
+                        .with_arg("name", name)
+                })?;
+                tok.skip_comma();
+            }
+            "Unshaded" | "SphereEnvMap" | "TwoSided" | "Unfogged" | "NoDepthTest"
+            | "NoDepthSet" => {
+                shading_bits |= match key.as_str() {
+                    "Unshaded" => ShadingFlags::Unshaded,
+                    "SphereEnvMap" => ShadingFlags::SphereEnvMap,
+                    "TwoSided" => ShadingFlags::TwoSided,
+                    "Unfogged" => ShadingFlags::Unfogged,
+                    "NoDepthTest" => ShadingFlags::NoDepthTest,
+                    _ => ShadingFlags::NoDepthSet,
+                } as u32;
+                tok.skip_comma();
+            }
+            "TextureID" => {
+                let value = tok.read_u32()?;
+                if tok.peek() == Some(&Token::OpenBrace) {
+                    let controller = read_track_body(tok, 1)?;
+                    layer.texture_id_idx = model.controllers.len() as i32;
+                    model.controllers.push(controller);
+                } else {
+                    layer.texture_id = Some(value as usize);
+                    tok.skip_comma();
+                }
+            }
+            "TVertexAnimId" => {
+                // -1 means no texture animation
+                layer.texture_animation_id = usize::try_from(tok.read_i32()?).ok();
+                tok.skip_comma();
+            }
+            "CoordId" => {
+                layer.coord_id = tok.read_u32()?;
+                tok.skip_comma();
+            }
+            "Alpha" => {
+                let value = tok.read_f32()?;
+                if tok.peek() == Some(&Token::OpenBrace) {
+                    let controller = read_track_body(tok, 1)?;
+                    layer.alpha_idx = model.controllers.len() as i32;
+                    model.controllers.push(controller);
+                } else {
+                    layer.alpha = value;
+                    tok.skip_comma();
+                }
+            }
+            _ => tok.skip_value()?,
+        }
+    }
+
+    layer.shading_flags = ShadingFlags::from_bits(shading_bits);
+    Ok(layer)
+}
+
+fn filter_mode_from_name(name: &str) -> Option<FilterMode> {
+    Some(match name {
+        "None" => FilterMode::None,
+        "Transparent" => FilterMode::Transparent,
+        "Blend" => FilterMode::Blend,
+        "Additive" => FilterMode::Additive,
+        "AddAlpha" => FilterMode::AddAlpha,
+        "Modulate" => FilterMode::Modulate,
+        "Modulate2x" => FilterMode::Modulate2x,
+        _ => return None,
+    })
+}
+
+fn primitive_type_from_name(name: &str) -> Option<u32> {
+    Some(match name {
+        "Points" => PRIMITIVE_POINTS,
+        "Lines" => PRIMITIVE_LINES,
+        "LineLoop" => PRIMITIVE_LINE_LOOP,
+        "LineStrip" => PRIMITIVE_LINE_STRIP,
+        "Triangles" => PRIMITIVE_TRIANGLES,
+        "TriangleStrip" => PRIMITIVE_TRIANGLE_STRIP,
+        "TriangleFan" => PRIMITIVE_TRIANGLE_FAN,
+        "Quads" => PRIMITIVE_QUADS,
+        "QuadStrip" => PRIMITIVE_QUAD_STRIP,
+        "Polygons" => PRIMITIVE_POLYGONS,
+        _ => return None,
+    })
+}
+
+fn read_geoset(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut geoset = Geoset::default();
+    let mut has_tex_coords = false;
+
+    tok.expect(Token::OpenBrace)?;
+    while !tok.close_brace() {
+        match tok.read_word()?.as_str() {
+            "Vertices" => {
+                tok.read_u32()?;
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    geoset.vertices.push(Vertex {
+                        position: tok.read_floats::<3>()?,
+                    });
+                    tok.skip_comma();
+                }
+            }
+            "Normals" => {
+                tok.read_u32()?;
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    geoset.normals.push(Normal {
+                        normal: tok.read_floats::<3>()?,
+                    });
+                    tok.skip_comma();
+                }
+            }
+            "TVertices" => {
+                // The first set is the primary texture coordinates, the next ones
+                // are picked by layers through their CoordId
+                tok.read_u32()?;
+                let mut tex_coords = Vec::new();
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    tex_coords.push(TexCoord {
+                        uv: tok.read_floats::<2>()?,
+                    });
+                    tok.skip_comma();
+                }
+                if has_tex_coords {
+                    geoset.secondary_tex_coords.push(tex_coords);
+                } else {
+                    geoset.tex_coords = tex_coords;
+                    has_tex_coords = true;
+                }
+            }
+            "VertexGroup" => {
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    geoset.vertex_groups.push(tok.read_u32()? as u8);
+                    tok.skip_comma();
+                }
+            }
+            "Faces" => {
+                tok.read_u32()?;
+                tok.read_u32()?;
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    // One block per primitive group, named after its type
+                    let line = tok.line();
+                    let name = tok.read_word()?;
+                    let mut indices = Vec::new();
+                    tok.expect(Token::OpenBrace)?;
+                    while !tok.close_brace() {
+                        if tok.peek() == Some(&Token::OpenBrace) {
+                            indices.extend(tok.read_int_list()?.into_iter().map(|i| i as u32));
+                        } else {
+                            indices.push(tok.read_u32()?);
+                        }
+                        tok.skip_comma();
+                    }
+                    let Some(primitive_type) = primitive_type_from_name(&name) else {
+                        eprintln!(
+                            "[mdlvis-rs] Line {}: skipped {} indices of unknown primitive type {}",
+                            line,
+                            indices.len(),
+                            name
+                        );
+                        continue;
+                    };
+                    geoset.primitive_types.push(primitive_type);
+                    geoset.primitive_counts.push(indices.len() as u32);
+                    geoset.primitive_indices.extend(indices);
+                }
+            }
+            "Groups" => {
+                tok.read_u32()?;
+                tok.read_u32()?;
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    // Matrices
+                    tok.read_word()?;
+                    let group = tok.read_int_list()?;
+                    geoset
+                        .matrix_groups
+                        .push(group.into_iter().map(|i| i as u32).collect());
+                    tok.skip_comma();
+                }
+            }
+            "MinimumExtent" => {
+                geoset.minimum_extent = tok.read_floats::<3>()?;
+                tok.skip_comma();
+            }
+            "MaximumExtent" => {
+                geoset.maximum_extent = tok.read_floats::<3>()?;
+                tok.skip_comma();
+            }
+            "BoundsRadius" => {
+                geoset.bounds_radius = tok.read_f32()?;
+                tok.skip_comma();
+            }
+            "Anim" => {
+                let mut extent = Extent {
+                    bounds_radius: 0.0,
+                    minimum_extent: [0.0; 3],
+                    maximum_extent: [0.0; 3],
+                };
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    match tok.read_word()?.as_str() {
+                        "MinimumExtent" => {
+                            extent.minimum_extent = tok.read_floats::<3>()?;
+                            tok.skip_comma();
+                        }
+                        "MaximumExtent" => {
+                            extent.maximum_extent = tok.read_floats::<3>()?;
+                            tok.skip_comma();
+                        }
+                        "BoundsRadius" => {
+                            extent.bounds_radius = tok.read_f32()?;
+                            tok.skip_comma();
+                        }
+                        _ => tok.skip_value()?,
+                    }
+                }
+                geoset.sequence_extents.push(extent);
+            }
+            "MaterialID" => {
+                geoset.material_id = Some(tok.read_u32()? as usize);
+                tok.skip_comma();
+            }
+            "SelectionGroup" => {
+                geoset.selection_group = tok.read_u32()? as usize;
+                tok.skip_comma();
+            }
+            "Unselectable" => {
+                geoset.selectable = GEOSET_UNSELECTABLE;
+                tok.skip_comma();
+            }
+            _ => tok.skip_value()?,
+        }
+    }
+
+    build_faces(&mut geoset, model.geosets.len());
+
+    if !geoset.vertices.is_empty() {
+        verbose!(
+            "  Geoset {}: {} vertices, {} faces, {} vertex groups, {} matrix groups",
+            model.geosets.len(),
+            geoset.vertices.len(),
+            geoset.faces.len(),
+            geoset.vertex_groups.len(),
+            geoset.matrix_groups.len()
+        );
+        model.geosets.push(geoset);
+    }
+
+    Ok(())
+}
+
+fn read_geoset_animation(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut geoset_animation = GeosetAnimation::default();
+
+    tok.expect(Token::OpenBrace)?;
+    while !tok.close_brace() {
+        let mut key = tok.read_word()?;
+        if key == "static" {
+            key = tok.read_word()?;
+        }
+
+        match key.as_str() {
+            "DropShadow" => {
+                geoset_animation.flags |= GEOSET_ANIM_DROP_SHADOW;
+                tok.skip_comma();
+            }
+            "Alpha" => {
+                let value = tok.read_f32()?;
+                if tok.peek() == Some(&Token::OpenBrace) {
+                    let controller = read_track_body(tok, 1)?;
+                    geoset_animation.alpha_idx = model.controllers.len() as i32;
+                    model.controllers.push(controller);
+                } else {
+                    geoset_animation.alpha = value;
+                    tok.skip_comma();
+                }
+            }
+            "Color" => {
+                // Color is used as soon as it is present, static or animated
+                geoset_animation.flags |= GEOSET_ANIM_COLOR;
+                if tok.peek() == Some(&Token::OpenBrace) {
+                    geoset_animation.color = tok.read_floats::<3>()?;
+                    tok.skip_comma();
+                } else {
+                    geoset_animation.color_idx = read_track(tok, model, 3)?;
+                }
+            }
+            "GeosetId" => {
+                geoset_animation.geoset_id = tok.read_u32()?;
+                tok.skip_comma();
+            }
+            _ => tok.skip_value()?,
+        }
+    }
+
+    model.geoset_animations.push(geoset_animation);
+    Ok(())
+}
+
+/// Fields shared by every node type (bones, helpers...)
+struct NodeFields {
+    name: String,
+    object_id: u32,
+    parent_id: i32,
+    flags: u32,
+    translation_idx: i32,
+    rotation_idx: i32,
+    scaling_idx: i32,
+    visibility_idx: i32,
+}
+
+/// Reads a node block, handing fields that are not common to all nodes to `extra`
+fn read_node(
+    tok: &mut Tokenizer,
+    model: &mut Model,
+    mut extra: impl FnMut(&str, &mut Tokenizer) -> Result<bool, MdlError>,
+) -> Result<NodeFields, MdlError> {
+    let mut node = NodeFields {
+        name: tok.read_string()?,
+        object_id: 0,
+        parent_id: -1,
+        flags: 0,
+        translation_idx: -1,
+        rotation_idx: -1,
+        scaling_idx: -1,
+        visibility_idx: -1,
+    };
+
+    tok.expect(Token::OpenBrace)?;
+    while !tok.close_brace() {
+        let key = tok.read_word()?;
+        match key.as_str() {
+            "ObjectId" => {
+                node.object_id = tok.read_u32()?;
+                tok.skip_comma();
+            }
+            "Parent" => {
+                node.parent_id = tok.read_i32()?;
+                tok.skip_comma();
+            }
+            "Billboarded" | "BillboardedLockX" | "BillboardedLockY" | "BillboardedLockZ"
+            | "CameraAnchored" => {
+                node.flags |= match key.as_str() {
+                    "Billboarded" => NODE_FLAG_BILLBOARDED,
+                    "BillboardedLockX" => NODE_FLAG_BILLBOARDED_LOCK_X,
+                    "BillboardedLockY" => NODE_FLAG_BILLBOARDED_LOCK_Y,
+                    "BillboardedLockZ" => NODE_FLAG_BILLBOARDED_LOCK_Z,
+                    _ => NODE_FLAG_CAMERA_ANCHORED,
+                };
+                tok.skip_comma();
+            }
+            "DontInherit" => {
+                // DontInherit { Translation, Rotation, Scaling },
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    let line = tok.line();
+                    let name = tok.read_word()?;
+                    node.flags |= match name.as_str() {
+                        "Translation" => NODE_FLAG_DONT_INHERIT_TRANSLATION,
+                        "Rotation" => NODE_FLAG_DONT_INHERIT_ROTATION,
+                        "Scaling" => NODE_FLAG_DONT_INHERIT_SCALING,
+                        _ => {
+                            return Err(MdlError::new("mdl-unknown-dont-inherit")
+                                .with_arg("line", line)
+                                .with_arg("name", name));
+                        }
+                    };
+                    tok.skip_comma();
+                }
+                tok.skip_comma();
+            }
+            "Translation" => node.translation_idx = read_track(tok, model, 3)?,
+            "Rotation" => node.rotation_idx = read_track(tok, model, 4)?,
+            "Scaling" => node.scaling_idx = read_track(tok, model, 3)?,
+            "Visibility" => node.visibility_idx = read_track(tok, model, 1)?,
+            _ => {
+                if !extra(&key, tok)? {
+                    tok.skip_value()?;
+                }
+            }
+        }
+    }
+
+    Ok(node)
+}
+
+fn read_bone(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut geoset_id = None;
+    let mut geoset_anim_id = None;
+
+    let node = read_node(tok, model, |key, tok| {
+        match key {
+            // "Multiple" and "None" stand for -1
+            "GeosetId" => {
+                if !tok.accept_word("Multiple") {
+                    geoset_id = u32::try_from(tok.read_i32()?).ok();
+                }
+            }
+            "GeosetAnimId" => {
+                if !tok.accept_word("None") {
+                    geoset_anim_id = u32::try_from(tok.read_i32()?).ok();
+                }
+            }
+            _ => return Ok(false),
+        }
+        tok.skip_comma();
+        Ok(true)
+    })?;
+
+    model.bones.push(Bone {
+        name: node.name,
+        object_id: node.object_id,
+        parent_id: node.parent_id,
+        flags: node.flags,
+        type_flag_missing: false,
+        geoset_id,
+        geoset_anim_id,
+        translation_idx: node.translation_idx,
+        rotation_idx: node.rotation_idx,
+        scaling_idx: node.scaling_idx,
+        visibility_idx: node.visibility_idx,
+        unknown_chunks: Vec::new(),
+        node_track_order: Vec::new(),
+    });
+
+    Ok(())
+}
+
+fn read_helper(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let node = read_node(tok, model, |_, _| Ok(false))?;
+
+    model.helpers.push(Helper {
+        name: node.name,
+        object_id: node.object_id,
+        parent_id: node.parent_id,
+        flags: node.flags,
+        translation_idx: node.translation_idx,
+        rotation_idx: node.rotation_idx,
+        scaling_idx: node.scaling_idx,
+        visibility_idx: node.visibility_idx,
+        unknown_chunks: Vec::new(),
+        node_track_order: Vec::new(),
+    });
+
+    Ok(())
+}
+
+fn read_light(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut light = Light::default();
+    // AttenuationStart, AttenuationEnd, Color, Intensity, AmbIntensity, AmbColor, in MDX order
+    let mut tracks: [Option<AnimationController>; 6] = Default::default();
+
+    let node = read_node(tok, model, |key, tok| {
+        let key = if key == "static" {
+            tok.read_word()?
+        } else {
+            key.to_string()
+        };
+        match key.as_str() {
+            "Omnidirectional" => light.light_type = LIGHT_TYPE_OMNI,
+            "Directional" => light.light_type = LIGHT_TYPE_DIRECTIONAL,
+            "Ambient" => light.light_type = LIGHT_TYPE_AMBIENT,
+            "AttenuationStart" | "AttenuationEnd" | "Intensity" | "AmbIntensity" => {
+                // A static value, or the keyframe count of a track
+                let static_value = tok.read_f32()?;
+                let track = match key.as_str() {
+                    "AttenuationStart" => 0,
+                    "AttenuationEnd" => 1,
+                    "Intensity" => 3,
+                    _ => 4,
+                };
+                // Animated values leave the fixed MDX field at 0
+                let static_value = if tok.peek() == Some(&Token::OpenBrace) {
+                    tracks[track] = Some(read_track_body(tok, 1)?);
+                    0.0
+                } else {
+                    static_value
+                };
+                match track {
+                    0 => light.attenuation_start = static_value,
+                    1 => light.attenuation_end = static_value,
+                    3 => light.intensity = static_value,
+                    _ => light.amb_intensity = static_value,
+                }
+            }
+            "Color" | "AmbColor" => {
+                // static Color { b, g, r }, or a track of them, kept blue first
+                let (color, track) = if key == "Color" {
+                    (&mut light.color, 2)
+                } else {
+                    (&mut light.amb_color, 5)
+                };
+                if tok.peek() == Some(&Token::OpenBrace) {
+                    *color = tok.read_floats::<3>()?;
+                } else {
+                    tok.read_u32()?;
+                    tracks[track] = Some(read_track_body(tok, 3)?);
+                }
+            }
+            _ => return Ok(false),
+        }
+        tok.skip_comma();
+        Ok(true)
+    })?;
+
+    let (
+        [
+            attenuation_start_idx,
+            attenuation_end_idx,
+            color_idx,
+            intensity_idx,
+            amb_intensity_idx,
+            amb_color_idx,
+        ],
+        visibility_idx,
+    ) = push_emitter_tracks(model, node.visibility_idx, tracks);
+
+    model.lights.push(Light {
+        name: node.name,
+        object_id: node.object_id,
+        parent_id: node.parent_id,
+        flags: node.flags,
+        type_flag_missing: false,
+        translation_idx: node.translation_idx,
+        rotation_idx: node.rotation_idx,
+        scaling_idx: node.scaling_idx,
+        attenuation_start_idx,
+        attenuation_end_idx,
+        color_idx,
+        intensity_idx,
+        amb_intensity_idx,
+        amb_color_idx,
+        visibility_idx,
+        ..light
+    });
+
+    Ok(())
+}
+
+fn read_attachment(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut path = String::new();
+    let mut attachment_id = 0;
+
+    let node = read_node(tok, model, |key, tok| {
+        match key {
+            "AttachmentID" => attachment_id = tok.read_u32()?,
+            "Path" => path = tok.read_string()?,
+            _ => return Ok(false),
+        }
+        tok.skip_comma();
+        Ok(true)
+    })?;
+
+    model.attachments.push(Attachment {
+        name: node.name,
+        object_id: node.object_id,
+        parent_id: node.parent_id,
+        flags: node.flags,
+        type_flag_missing: false,
+        path,
+        attachment_id,
+        translation_idx: node.translation_idx,
+        rotation_idx: node.rotation_idx,
+        scaling_idx: node.scaling_idx,
+        visibility_idx: node.visibility_idx,
+        unknown_chunks: Vec::new(),
+        node_track_order: Vec::new(),
+        unknown_tracks: Vec::new(),
+        track_order: Vec::new(),
+    });
+
+    Ok(())
+}
+
+fn read_particle_emitter(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut emitter = ParticleEmitter::default();
+    // EmissionRate, Gravity, Longitude, Latitude, LifeSpan, InitVelocity, in MDX order
+    let mut tracks: [Option<AnimationController>; 6] = Default::default();
+
+    // A static value, or the keyframe count of a track. Animated values leave the
+    // fixed MDX field at 0
+    let mut read_value = |tok: &mut Tokenizer, key: &str| -> Result<bool, MdlError> {
+        let slot = match key {
+            "EmissionRate" => (0, &mut emitter.emission_rate),
+            "Gravity" => (1, &mut emitter.gravity),
+            "Longitude" => (2, &mut emitter.longitude),
+            "Latitude" => (3, &mut emitter.latitude),
+            "LifeSpan" => (4, &mut emitter.lifespan),
+            "InitVelocity" => (5, &mut emitter.speed),
+            _ => return Ok(false),
+        };
+        let (track, value) = slot;
+        *value = tok.read_f32()?;
+        if tok.peek() == Some(&Token::OpenBrace) {
+            tracks[track] = Some(read_track_body(tok, 1)?);
+            *value = 0.0;
+        } else {
+            tok.skip_comma();
+        }
+        Ok(true)
+    };
+    let mut flags = 0;
+    let mut path = String::new();
+
+    let node = read_node(tok, model, |key, tok| {
+        let key = if key == "static" {
+            tok.read_word()?
+        } else {
+            key.to_string()
+        };
+        if read_value(tok, &key)? {
+            return Ok(true);
+        }
+
+        match key.as_str() {
+            "EmitterUsesMDL" => flags |= PARTICLE_FLAG_EMITTER_USES_MDL,
+            "EmitterUsesTGA" => flags |= PARTICLE_FLAG_EMITTER_USES_TGA,
+            "Particle" => {
+                // Particle { static LifeSpan <f>, static InitVelocity <f>, Path "<path>" }
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    let key = tok.read_word()?;
+                    let key = if key == "static" {
+                        tok.read_word()?
+                    } else {
+                        key
+                    };
+                    if key == "Path" {
+                        path = tok.read_string()?;
+                        tok.skip_comma();
+                    } else if !read_value(tok, &key)? {
+                        tok.skip_value()?;
+                    }
+                }
+            }
+            _ => return Ok(false),
+        }
+        tok.skip_comma();
+        Ok(true)
+    })?;
+
+    let (
+        [
+            emission_rate_idx,
+            gravity_idx,
+            longitude_idx,
+            latitude_idx,
+            lifespan_idx,
+            speed_idx,
+        ],
+        visibility_idx,
+    ) = push_emitter_tracks(model, node.visibility_idx, tracks);
+
+    model.particle_emitters.push(ParticleEmitter {
+        name: node.name,
+        object_id: node.object_id,
+        parent_id: node.parent_id,
+        flags: node.flags | flags,
+        type_flag_missing: false,
+        path,
+        translation_idx: node.translation_idx,
+        rotation_idx: node.rotation_idx,
+        scaling_idx: node.scaling_idx,
+        emission_rate_idx,
+        gravity_idx,
+        longitude_idx,
+        latitude_idx,
+        lifespan_idx,
+        speed_idx,
+        visibility_idx,
+        ..emitter
+    });
+
+    Ok(())
+}
+
+fn read_particle_emitter2(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut emitter = ParticleEmitter2::default();
+    // Speed, Variation, Latitude, Gravity, EmissionRate, Length, Width, in MDX order
+    let mut tracks: [Option<AnimationController>; 7] = Default::default();
+
+    let node = read_node(tok, model, |key, tok| {
+        let key = if key == "static" {
+            tok.read_word()?
+        } else {
+            key.to_string()
+        };
+        let slot = match key.as_str() {
+            "Speed" => Some((0, &mut emitter.speed)),
+            "Variation" => Some((1, &mut emitter.variation)),
+            "Latitude" => Some((2, &mut emitter.latitude)),
+            "Gravity" => Some((3, &mut emitter.gravity)),
+            "EmissionRate" => Some((4, &mut emitter.emission_rate)),
+            "Length" => Some((5, &mut emitter.length)),
+            "Width" => Some((6, &mut emitter.width)),
+            _ => None,
+        };
+        if let Some((track, value)) = slot {
+            // A static value, or the keyframe count of a track
+            let static_value = tok.read_f32()?;
+            if tok.peek() == Some(&Token::OpenBrace) {
+                tracks[track] = Some(read_track_body(tok, 1)?);
+            } else {
+                *value = static_value;
+                tok.skip_comma();
+            }
+            return Ok(true);
+        }
+
+        match key.as_str() {
+            "SortPrimsFarZ" => emitter.flags |= PARTICLE2_FLAG_SORT_PRIMS_FAR_Z,
+            "Unshaded" => emitter.flags |= PARTICLE2_FLAG_UNSHADED,
+            "LineEmitter" => emitter.flags |= PARTICLE2_FLAG_LINE_EMITTER,
+            "Unfogged" => emitter.flags |= PARTICLE2_FLAG_UNFOGGED,
+            "ModelSpace" => emitter.flags |= PARTICLE2_FLAG_MODEL_SPACE,
+            "XYQuad" => emitter.flags |= PARTICLE2_FLAG_XY_QUAD,
+            "Squirt" => emitter.squirt = true,
+            "Blend" => emitter.filter_mode = PARTICLE2_FILTER_BLEND,
+            "Additive" => emitter.filter_mode = PARTICLE2_FILTER_ADDITIVE,
+            "Modulate" => emitter.filter_mode = PARTICLE2_FILTER_MODULATE,
+            "Modulate2x" => emitter.filter_mode = PARTICLE2_FILTER_MODULATE_2X,
+            "AlphaKey" => emitter.filter_mode = PARTICLE2_FILTER_ALPHA_KEY,
+            "Head" => emitter.head_or_tail = PARTICLE2_HEAD,
+            "Tail" => emitter.head_or_tail = PARTICLE2_TAIL,
+            "Both" => emitter.head_or_tail = PARTICLE2_BOTH,
+            "LifeSpan" => emitter.lifespan = tok.read_f32()?,
+            "TailLength" => emitter.tail_length = tok.read_f32()?,
+            "Time" => emitter.time = tok.read_f32()?,
+            "Rows" => emitter.rows = tok.read_u32()?,
+            "Columns" => emitter.columns = tok.read_u32()?,
+            "TextureID" => emitter.texture_id = tok.read_u32()?,
+            "ReplaceableId" => emitter.replaceable_id = tok.read_u32()?,
+            "PriorityPlane" => emitter.priority_plane = tok.read_i32()?,
+            "SegmentColor" => {
+                // SegmentColor { Color { b, g, r }, ... }, kept blue first
+                tok.expect(Token::OpenBrace)?;
+                let mut segment = 0;
+                while !tok.close_brace() {
+                    tok.read_word()?;
+                    let values = tok.read_floats::<3>()?;
+                    if let Some(color) = emitter.segment_colors.get_mut(segment) {
+                        *color = values;
+                    }
+                    segment += 1;
+                    tok.skip_comma();
+                }
+            }
+            "Alpha" => emitter.segment_alpha = tok.read_floats::<3>()?.map(|a| a as u8),
+            "ParticleScaling" => emitter.segment_scaling = tok.read_floats::<3>()?,
+            "LifeSpanUVAnim" | "DecayUVAnim" | "TailUVAnim" | "TailDecayUVAnim" => {
+                let interval = tok.read_floats::<3>()?.map(|v| v as u32);
+                match key.as_str() {
+                    "LifeSpanUVAnim" => emitter.head_intervals = interval,
+                    "DecayUVAnim" => emitter.head_decay_intervals = interval,
+                    "TailUVAnim" => emitter.tail_intervals = interval,
+                    _ => emitter.tail_decay_intervals = interval,
+                }
+            }
+            _ => return Ok(false),
+        }
+        tok.skip_comma();
+        Ok(true)
+    })?;
+
+    let (
+        [
+            speed_idx,
+            variation_idx,
+            latitude_idx,
+            gravity_idx,
+            emission_rate_idx,
+            length_idx,
+            width_idx,
+        ],
+        visibility_idx,
+    ) = push_emitter_tracks(model, node.visibility_idx, tracks);
+
+    model.particle_emitters2.push(ParticleEmitter2 {
+        name: node.name,
+        object_id: node.object_id,
+        parent_id: node.parent_id,
+        flags: node.flags | emitter.flags,
+        type_flag_missing: false,
+        translation_idx: node.translation_idx,
+        rotation_idx: node.rotation_idx,
+        scaling_idx: node.scaling_idx,
+        speed_idx,
+        variation_idx,
+        latitude_idx,
+        gravity_idx,
+        emission_rate_idx,
+        length_idx,
+        width_idx,
+        visibility_idx,
+        ..emitter
+    });
+
+    Ok(())
+}
+
+fn read_ribbon_emitter(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut emitter = RibbonEmitter::default();
+    // HeightAbove, HeightBelow, Alpha, Color, TextureSlot, in MDX order
+    let mut tracks: [Option<AnimationController>; 5] = Default::default();
+
+    let node = read_node(tok, model, |key, tok| {
+        let key = if key == "static" {
+            tok.read_word()?
+        } else {
+            key.to_string()
+        };
+        match key.as_str() {
+            "HeightAbove" | "HeightBelow" | "Alpha" | "TextureSlot" => {
+                // A static value, or the keyframe count of a track
+                let static_value = tok.read_f32()?;
+                let track = match key.as_str() {
+                    "HeightAbove" => 0,
+                    "HeightBelow" => 1,
+                    "Alpha" => 2,
+                    _ => 4,
+                };
+                // Animated values leave the fixed MDX field at 0
+                let static_value = if tok.peek() == Some(&Token::OpenBrace) {
+                    tracks[track] = Some(read_track_body(tok, 1)?);
+                    0.0
+                } else {
+                    static_value
+                };
+                match track {
+                    0 => emitter.height_above = static_value,
+                    1 => emitter.height_below = static_value,
+                    2 => emitter.alpha = static_value,
+                    _ => emitter.texture_slot = static_value as u32,
+                }
+            }
+            "Color" => {
+                // static Color { b, g, r }, or a track of them, kept blue first
+                if tok.peek() == Some(&Token::OpenBrace) {
+                    emitter.color = tok.read_floats::<3>()?;
+                } else {
+                    tok.read_u32()?;
+                    tracks[3] = Some(read_track_body(tok, 3)?);
+                }
+            }
+            "EmissionRate" => emitter.emission_rate = tok.read_u32()?,
+            "LifeSpan" => emitter.lifespan = tok.read_f32()?,
+            "Gravity" => emitter.gravity = tok.read_f32()?,
+            "Rows" => emitter.rows = tok.read_u32()?,
+            "Columns" => emitter.columns = tok.read_u32()?,
+            "MaterialID" => emitter.material_id = tok.read_u32()?,
+            _ => return Ok(false),
+        }
+        tok.skip_comma();
+        Ok(true)
+    })?;
+
+    let (
+        [
+            height_above_idx,
+            height_below_idx,
+            alpha_idx,
+            color_idx,
+            texture_slot_idx,
+        ],
+        visibility_idx,
+    ) = push_emitter_tracks(model, node.visibility_idx, tracks);
+
+    model.ribbon_emitters.push(RibbonEmitter {
+        name: node.name,
+        object_id: node.object_id,
+        parent_id: node.parent_id,
+        flags: node.flags | emitter.flags,
+        type_flag_missing: false,
+        translation_idx: node.translation_idx,
+        rotation_idx: node.rotation_idx,
+        scaling_idx: node.scaling_idx,
+        height_above_idx,
+        height_below_idx,
+        alpha_idx,
+        color_idx,
+        texture_slot_idx,
+        visibility_idx,
+        ..emitter
+    });
+
+    Ok(())
+}
+
+fn read_camera(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut camera = Camera {
+        name: tok.read_string()?,
+        ..Default::default()
+    };
+
+    tok.expect(Token::OpenBrace)?;
+    while !tok.close_brace() {
+        match tok.read_word()?.as_str() {
+            "Position" => {
+                camera.position = tok.read_floats::<3>()?;
+                tok.skip_comma();
+            }
+            "FieldOfView" => {
+                camera.field_of_view = tok.read_f32()?;
+                tok.skip_comma();
+            }
+            "FarClip" => {
+                camera.far_clip = tok.read_f32()?;
+                tok.skip_comma();
+            }
+            "NearClip" => {
+                camera.near_clip = tok.read_f32()?;
+                tok.skip_comma();
+            }
+            "Translation" => camera.translation_idx = read_track(tok, model, 3)?,
+            "Rotation" => camera.rotation_idx = read_track(tok, model, 1)?,
+            "Target" => {
+                // Target { Position { x, y, z }, Translation <track> }
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    match tok.read_word()?.as_str() {
+                        "Position" => {
+                            camera.target_position = tok.read_floats::<3>()?;
+                            tok.skip_comma();
+                        }
+                        "Translation" => camera.target_translation_idx = read_track(tok, model, 3)?,
+                        _ => tok.skip_value()?,
+                    }
+                }
+                tok.skip_comma();
+            }
+            _ => tok.skip_value()?,
+        }
+    }
+
+    model.cameras.push(camera);
+    Ok(())
+}
+
+fn read_event_object(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut has_track = false;
+    let mut global_seq_id = -1;
+    let mut frames = Vec::new();
+
+    let node = read_node(tok, model, |key, tok| {
+        if key != "EventTrack" {
+            return Ok(false);
+        }
+        // EventTrack <count> { GlobalSeqId <id>, <frame>, ... }
+        has_track = true;
+        tok.read_u32()?;
+        tok.expect(Token::OpenBrace)?;
+        while !tok.close_brace() {
+            if tok.accept_word("GlobalSeqId") {
+                global_seq_id = tok.read_i32()?;
+            } else {
+                frames.push(tok.read_u32()?);
+            }
+            tok.skip_comma();
+        }
+        Ok(true)
+    })?;
+
+    model.event_objects.push(EventObject {
+        name: node.name,
+        object_id: node.object_id,
+        parent_id: node.parent_id,
+        flags: node.flags,
+        type_flag_missing: false,
+        has_track,
+        global_seq_id,
+        frames,
+        translation_idx: node.translation_idx,
+        rotation_idx: node.rotation_idx,
+        scaling_idx: node.scaling_idx,
+        unknown_chunks: Vec::new(),
+        node_track_order: Vec::new(),
+    });
+
+    Ok(())
+}
+
+fn read_collision_shape(tok: &mut Tokenizer, model: &mut Model) -> Result<(), MdlError> {
+    let mut shape_type = COLLISION_SHAPE_BOX;
+    let mut vertices = Vec::new();
+    let mut radius = 0.0;
+
+    let node = read_node(tok, model, |key, tok| {
+        match key {
+            "Box" => shape_type = COLLISION_SHAPE_BOX,
+            "Plane" => shape_type = COLLISION_SHAPE_PLANE,
+            "Sphere" => shape_type = COLLISION_SHAPE_SPHERE,
+            "Cylinder" => shape_type = COLLISION_SHAPE_CYLINDER,
+            "Vertices" => {
+                // Vertices <count> { { x, y, z }, ... }
+                tok.read_u32()?;
+                tok.expect(Token::OpenBrace)?;
+                while !tok.close_brace() {
+                    vertices.push(tok.read_floats::<3>()?);
+                    tok.skip_comma();
+                }
+            }
+            "BoundsRadius" => radius = tok.read_f32()?,
+            _ => return Ok(false),
+        }
+        tok.skip_comma();
+        Ok(true)
+    })?;
+
+    model.collision_shapes.push(CollisionShape {
+        name: node.name,
+        object_id: node.object_id,
+        parent_id: node.parent_id,
+        flags: node.flags,
+        type_flag_missing: false,
+        shape_type,
+        vertices,
+        radius,
+        translation_idx: node.translation_idx,
+        rotation_idx: node.rotation_idx,
+        scaling_idx: node.scaling_idx,
+        unknown_chunks: Vec::new(),
+        node_track_order: Vec::new(),
+    });
+
+    Ok(())
+}
+
+/// Adds the emitter tracks of a node to `model.controllers` and returns their indices,
+/// with the index of the visibility track last.
+/// MDX keeps the emitter tracks between the node tracks and the visibility track,
+/// which is the last block of the emitter, so visibility is moved after them
+fn push_emitter_tracks<const N: usize>(
+    model: &mut Model,
+    visibility_idx: i32,
+    tracks: [Option<AnimationController>; N],
+) -> ([i32; N], i32) {
+    let visibility =
+        if visibility_idx >= 0 && visibility_idx as usize + 1 == model.controllers.len() {
+            model.controllers.pop()
+        } else {
+            None
+        };
+    let track_idx = tracks.map(|controller| match controller {
+        Some(controller) => {
+            model.controllers.push(controller);
+            model.controllers.len() as i32 - 1
+        }
+        None => -1,
+    });
+    let visibility_idx = match visibility {
+        Some(controller) => {
+            model.controllers.push(controller);
+            model.controllers.len() as i32 - 1
+        }
+        None => visibility_idx,
+    };
+    (track_idx, visibility_idx)
+}
+
+/// Reads a keyframe track `<count> { <interp>, GlobalSeqId <id>, <frame>: <values>, ... }`
+/// into `model.controllers` and returns its index
+fn read_track(
+    tok: &mut Tokenizer,
+    model: &mut Model,
+    element_size: usize,
+) -> Result<i32, MdlError> {
+    // Keyframe count, the keyframes themselves are counted while reading
+    tok.read_u32()?;
+    let controller = read_track_body(tok, element_size)?;
+
+    let controller_idx = model.controllers.len() as i32;
+    model.controllers.push(controller);
+    Ok(controller_idx)
+}
+
+/// Reads the `{ ... }` part of a keyframe track whose count was already read
+fn read_track_body(
+    tok: &mut Tokenizer,
+    element_size: usize,
+) -> Result<AnimationController, MdlError> {
+    tok.expect(Token::OpenBrace)?;
+
+    let line = tok.line();
+    let interpolation_type = match tok.read_word()?.as_str() {
+        "DontInterp" => 0,
+        "Linear" => 1,
+        "Hermite" => 2,
+        "Bezier" => 3,
+        other => {
+            return Err(MdlError::new("mdl-unknown-interpolation")
+                .with_arg("line", line)
+                .with_arg("name", other));
+        }
+    };
+    tok.skip_comma();
+
+    let mut global_seq_id = -1;
+    let mut keyframes = Vec::new();
+
+    while !tok.close_brace() {
+        if tok.accept_word("GlobalSeqId") {
+            global_seq_id = tok.read_i32()?;
+            tok.skip_comma();
+            continue;
+        }
+
+        let frame = tok.read_i32()?;
+        tok.expect(Token::Colon)?;
+        let data = read_values(tok, element_size)?;
+        tok.skip_comma();
+
+        // Tangents are present only for Hermite (2) and Bezier (3)
+        let (in_tan, out_tan) = if interpolation_type == 2 || interpolation_type == 3 {
+            if tok.read_word()? != "InTan" {
+                return Err(tok.error("InTan"));
+            }
+            let in_tan = read_values(tok, element_size)?;
+            tok.skip_comma();
+            if tok.read_word()? != "OutTan" {
+                return Err(tok.error("OutTan"));
+            }
+            let out_tan = read_values(tok, element_size)?;
+            tok.skip_comma();
+            (in_tan, out_tan)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        keyframes.push(Keyframe {
+            frame,
+            data,
+            in_tan,
+            out_tan,
+        });
+    }
+
+    Ok(AnimationController {
+        interpolation_type,
+        global_seq_id,
+        keyframes,
+    })
+}
+
+/// Reads a single number, or `{ ... }` with `element_size` numbers
+fn read_values(tok: &mut Tokenizer, element_size: usize) -> Result<Vec<f32>, MdlError> {
+    let line = tok.line();
+    let values = if tok.peek() == Some(&Token::OpenBrace) {
+        tok.read_float_list()?
+    } else {
+        vec![tok.read_f32()?]
+    };
+
+    if values.len() != element_size {
+        return Err(MdlError::new("mdl-syntax-error")
+            .with_arg("line", line)
+            .with_arg("expected", format!("{} values", element_size))
+            .with_arg("found", format!("{} values", values.len())));
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::load_mdl_from_str;
+    use crate::model::collision::COLLISION_SHAPE_CYLINDER;
+    use crate::model::geoset::{PRIMITIVE_POINTS, PRIMITIVE_TRIANGLE_STRIP};
+    use crate::model::light::LIGHT_TYPE_AMBIENT;
+    use crate::model::model::Model;
+    use crate::model::skeleton::NODE_FLAG_BILLBOARDED;
+    use crate::parser::load::load_from_bytes;
+    use crate::writer::mdl::save_mdl_to_string;
+    use crate::writer::save::save_to_bytes;
+
+    /// One node of each kind hanging from a bone, written the way Blizzard MDLs are
+    const NODES: &str = r#"
+Version {
+	FormatVersion 800,
+}
+Model "Fixture" {
+	BlendTime 150,
+}
+Sequences 1 {
+	Anim "Stand" {
+		Interval { 333, 1333 },
+	}
+}
+Geoset {
+	Vertices 4 {
+		{ 0, 0, 0 },
+		{ 10, 0, 0 },
+		{ 0, 10, 0 },
+		{ 10, 10, 0 },
+	}
+	VertexGroup {
+		0,
+		0,
+		0,
+		0,
+	}
+	Faces 2 5 {
+		TriangleStrip {
+			{ 0, 1, 2, 3 },
+		}
+		Points {
+			{ 3 },
+		}
+	}
+	Groups 1 1 {
+		Matrices { 0 },
+	}
+	MaterialID 0,
+	SelectionGroup 0,
+}
+Bone "Root" {
+	ObjectId 0,
+	GeosetId Multiple,
+	GeosetAnimId None,
+}
+Light "Glow" {
+	ObjectId 1,
+	Parent 0,
+	Ambient,
+	static AttenuationStart 80,
+	static AttenuationEnd 200,
+	Color 2 {
+		Linear,
+		333: { 0, 0, 1 },
+		1333: { 1, 0, 0 },
+	}
+	static Intensity 1.5,
+	static AmbColor { 0.5, 0.5, 0.5 },
+	static AmbIntensity 0.2,
+}
+Attachment "Chest Ref" {
+	ObjectId 2,
+	Parent 0,
+	AttachmentID 4,
+	Visibility 1 {
+		DontInterp,
+		333: 0,
+	}
+}
+PivotPoints 7 {
+	{ 0, 0, 0 },
+	{ 0, 0, 50 },
+	{ 0, 10, 60 },
+	{ 0, 0, 40 },
+	{ 0, 0, 80 },
+	{ 0, 0, 0 },
+	{ 0, 0, 0 },
+}
+ParticleEmitter "Sparks" {
+	ObjectId 3,
+	Parent 0,
+	EmitterUsesMDL,
+	static EmissionRate 12,
+	static Gravity 9.8,
+	static Longitude 0.1,
+	static Latitude 0.2,
+	Particle {
+		static LifeSpan 1.5,
+		static InitVelocity 100,
+		Path "Objects\Spawnmodels\Spark.mdl",
+	}
+}
+RibbonEmitter "Trail" {
+	ObjectId 4,
+	Parent 0,
+	static HeightAbove 20,
+	static HeightBelow 10,
+	static Alpha 0.8,
+	static Color { 1, 0.5, 0 },
+	static TextureSlot 0,
+	EmissionRate 30,
+	LifeSpan 0.6,
+	Rows 2,
+	Columns 1,
+	MaterialID 0,
+	Gravity 50,
+}
+Camera "Portrait" {
+	Position { 120, 0, 80 },
+	FieldOfView 0.75,
+	FarClip 1000,
+	NearClip 8,
+	Target {
+		Position { 0, 0, 60 },
+	}
+}
+EventObject "SNDxDEAT" {
+	ObjectId 5,
+	Parent 0,
+	Billboarded,
+	EventTrack 2 {
+		400,
+		900,
+	}
+}
+CollisionShape "Body" {
+	ObjectId 6,
+	Cylinder,
+	Vertices 2 {
+		{ 0, 0, 0 },
+		{ 0, 0, 90 },
+	}
+	BoundsRadius 30,
+}
+"#;
+
+    #[test]
+    fn reads_nodes() {
+        let model = load_mdl_from_str(NODES).unwrap();
+        assert_eq!(model.name, "Fixture");
+        assert_eq!(model.pivot_points[2], [0.0, 10.0, 60.0]);
+
+        // Primitive groups stay as written, only the strip has faces
+        let geoset = &model.geosets[0];
+        assert_eq!(
+            geoset.primitive_types,
+            [PRIMITIVE_TRIANGLE_STRIP, PRIMITIVE_POINTS]
+        );
+        assert_eq!(geoset.primitive_counts, [4, 1]);
+        assert_eq!(geoset.primitive_indices, [0, 1, 2, 3, 3]);
+        assert_eq!(geoset.faces.len(), 2);
+
+        let light = &model.lights[0];
+        assert_eq!(light.light_type, LIGHT_TYPE_AMBIENT);
+        assert_eq!(
+            (light.attenuation_start, light.attenuation_end),
+            (80.0, 200.0)
+        );
+        // Colors stay blue first, as written
+        let color = &model.controllers[light.color_idx as usize];
+        assert_eq!(color.keyframes[1].data, [1.0, 0.0, 0.0]);
+        assert_eq!(light.amb_color, [0.5, 0.5, 0.5]);
+
+        let attachment = &model.attachments[0];
+        assert_eq!((attachment.object_id, attachment.parent_id), (2, 0));
+        assert_eq!(attachment.attachment_id, 4);
+        let visibility = &model.controllers[attachment.visibility_idx as usize];
+        assert_eq!(visibility.interpolation_type, 0);
+
+        let sparks = &model.particle_emitters[0];
+        assert_eq!(sparks.path, "Objects\\Spawnmodels\\Spark.mdl");
+        assert_eq!((sparks.lifespan, sparks.speed), (1.5, 100.0));
+
+        let trail = &model.ribbon_emitters[0];
+        assert_eq!(trail.flags, 0);
+        assert_eq!(trail.color, [1.0, 0.5, 0.0]);
+        assert_eq!(
+            (trail.emission_rate, trail.rows, trail.gravity),
+            (30, 2, 50.0)
+        );
+
+        let camera = &model.cameras[0];
+        assert_eq!(camera.position, [120.0, 0.0, 80.0]);
+        assert_eq!(camera.target_position, [0.0, 0.0, 60.0]);
+
+        let event = &model.event_objects[0];
+        assert_eq!(event.flags, NODE_FLAG_BILLBOARDED);
+        assert_eq!(event.frames, [400, 900]);
+
+        let body = &model.collision_shapes[0];
+        assert_eq!(body.shape_type, COLLISION_SHAPE_CYLINDER);
+        assert_eq!(body.vertices, [[0.0, 0.0, 0.0], [0.0, 0.0, 90.0]]);
+        assert_eq!(body.radius, 30.0);
+    }
+
+    #[test]
+    fn written_text_reads_back_the_same() {
+        let model = load_mdl_from_str(NODES).unwrap();
+        let again = load_mdl_from_str(&save_mdl_to_string(&model)).unwrap();
+        assert_eq!(format!("{:?}", again), format!("{:?}", model));
+    }
+
+    #[test]
+    fn names_with_quotes_and_backslashes() {
+        let mut model = load_mdl_from_str(NODES).unwrap();
+        model.name = "Say \"hi\"".to_string();
+        model.bones[0].name = "Arm\\Left".to_string();
+        model.lights[0].name = "Dir\\".to_string();
+        model.animation_file = "a\\\\b\\\"c".to_string();
+
+        let text = save_mdl_to_string(&model);
+        // Paths are written as other tools expect them
+        assert!(text.contains("Path \"Objects\\Spawnmodels\\Spark.mdl\""));
+        let again = load_mdl_from_str(&text).unwrap();
+        assert_eq!(format!("{:?}", again), format!("{:?}", model));
+    }
+
+    #[test]
+    fn mdx_keeps_what_the_text_holds() {
+        let model = load_mdl_from_str(NODES).unwrap();
+        let mdx = load_from_bytes(&save_to_bytes(&model).unwrap()).unwrap();
+
+        // The MDX remembers its chunk layout, the text does not
+        let without_layout = |model: Model| Model {
+            chunk_order: Vec::new(),
+            ..model
+        };
+        assert_eq!(
+            format!("{:?}", without_layout(mdx)),
+            format!("{:?}", without_layout(model))
+        );
+    }
+
+    #[test]
+    fn out_of_range_integer_is_an_error() {
+        let read =
+            |id: &str| load_mdl_from_str(&format!("Bone \"Root\" {{\n\tObjectId {id},\n}}\n"));
+        assert_eq!(read("4294967295").unwrap().bones[0].object_id, u32::MAX);
+        let error = read("4294967296").unwrap_err();
+        assert_eq!(error.key, "mdl-syntax-error");
+        assert_eq!(error.args["found"], "4294967296");
+        assert!(read("-2147483649").is_err());
+    }
+
+    #[test]
+    fn unknown_interpolation_is_an_error() {
+        let text = "Bone \"Root\" {\n\tObjectId 0,\n\tTranslation 1 {\n\t\tSpline,\n\t}\n}\n";
+        let error = load_mdl_from_str(text).unwrap_err();
+        assert_eq!(error.key, "mdl-unknown-interpolation");
+        assert_eq!(error.args["line"], "4");
+    }
+}
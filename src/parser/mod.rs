@@ -1,3 +1,5 @@
 pub mod parser;
 pub mod load;
-mod geoset;
\ No newline at end of file
+pub mod mdl;
+mod geoset;
+mod tokenizer;
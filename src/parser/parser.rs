@@ -1,15 +1,98 @@
 use crate::error::MdlError;
 use crate::material::{FilterMode, Layer, Material, ShadingFlags};
 use crate::model::animation::Sequence;
+use crate::model::camera::Camera;
+use crate::model::collision::{COLLISION_SHAPE_CYLINDER, CollisionShape};
+use crate::model::emitter::{ParticleEmitter, ParticleEmitter2, RibbonEmitter};
+use crate::model::event::EventObject;
+use crate::model::geoset::GeosetAnimation;
+use crate::model::light::Light;
 use crate::model::model::Model;
-use crate::model::skeleton::{AnimationController, Bone, Helper, Keyframe};
-use crate::model::texture::Texture;
+use crate::model::raw_chunk::RawChunk;
+use crate::model::skeleton::{
+    AnimationController, Attachment, Bone, Helper, Keyframe, NODE_FLAG_ATTACHMENT, NODE_FLAG_BONE,
+    NODE_FLAG_COLLISION_SHAPE, NODE_FLAG_EVENT, NODE_FLAG_LIGHT, NODE_FLAG_PARTICLE_EMITTER,
+    NODE_FLAG_RIBBON_EMITTER,
+};
+use crate::model::texture::{Texture, TextureAnimation};
+use crate::verbose;
 use byteorder::{LittleEndian, ReadBytesExt};
-use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 
-pub(crate) fn read_sequences(
-    file: &mut File,
+/// Read a zero-padded string of fixed length
+fn read_fixed_string<R: Read>(file: &mut R, len: usize) -> Result<String, MdlError> {
+    let mut bytes = vec![0u8; len];
+    file.read_exact(&mut bytes)?;
+    Ok(
+        String::from_utf8(bytes.into_iter().take_while(|&b| b != 0).collect())
+            .unwrap_or_else(|_| "Unknown".to_string()),
+    )
+}
+
+/// Read a zero-padded string of fixed length, along with whatever follows its end.
+/// The padding is empty when the rest of the field is all zeros
+fn read_padded_string<R: Read>(file: &mut R, len: usize) -> Result<(String, Vec<u8>), MdlError> {
+    let mut bytes = vec![0u8; len];
+    file.read_exact(&mut bytes)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+    let mut padding = bytes.split_off((end + 1).min(len));
+    while padding.last() == Some(&0) {
+        padding.pop();
+    }
+    bytes.truncate(end);
+    let name = String::from_utf8(bytes).unwrap_or_else(|_| "Unknown".to_string());
+    Ok((name, padding))
+}
+
+/// Read `len` bytes, a size that comes from the file: the buffer only grows with what is
+/// really there, so a broken size ends in an error instead of a huge allocation
+pub(crate) fn read_bytes<R: Read>(file: &mut R, len: u64) -> Result<Vec<u8>, MdlError> {
+    let mut data = Vec::new();
+    file.by_ref().take(len).read_to_end(&mut data)?;
+    if (data.len() as u64) < len {
+        return Err(MdlError::new("mdx-unexpected-end")
+            .with_arg("expected", len)
+            .with_arg("found", data.len()));
+    }
+    Ok(data)
+}
+
+/// Read N consecutive floats
+fn read_f32_array<R: Read, const N: usize>(file: &mut R) -> Result<[f32; N], MdlError> {
+    let mut values = [0.0; N];
+    for value in &mut values {
+        *value = file.read_f32::<LittleEndian>()?;
+    }
+    Ok(values)
+}
+
+/// Read N consecutive u32 values
+fn read_u32_array<R: Read, const N: usize>(file: &mut R) -> Result<[u32; N], MdlError> {
+    let mut values = [0; N];
+    for value in &mut values {
+        *value = file.read_u32::<LittleEndian>()?;
+    }
+    Ok(values)
+}
+
+pub(crate) fn read_model_info<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+) -> Result<(), MdlError> {
+    model.name = read_fixed_string(file, 0x50)?;
+    (model.animation_file, model.animation_file_padding) = read_padded_string(file, 0x104)?;
+
+    // Extent: bounds radius, min, max
+    model.bounds_radius = file.read_f32::<LittleEndian>()?;
+    file.read_f32_into::<LittleEndian>(&mut model.minimum_extent)?;
+    file.read_f32_into::<LittleEndian>(&mut model.maximum_extent)?;
+    model.blend_time = file.read_u32::<LittleEndian>()?;
+
+    Ok(())
+}
+
+pub(crate) fn read_sequences<R: Read + Seek>(
+    file: &mut R,
     model: &mut Model,
     size: u32,
 ) -> Result<(), MdlError> {
@@ -17,7 +100,7 @@ pub(crate) fn read_sequences(
     const SEQUENCE_SIZE: u32 = 0x50 + 13 * 4; // 132 bytes
 
     let count = size / SEQUENCE_SIZE;
-    println!("Reading {} sequences from SEQS chunk", count);
+    verbose!("Reading {} sequences from SEQS chunk", count);
 
     for _ in 0..count {
         let mut name_bytes = [0u8; 0x50]; // 80 bytes for name
@@ -33,37 +116,69 @@ pub(crate) fn read_sequences(
 
         let start_frame = file.read_u32::<LittleEndian>()?;
         let end_frame = file.read_u32::<LittleEndian>()?;
-        let _move_speed = file.read_f32::<LittleEndian>()?;
+        let move_speed = file.read_f32::<LittleEndian>()?;
         let non_looping_flag = file.read_u32::<LittleEndian>()?;
         let rarity = file.read_f32::<LittleEndian>()?;
+        let sync_point = file.read_u32::<LittleEndian>()?;
 
-        // Skip remaining data (padding + bounds: 4 + 4 + 4*3 + 4*3 = 32 bytes)
-        file.seek(SeekFrom::Current(32))?;
+        // Extent: bounds radius, min, max
+        let bounds_radius = file.read_f32::<LittleEndian>()?;
+        let mut minimum_extent = [0.0; 3];
+        file.read_f32_into::<LittleEndian>(&mut minimum_extent)?;
+        let mut maximum_extent = [0.0; 3];
+        file.read_f32_into::<LittleEndian>(&mut maximum_extent)?;
 
         let seq_name = name.trim().to_string();
-        println!(
+        verbose!(
             "  Sequence: '{}' frames {}-{}",
-            seq_name, start_frame, end_frame
+            seq_name,
+            start_frame,
+            end_frame
         );
 
         model.sequences.push(Sequence {
             name: seq_name,
             start_frame,
             end_frame,
-            rarity: Some(rarity as u32),
+            move_speed,
+            rarity,
             non_looping: non_looping_flag != 0,
+            sync_point,
+            bounds_radius,
+            minimum_extent,
+            maximum_extent,
         });
     }
 
     Ok(())
 }
 
-pub(crate) fn read_textures(file: &mut File, model: &mut Model, size: u32) -> Result<(), MdlError> {
+pub(crate) fn read_global_sequences<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let count = size / 4; // Each global sequence is a single u32 duration
+
+    for _ in 0..count {
+        model
+            .global_sequences
+            .push(file.read_u32::<LittleEndian>()?);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn read_textures<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
     // From Delphi: TEXSize = $100 + 3*4 = 256 + 12 = 268 bytes per texture
     const TEXTURE_SIZE: u32 = 0x100 + 3 * 4; // 268 bytes
 
     let count = size / TEXTURE_SIZE;
-    println!("Reading {} textures from TEXS chunk", count);
+    verbose!("Reading {} textures from TEXS chunk", count);
 
     for _ in 0..count {
         let replaceable_id = file.read_u32::<LittleEndian>()?;
@@ -82,18 +197,20 @@ pub(crate) fn read_textures(file: &mut File, model: &mut Model, size: u32) -> Re
         // Skip padding (4 bytes)
         file.seek(SeekFrom::Current(4))?;
 
-        // Read flags
-        let _flags = file.read_u32::<LittleEndian>()?;
+        // Read flags (wrap width / wrap height)
+        let flags = file.read_u32::<LittleEndian>()?;
 
         let tex_filename = filename.trim().to_string();
-        println!(
+        verbose!(
             "  Texture: '{}', ReplaceableID: {}",
-            tex_filename, replaceable_id
+            tex_filename,
+            replaceable_id
         );
 
         model.textures.push(Texture {
             filename: tex_filename,
             replaceable_id,
+            flags,
             image_data: None, // Will be loaded later if needed
             width: 0,
             height: 0,
@@ -108,13 +225,252 @@ const TAG_KGTR: u32 = 0x5254474B; // Translation (3 floats)
 const TAG_KGRT: u32 = 0x5452474B; // Rotation (4 floats - quaternion)
 const TAG_KGSC: u32 = 0x4353474B; // Scaling (3 floats)
 const TAG_KLAV: u32 = 0x56414C4B; // Visibility (1 float)
+const TAG_KATV: u32 = 0x5654414B; // Attachment visibility (1 float)
+const TAG_KMTA: u32 = 0x41544D4B; // Layer alpha (1 float)
+const TAG_KMTF: u32 = 0x46544D4B; // Layer texture ID (1 u32)
+const TAG_KTAT: u32 = 0x5441544B; // Texture animation translation (3 floats)
+const TAG_KTAR: u32 = 0x5241544B; // Texture animation rotation (4 floats - quaternion)
+const TAG_KTAS: u32 = 0x5341544B; // Texture animation scaling (3 floats)
+const TAG_KGAO: u32 = 0x4F41474B; // Geoset animation alpha (1 float)
+const TAG_KGAC: u32 = 0x4341474B; // Geoset animation color (3 floats, BGR)
+const TAG_KLAS: u32 = 0x53414C4B; // Light attenuation start (1 u32)
+const TAG_KLAE: u32 = 0x45414C4B; // Light attenuation end (1 u32)
+const TAG_KLAC: u32 = 0x43414C4B; // Light color (3 floats, BGR)
+const TAG_KLAI: u32 = 0x49414C4B; // Light intensity (1 float)
+const TAG_KLBI: u32 = 0x49424C4B; // Light ambient intensity (1 float)
+const TAG_KLBC: u32 = 0x43424C4B; // Light ambient color (3 floats, BGR)
+const TAG_KPEE: u32 = 0x4545504B; // Particle emitter emission rate (1 float)
+const TAG_KPEG: u32 = 0x4745504B; // Particle emitter gravity (1 float)
+const TAG_KPLN: u32 = 0x4E4C504B; // Particle emitter longitude (1 float)
+const TAG_KPLT: u32 = 0x544C504B; // Particle emitter latitude (1 float)
+const TAG_KPEL: u32 = 0x4C45504B; // Particle emitter lifespan (1 float)
+const TAG_KPES: u32 = 0x5345504B; // Particle emitter initial velocity (1 float)
+const TAG_KPEV: u32 = 0x5645504B; // Particle emitter visibility (1 float)
+const TAG_KP2S: u32 = 0x5332504B; // Particle emitter 2 speed (1 float)
+const TAG_KP2R: u32 = 0x5232504B; // Particle emitter 2 variation (1 float)
+const TAG_KP2L: u32 = 0x4C32504B; // Particle emitter 2 latitude (1 float)
+const TAG_KP2G: u32 = 0x4732504B; // Particle emitter 2 gravity (1 float)
+const TAG_KP2E: u32 = 0x4532504B; // Particle emitter 2 emission rate (1 float)
+const TAG_KP2N: u32 = 0x4E32504B; // Particle emitter 2 length (1 float)
+const TAG_KP2W: u32 = 0x5732504B; // Particle emitter 2 width (1 float)
+const TAG_KP2V: u32 = 0x5632504B; // Particle emitter 2 visibility (1 float)
+const TAG_KRHA: u32 = 0x4148524B; // Ribbon emitter height above (1 float)
+const TAG_KRHB: u32 = 0x4248524B; // Ribbon emitter height below (1 float)
+const TAG_KRAL: u32 = 0x4C41524B; // Ribbon emitter alpha (1 float)
+const TAG_KRCO: u32 = 0x4F43524B; // Ribbon emitter color (3 floats, BGR)
+const TAG_KRTX: u32 = 0x5854524B; // Ribbon emitter texture slot (1 u32)
+const TAG_KRVS: u32 = 0x5356524B; // Ribbon emitter visibility (1 float)
+const TAG_KCTR: u32 = 0x5254434B; // Camera translation (3 floats)
+const TAG_KCRL: u32 = 0x4C52434B; // Camera roll (1 float)
+const TAG_KTTR: u32 = 0x5254544B; // Camera target translation (3 floats)
+const TAG_KEVT: u32 = 0x5456454B; // Event frames (no values)
+
+// Number of values per keyframe for every track tag (see TrackTag in mdx.hexpat)
+fn track_value_count(tag: &[u8; 4]) -> Option<usize> {
+    match tag {
+        b"KGRT" | b"KTAR" => Some(4),
+        b"KGTR" | b"KGSC" | b"KFC3" | b"KTAT" | b"KTAS" | b"KGAC" | b"KLAC" | b"KLBC" | b"KRCO"
+        | b"KCTR" | b"KTTR" | b"KPPC" => Some(3),
+        b"KMTF" | b"KMTA" | b"KMTE" | b"KFCA" | b"KFTC" | b"KGAO" | b"KLAS" | b"KLAE" | b"KLAI"
+        | b"KLBI" | b"KLAV" | b"KATV" | b"KPEE" | b"KPEG" | b"KPLN" | b"KPLT" | b"KPEL"
+        | b"KPES" | b"KPEV" | b"KP2E" | b"KP2G" | b"KP2L" | b"KP2S" | b"KP2V" | b"KP2R"
+        | b"KP2N" | b"KP2W" | b"KRVS" | b"KRHA" | b"KRHB" | b"KRAL" | b"KRTX" | b"KCRL"
+        | b"KPPA" | b"KPPE" | b"KPPL" | b"KPPS" | b"KPPV" => Some(1),
+        _ => None,
+    }
+}
+
+// Keeps the next track chunk as a raw blob. An unrecognized tag takes everything up to
+// end_pos since its size is unknown.
+fn read_raw_track<R: Read + Seek>(file: &mut R, end_pos: u64) -> Result<RawChunk, MdlError> {
+    let mut tag = [0u8; 4];
+    file.read_exact(&mut tag)?;
+    let body_start = file.stream_position()?;
+
+    let body_size = match track_value_count(&tag) {
+        Some(values) if body_start + 12 <= end_pos => {
+            let keyframe_count = file.read_u32::<LittleEndian>()? as u64;
+            let interpolation_type = file.read_u32::<LittleEndian>()?;
+            // Frame plus values, and in/out tangents for Hermite (2) and Bezier (3)
+            let keyframe_values = if interpolation_type == 2 || interpolation_type == 3 {
+                values * 3
+            } else {
+                values
+            };
+            (12 + keyframe_count * 4 * (1 + keyframe_values as u64)).min(end_pos - body_start)
+        }
+        _ => end_pos - body_start,
+    };
+
+    file.seek(SeekFrom::Start(body_start))?;
+    let data = read_bytes(file, body_size)?;
+    Ok(RawChunk { tag, data })
+}
+
+// Tracks whose keyframes hold integers, kept as f32 in the model
+const INT_TRACKS: [u32; 4] = [TAG_KMTF, TAG_KLAS, TAG_KLAE, TAG_KRTX];
+
+/// Tracks of an object, see read_tracks
+struct Tracks<const N: usize> {
+    indices: [i32; N], // Controller index for each expected tag, -1 if absent
+    unknown: Vec<RawChunk>,
+    order: Vec<[u8; 4]>, // Tags as they came, empty when that is the usual order
+}
+
+// Reads the track chunks up to end_pos whatever their order. Each tag of `tags` becomes
+// a controller, anything else (or a tag seen twice) is kept raw. The usual order is
+// `tags` order followed by the raw tracks, which is what the writer puts out by default
+fn read_tracks<R: Read + Seek, const N: usize>(
+    file: &mut R,
+    model: &mut Model,
+    end_pos: u64,
+    tags: [u32; N],
+) -> Result<Tracks<N>, MdlError> {
+    let mut tracks = Tracks {
+        indices: [-1; N],
+        unknown: Vec::new(),
+        order: Vec::new(),
+    };
+
+    while file.stream_position()? + 4 <= end_pos {
+        let tag = file.read_u32::<LittleEndian>()?;
+        file.seek(SeekFrom::Current(-4))?;
+
+        match tags.iter().position(|&t| t == tag) {
+            Some(slot) if tracks.indices[slot] < 0 => {
+                let values = track_value_count(&tag.to_le_bytes()).unwrap_or(1);
+                tracks.indices[slot] = if INT_TRACKS.contains(&tag) {
+                    read_int_controller(file, model, tag, values)?
+                } else {
+                    read_controller(file, model, tag, values)?
+                };
+            }
+            _ => tracks.unknown.push(read_raw_track(file, end_pos)?),
+        }
+        tracks.order.push(tag.to_le_bytes());
+    }
+
+    let usual = tags
+        .iter()
+        .zip(tracks.indices)
+        .filter(|&(_, idx)| idx >= 0)
+        .map(|(tag, _)| tag.to_le_bytes())
+        .chain(tracks.unknown.iter().map(|chunk| chunk.tag));
+    if tracks.order.iter().copied().eq(usual) {
+        tracks.order.clear();
+    }
+
+    Ok(tracks)
+}
+
+// Node structure without tracks: inclusiveSize, name, ObjectID, parent and flags
+const NODE_HEADER_SIZE: u64 = 4 + 0x50 + 12;
+
+// End of a structure starting at `start` with the inclusiveSize `size`. The size must cover
+// the `min_size` bytes of fixed fields and stay within `end_pos`, or the structure is broken
+// and reading on would go backwards or past its container.
+fn checked_end(start: u64, size: u32, min_size: u64, end_pos: u64) -> Result<u64, MdlError> {
+    let end = start + size as u64;
+    if (size as u64) < min_size || end > end_pos {
+        return Err(MdlError::new("mdx-bad-inclusive-size")
+            .with_arg("offset", start)
+            .with_arg("size", size));
+    }
+    Ok(end)
+}
+
+/// Fields of the Node structure every object starts with
+struct NodeFields {
+    name: String,
+    object_id: u32,
+    parent_id: i32,
+    flags: u32,
+    translation_idx: i32,
+    rotation_idx: i32,
+    scaling_idx: i32,
+    visibility_idx: i32, // Bones and helpers only, -1 for the others
+    unknown_chunks: Vec<RawChunk>,
+    track_order: Vec<[u8; 4]>,
+}
+
+// Reads a Node structure, with its own inclusiveSize that has to end by `end_pos`,
+// and leaves the stream at its end. Bones and helpers keep their KLAV in the node,
+// other objects have it with their own tracks
+fn read_node<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    end_pos: u64,
+    with_visibility: bool,
+) -> Result<NodeFields, MdlError> {
+    let node_start = file.stream_position()?;
+    let node_size = file.read_u32::<LittleEndian>()?;
+    let node_end = checked_end(node_start, node_size, NODE_HEADER_SIZE, end_pos)?;
+    let name = read_fixed_string(file, 0x50)?;
+    let object_id = file.read_u32::<LittleEndian>()?;
+    let parent_id = file.read_i32::<LittleEndian>()?;
+    let flags = file.read_u32::<LittleEndian>()?;
+    let tracks = if with_visibility {
+        read_tracks(
+            file,
+            model,
+            node_end,
+            [TAG_KGTR, TAG_KGRT, TAG_KGSC, TAG_KLAV],
+        )?
+    } else {
+        let Tracks {
+            indices: [translation_idx, rotation_idx, scaling_idx],
+            unknown,
+            order,
+        } = read_tracks(file, model, node_end, [TAG_KGTR, TAG_KGRT, TAG_KGSC])?;
+        Tracks {
+            indices: [translation_idx, rotation_idx, scaling_idx, -1],
+            unknown,
+            order,
+        }
+    };
+    let [translation_idx, rotation_idx, scaling_idx, visibility_idx] = tracks.indices;
+    file.seek(SeekFrom::Start(node_end))?;
+
+    Ok(NodeFields {
+        name,
+        object_id,
+        parent_id,
+        flags,
+        translation_idx,
+        rotation_idx,
+        scaling_idx,
+        visibility_idx,
+        unknown_chunks: tracks.unknown,
+        track_order: tracks.order,
+    })
+}
 
 // Reads a controller chunk if present, returns controller index or -1 if not found
-fn read_controller(
-    file: &mut File,
+fn read_controller<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    expected_tag: u32,
+    element_size: usize,
+) -> Result<i32, MdlError> {
+    read_controller_as(file, model, expected_tag, element_size, f32::from_bits)
+}
+
+// Same as read_controller for tracks of integers (KMTF), the values are kept as f32
+fn read_int_controller<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    expected_tag: u32,
+    element_size: usize,
+) -> Result<i32, MdlError> {
+    read_controller_as(file, model, expected_tag, element_size, |bits| bits as f32)
+}
+
+fn read_controller_as<R: Read + Seek>(
+    file: &mut R,
     model: &mut Model,
     expected_tag: u32,
     element_size: usize,
+    decode: fn(u32) -> f32,
 ) -> Result<i32, MdlError> {
     let pos_before = file.stream_position()?;
 
@@ -140,7 +496,8 @@ fn read_controller(
 
     // Create AnimationController
     let controller_idx = model.controllers.len() as i32;
-    let mut keyframes = Vec::with_capacity(keyframe_count);
+    // Not reserved up front: the count comes from the file and may be broken
+    let mut keyframes = Vec::new();
 
     // Read keyframes
     for _ in 0..keyframe_count {
@@ -149,7 +506,7 @@ fn read_controller(
         // Read data values
         let mut data = Vec::with_capacity(element_size);
         for _ in 0..element_size {
-            data.push(file.read_f32::<LittleEndian>()?);
+            data.push(decode(file.read_u32::<LittleEndian>()?));
         }
 
         // Read tangents if Hermite (2) or Bezier (3)
@@ -158,10 +515,10 @@ fn read_controller(
             let mut out_tan = Vec::with_capacity(element_size);
 
             for _ in 0..element_size {
-                in_tan.push(file.read_f32::<LittleEndian>()?);
+                in_tan.push(decode(file.read_u32::<LittleEndian>()?));
             }
             for _ in 0..element_size {
-                out_tan.push(file.read_f32::<LittleEndian>()?);
+                out_tan.push(decode(file.read_u32::<LittleEndian>()?));
             }
 
             (in_tan, out_tan)
@@ -185,67 +542,45 @@ fn read_controller(
 
     // Debug: print first controller info
     if model.controllers.len() == 1 {
-        println!(
+        verbose!(
             "  First controller: {} keyframes, interp_type={}, global_seq={}",
-            keyframe_count, interpolation_type, global_seq_id
+            keyframe_count,
+            interpolation_type,
+            global_seq_id
         );
         if !model.controllers[0].keyframes.is_empty() {
             let kf = &model.controllers[0].keyframes[0];
-            println!("    First keyframe: frame={}, data={:?}", kf.frame, kf.data);
+            verbose!("    First keyframe: frame={}, data={:?}", kf.frame, kf.data);
         }
     }
 
     Ok(controller_idx)
 }
 
-pub(crate) fn read_bones(file: &mut File, model: &mut Model, size: u32) -> Result<(), MdlError> {
+pub(crate) fn read_bones<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
     let start_pos = file.stream_position()?;
     let end_pos = start_pos + size as u64;
 
-    while file.stream_position()? < end_pos {
-        let node_start = file.stream_position()?;
-
-        if node_start >= end_pos {
-            break;
-        }
-
-        // Read Node.inclusiveSize - this is the size of the Node structure INCLUDING this u32
-        let inclusive_size = file.read_u32::<LittleEndian>()?;
-
-        // Read Node fields
-        let mut name_bytes = [0u8; 0x50]; // 80 bytes for name
-        file.read_exact(&mut name_bytes)?;
-        let name = String::from_utf8(
-            name_bytes
-                .iter()
-                .take_while(|&&b| b != 0)
-                .copied()
-                .collect(),
-        )
-        .unwrap_or_else(|_| "Unknown".to_string());
-
-        let object_id = file.read_u32::<LittleEndian>()?;
-        let parent_id = file.read_i32::<LittleEndian>()?;
-        let _flags = file.read_u32::<LittleEndian>()?;
-
-        // Read controllers (these are inside the Node structure)
-        let translation_idx = read_controller(file, model, TAG_KGTR, 3)?;
-        let rotation_idx = read_controller(file, model, TAG_KGRT, 4)?;
-        let scaling_idx = read_controller(file, model, TAG_KGSC, 3)?;
-        let visibility_idx = read_controller(file, model, TAG_KLAV, 1)?;
+    // Geoset and geoset animation ids after the node
+    const FIELDS_SIZE: u64 = 8;
 
-        // Seek to end of Node structure
-        file.seek(SeekFrom::Start(node_start + inclusive_size as u64))?;
+    while file.stream_position()? < end_pos {
+        let node = read_node(file, model, end_pos - FIELDS_SIZE, true)?;
 
         // Now read Bone-specific fields (AFTER Node structure)
         let geoset_id = file.read_i32::<LittleEndian>()?;
         let geoset_anim_id = file.read_i32::<LittleEndian>()?;
 
         model.bones.push(Bone {
-            name: name.trim().to_string(),
-            object_id,
-            parent_id,
-            pivot_point: [0.0, 0.0, 0.0], // Will be set from PIVT chunk
+            name: node.name.trim().to_string(),
+            object_id: node.object_id,
+            parent_id: node.parent_id,
+            flags: node.flags & !NODE_FLAG_BONE,
+            type_flag_missing: node.flags & NODE_FLAG_BONE == 0,
             geoset_id: if geoset_id >= 0 {
                 Some(geoset_id as u32)
             } else {
@@ -256,14 +591,16 @@ pub(crate) fn read_bones(file: &mut File, model: &mut Model, size: u32) -> Resul
             } else {
                 None
             },
-            translation_idx,
-            rotation_idx,
-            scaling_idx,
-            visibility_idx,
+            translation_idx: node.translation_idx,
+            rotation_idx: node.rotation_idx,
+            scaling_idx: node.scaling_idx,
+            visibility_idx: node.visibility_idx,
+            unknown_chunks: node.unknown_chunks,
+            node_track_order: node.track_order,
         });
     }
 
-    println!(
+    verbose!(
         "Loaded {} bones, {} controllers",
         model.bones.len(),
         model.controllers.len()
@@ -271,78 +608,652 @@ pub(crate) fn read_bones(file: &mut File, model: &mut Model, size: u32) -> Resul
     Ok(())
 }
 
-pub(crate) fn read_helpers(file: &mut File, model: &mut Model, size: u32) -> Result<(), MdlError> {
+pub(crate) fn read_helpers<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
     let start_pos = file.stream_position()?;
     let end_pos = start_pos + size as u64;
 
     while file.stream_position()? < end_pos {
-        let node_start = file.stream_position()?;
+        let node = read_node(file, model, end_pos, true)?;
 
-        if node_start >= end_pos {
-            break;
-        }
+        // Helper has no additional fields after Node, unlike Bone
+        model.helpers.push(Helper {
+            name: node.name.trim().to_string(),
+            object_id: node.object_id,
+            parent_id: node.parent_id,
+            flags: node.flags,
+            translation_idx: node.translation_idx,
+            rotation_idx: node.rotation_idx,
+            scaling_idx: node.scaling_idx,
+            visibility_idx: node.visibility_idx,
+            unknown_chunks: node.unknown_chunks,
+            node_track_order: node.track_order,
+        });
+    }
 
-        // Read Node.inclusiveSize
-        let inclusive_size = file.read_u32::<LittleEndian>()?;
+    verbose!("Loaded {} helpers", model.helpers.len());
+    Ok(())
+}
 
-        // Read Node fields
-        let mut name_bytes = [0u8; 0x50]; // 80 bytes for name
-        file.read_exact(&mut name_bytes)?;
-        let name = String::from_utf8(
-            name_bytes
-                .iter()
-                .take_while(|&&b| b != 0)
-                .copied()
-                .collect(),
-        )
-        .unwrap_or_else(|_| "Unknown".to_string());
+pub(crate) fn read_attachments<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
 
-        let object_id = file.read_u32::<LittleEndian>()?;
-        let parent_id = file.read_i32::<LittleEndian>()?;
-        let _flags = file.read_u32::<LittleEndian>()?;
+    // Fixed fields between the node and the tracks
+    const FIELDS_SIZE: u64 = 0x104 + 4;
 
-        // Read controllers (these are inside the Node structure)
-        let translation_idx = read_controller(file, model, TAG_KGTR, 3)?;
-        let rotation_idx = read_controller(file, model, TAG_KGRT, 4)?;
-        let scaling_idx = read_controller(file, model, TAG_KGSC, 3)?;
-        let visibility_idx = read_controller(file, model, TAG_KLAV, 1)?;
+    while file.stream_position()? < end_pos {
+        let attachment_start = file.stream_position()?;
+        let attachment_size = file.read_u32::<LittleEndian>()?;
+        let attachment_end = checked_end(
+            attachment_start,
+            attachment_size,
+            4 + NODE_HEADER_SIZE + FIELDS_SIZE,
+            end_pos,
+        )?;
+
+        let node = read_node(file, model, attachment_end - FIELDS_SIZE, false)?;
+
+        // Attachment fields come after the node, visibility is KATV instead of KLAV
+        let path = read_fixed_string(file, 0x104)?;
+        let attachment_id = file.read_u32::<LittleEndian>()?;
+        let Tracks {
+            indices: [visibility_idx],
+            unknown: unknown_tracks,
+            order: track_order,
+        } = read_tracks(file, model, attachment_end, [TAG_KATV])?;
+
+        model.attachments.push(Attachment {
+            name: node.name,
+            object_id: node.object_id,
+            parent_id: node.parent_id,
+            flags: node.flags & !NODE_FLAG_ATTACHMENT,
+            type_flag_missing: node.flags & NODE_FLAG_ATTACHMENT == 0,
+            path,
+            attachment_id,
+            translation_idx: node.translation_idx,
+            rotation_idx: node.rotation_idx,
+            scaling_idx: node.scaling_idx,
+            visibility_idx,
+            unknown_chunks: node.unknown_chunks,
+            node_track_order: node.track_order,
+            unknown_tracks,
+            track_order,
+        });
 
-        // Seek to end of Node structure
-        file.seek(SeekFrom::Start(node_start + inclusive_size as u64))?;
+        file.seek(SeekFrom::Start(attachment_end))?;
+    }
 
-        // Helper has no additional fields after Node, unlike Bone
-        model.helpers.push(Helper {
-            name: name.trim().to_string(),
-            object_id,
-            parent_id,
-            pivot_point: [0.0, 0.0, 0.0], // Will be set from PIVT chunk
+    verbose!("Loaded {} attachments", model.attachments.len());
+    Ok(())
+}
+
+pub(crate) fn read_lights<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
+
+    // Fixed fields between the node and the tracks
+    const FIELDS_SIZE: u64 = 4 + 2 * 4 + 3 * 4 + 4 + 3 * 4 + 4;
+
+    while file.stream_position()? < end_pos {
+        let light_start = file.stream_position()?;
+        let light_size = file.read_u32::<LittleEndian>()?;
+        let light_end = checked_end(
+            light_start,
+            light_size,
+            4 + NODE_HEADER_SIZE + FIELDS_SIZE,
+            end_pos,
+        )?;
+
+        let node = read_node(file, model, light_end - FIELDS_SIZE, false)?;
+
+        let light_type = file.read_u32::<LittleEndian>()?;
+        let [attenuation_start, attenuation_end] = read_f32_array(file)?;
+        let color = read_f32_array(file)?;
+        let intensity = file.read_f32::<LittleEndian>()?;
+        let amb_color = read_f32_array(file)?;
+        let amb_intensity = file.read_f32::<LittleEndian>()?;
+
+        // Light tracks come after the fixed fields
+        let Tracks {
+            indices:
+                [
+                    attenuation_start_idx,
+                    attenuation_end_idx,
+                    color_idx,
+                    intensity_idx,
+                    amb_intensity_idx,
+                    amb_color_idx,
+                    visibility_idx,
+                ],
+            unknown: unknown_tracks,
+            order: track_order,
+        } = read_tracks(
+            file,
+            model,
+            light_end,
+            [
+                TAG_KLAS, TAG_KLAE, TAG_KLAC, TAG_KLAI, TAG_KLBI, TAG_KLBC, TAG_KLAV,
+            ],
+        )?;
+
+        model.lights.push(Light {
+            name: node.name,
+            object_id: node.object_id,
+            parent_id: node.parent_id,
+            flags: node.flags & !NODE_FLAG_LIGHT,
+            type_flag_missing: node.flags & NODE_FLAG_LIGHT == 0,
+            light_type,
+            attenuation_start,
+            attenuation_end,
+            color,
+            intensity,
+            amb_color,
+            amb_intensity,
+            translation_idx: node.translation_idx,
+            rotation_idx: node.rotation_idx,
+            scaling_idx: node.scaling_idx,
+            attenuation_start_idx,
+            attenuation_end_idx,
+            color_idx,
+            intensity_idx,
+            amb_intensity_idx,
+            amb_color_idx,
+            visibility_idx,
+            unknown_chunks: node.unknown_chunks,
+            node_track_order: node.track_order,
+            unknown_tracks,
+            track_order,
+        });
+
+        file.seek(SeekFrom::Start(light_end))?;
+    }
+
+    verbose!("Loaded {} lights", model.lights.len());
+    Ok(())
+}
+
+pub(crate) fn read_particle_emitters<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
+
+    // Fixed fields between the node and the tracks
+    const FIELDS_SIZE: u64 = 4 * 4 + 0x104 + 2 * 4;
+
+    while file.stream_position()? < end_pos {
+        let emitter_start = file.stream_position()?;
+        let emitter_size = file.read_u32::<LittleEndian>()?;
+        let emitter_end = checked_end(
+            emitter_start,
+            emitter_size,
+            4 + NODE_HEADER_SIZE + FIELDS_SIZE,
+            end_pos,
+        )?;
+
+        let node = read_node(file, model, emitter_end - FIELDS_SIZE, false)?;
+
+        let [emission_rate, gravity, longitude, latitude] = read_f32_array(file)?;
+        let path = read_fixed_string(file, 0x104)?;
+        let [lifespan, speed] = read_f32_array(file)?;
+
+        // Emitter tracks come after the fixed fields
+        let Tracks {
+            indices:
+                [
+                    emission_rate_idx,
+                    gravity_idx,
+                    longitude_idx,
+                    latitude_idx,
+                    lifespan_idx,
+                    speed_idx,
+                    visibility_idx,
+                ],
+            unknown: unknown_tracks,
+            order: track_order,
+        } = read_tracks(
+            file,
+            model,
+            emitter_end,
+            [
+                TAG_KPEE, TAG_KPEG, TAG_KPLN, TAG_KPLT, TAG_KPEL, TAG_KPES, TAG_KPEV,
+            ],
+        )?;
+
+        model.particle_emitters.push(ParticleEmitter {
+            name: node.name,
+            object_id: node.object_id,
+            parent_id: node.parent_id,
+            flags: node.flags & !NODE_FLAG_PARTICLE_EMITTER,
+            type_flag_missing: node.flags & NODE_FLAG_PARTICLE_EMITTER == 0,
+            emission_rate,
+            gravity,
+            longitude,
+            latitude,
+            path,
+            lifespan,
+            speed,
+            translation_idx: node.translation_idx,
+            rotation_idx: node.rotation_idx,
+            scaling_idx: node.scaling_idx,
+            emission_rate_idx,
+            gravity_idx,
+            longitude_idx,
+            latitude_idx,
+            lifespan_idx,
+            speed_idx,
+            visibility_idx,
+            unknown_chunks: node.unknown_chunks,
+            node_track_order: node.track_order,
+            unknown_tracks,
+            track_order,
+        });
+
+        file.seek(SeekFrom::Start(emitter_end))?;
+    }
+
+    verbose!("Loaded {} particle emitters", model.particle_emitters.len());
+    Ok(())
+}
+
+pub(crate) fn read_particle_emitters2<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
+
+    // Fixed fields between the node and the tracks
+    const FIELDS_SIZE: u64 = 8 * 4 + 4 * 4 + 2 * 4 + 9 * 4 + 3 + 3 * 4 + 12 * 4 + 4 * 4;
+
+    while file.stream_position()? < end_pos {
+        let emitter_start = file.stream_position()?;
+        let emitter_size = file.read_u32::<LittleEndian>()?;
+        let emitter_end = checked_end(
+            emitter_start,
+            emitter_size,
+            4 + NODE_HEADER_SIZE + FIELDS_SIZE,
+            end_pos,
+        )?;
+
+        let node = read_node(file, model, emitter_end - FIELDS_SIZE, false)?;
+
+        let [
+            speed,
+            variation,
+            latitude,
+            gravity,
+            lifespan,
+            emission_rate,
+            length,
+            width,
+        ] = read_f32_array(file)?;
+        let [filter_mode, rows, columns, head_or_tail] = read_u32_array(file)?;
+        let [tail_length, time] = read_f32_array(file)?;
+        // Blue first, like every color of the format
+        let segment_colors = [
+            read_f32_array(file)?,
+            read_f32_array(file)?,
+            read_f32_array(file)?,
+        ];
+        let mut segment_alpha = [0u8; 3];
+        file.read_exact(&mut segment_alpha)?;
+        let segment_scaling = read_f32_array(file)?;
+        let head_intervals = read_u32_array(file)?;
+        let head_decay_intervals = read_u32_array(file)?;
+        let tail_intervals = read_u32_array(file)?;
+        let tail_decay_intervals = read_u32_array(file)?;
+        let [texture_id, squirt] = read_u32_array(file)?;
+        let priority_plane = file.read_i32::<LittleEndian>()?;
+        let replaceable_id = file.read_u32::<LittleEndian>()?;
+
+        // Emitter tracks come after the fixed fields
+        let Tracks {
+            indices:
+                [
+                    speed_idx,
+                    variation_idx,
+                    latitude_idx,
+                    gravity_idx,
+                    emission_rate_idx,
+                    length_idx,
+                    width_idx,
+                    visibility_idx,
+                ],
+            unknown: unknown_tracks,
+            order: track_order,
+        } = read_tracks(
+            file,
+            model,
+            emitter_end,
+            [
+                TAG_KP2S, TAG_KP2R, TAG_KP2L, TAG_KP2G, TAG_KP2E, TAG_KP2N, TAG_KP2W, TAG_KP2V,
+            ],
+        )?;
+
+        model.particle_emitters2.push(ParticleEmitter2 {
+            name: node.name,
+            object_id: node.object_id,
+            parent_id: node.parent_id,
+            flags: node.flags & !NODE_FLAG_PARTICLE_EMITTER,
+            type_flag_missing: node.flags & NODE_FLAG_PARTICLE_EMITTER == 0,
+            speed,
+            variation,
+            latitude,
+            gravity,
+            lifespan,
+            emission_rate,
+            length,
+            width,
+            filter_mode,
+            rows,
+            columns,
+            head_or_tail,
+            tail_length,
+            time,
+            segment_colors,
+            segment_alpha,
+            segment_scaling,
+            head_intervals,
+            head_decay_intervals,
+            tail_intervals,
+            tail_decay_intervals,
+            texture_id,
+            squirt: squirt != 0,
+            priority_plane,
+            replaceable_id,
+            translation_idx: node.translation_idx,
+            rotation_idx: node.rotation_idx,
+            scaling_idx: node.scaling_idx,
+            speed_idx,
+            variation_idx,
+            latitude_idx,
+            gravity_idx,
+            emission_rate_idx,
+            length_idx,
+            width_idx,
+            visibility_idx,
+            unknown_chunks: node.unknown_chunks,
+            node_track_order: node.track_order,
+            unknown_tracks,
+            track_order,
+        });
+
+        file.seek(SeekFrom::Start(emitter_end))?;
+    }
+
+    verbose!(
+        "Loaded {} particle emitters 2",
+        model.particle_emitters2.len()
+    );
+    Ok(())
+}
+
+pub(crate) fn read_ribbon_emitters<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
+
+    // Fixed fields between the node and the tracks
+    const FIELDS_SIZE: u64 = 3 * 4 + 3 * 4 + 4 + 5 * 4 + 4;
+
+    while file.stream_position()? < end_pos {
+        let emitter_start = file.stream_position()?;
+        let emitter_size = file.read_u32::<LittleEndian>()?;
+        let emitter_end = checked_end(
+            emitter_start,
+            emitter_size,
+            4 + NODE_HEADER_SIZE + FIELDS_SIZE,
+            end_pos,
+        )?;
+
+        let node = read_node(file, model, emitter_end - FIELDS_SIZE, false)?;
+
+        let [height_above, height_below, alpha] = read_f32_array(file)?;
+        let color = read_f32_array(file)?;
+        let lifespan = file.read_f32::<LittleEndian>()?;
+        let [texture_slot, emission_rate, rows, columns, material_id] = read_u32_array(file)?;
+        let gravity = file.read_f32::<LittleEndian>()?;
+
+        // Emitter tracks come after the fixed fields
+        let Tracks {
+            indices:
+                [
+                    height_above_idx,
+                    height_below_idx,
+                    alpha_idx,
+                    color_idx,
+                    texture_slot_idx,
+                    visibility_idx,
+                ],
+            unknown: unknown_tracks,
+            order: track_order,
+        } = read_tracks(
+            file,
+            model,
+            emitter_end,
+            [TAG_KRHA, TAG_KRHB, TAG_KRAL, TAG_KRCO, TAG_KRTX, TAG_KRVS],
+        )?;
+
+        model.ribbon_emitters.push(RibbonEmitter {
+            name: node.name,
+            object_id: node.object_id,
+            parent_id: node.parent_id,
+            flags: node.flags & !NODE_FLAG_RIBBON_EMITTER,
+            type_flag_missing: node.flags & NODE_FLAG_RIBBON_EMITTER == 0,
+            height_above,
+            height_below,
+            alpha,
+            color,
+            lifespan,
+            texture_slot,
+            emission_rate,
+            rows,
+            columns,
+            material_id,
+            gravity,
+            translation_idx: node.translation_idx,
+            rotation_idx: node.rotation_idx,
+            scaling_idx: node.scaling_idx,
+            height_above_idx,
+            height_below_idx,
+            alpha_idx,
+            color_idx,
+            texture_slot_idx,
+            visibility_idx,
+            unknown_chunks: node.unknown_chunks,
+            node_track_order: node.track_order,
+            unknown_tracks,
+            track_order,
+        });
+
+        file.seek(SeekFrom::Start(emitter_end))?;
+    }
+
+    verbose!("Loaded {} ribbon emitters", model.ribbon_emitters.len());
+    Ok(())
+}
+
+pub(crate) fn read_cameras<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
+
+    while file.stream_position()? < end_pos {
+        let camera_start = file.stream_position()?;
+        let camera_size = file.read_u32::<LittleEndian>()?;
+        // Size, name, position, clipping and target come before the tracks
+        let camera_end = checked_end(camera_start, camera_size, 4 + 0x50 + 9 * 4, end_pos)?;
+
+        // Cameras are not nodes: no ObjectID, parent or flags
+        let name = read_fixed_string(file, 0x50)?;
+        let position = read_f32_array(file)?;
+        let [field_of_view, far_clip, near_clip] = read_f32_array(file)?;
+        let target_position = read_f32_array(file)?;
+
+        let Tracks {
+            indices: [translation_idx, rotation_idx, target_translation_idx],
+            unknown: unknown_chunks,
+            order: track_order,
+        } = read_tracks(file, model, camera_end, [TAG_KCTR, TAG_KCRL, TAG_KTTR])?;
+
+        model.cameras.push(Camera {
+            name,
+            position,
+            field_of_view,
+            far_clip,
+            near_clip,
+            target_position,
             translation_idx,
             rotation_idx,
-            scaling_idx,
-            visibility_idx,
+            target_translation_idx,
+            unknown_chunks,
+            track_order,
+        });
+
+        file.seek(SeekFrom::Start(camera_end))?;
+    }
+
+    verbose!("Loaded {} cameras", model.cameras.len());
+    Ok(())
+}
+
+pub(crate) fn read_event_objects<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
+
+    while file.stream_position()? < end_pos {
+        // Events have no inclusiveSize of their own, only the node has one
+        let node = read_node(file, model, end_pos, false)?;
+
+        // KEVT: frame count, global sequence, then only the frames
+        let mut event = EventObject {
+            name: node.name,
+            object_id: node.object_id,
+            parent_id: node.parent_id,
+            flags: node.flags & !NODE_FLAG_EVENT,
+            type_flag_missing: node.flags & NODE_FLAG_EVENT == 0,
+            translation_idx: node.translation_idx,
+            rotation_idx: node.rotation_idx,
+            scaling_idx: node.scaling_idx,
+            unknown_chunks: node.unknown_chunks,
+            node_track_order: node.track_order,
+            ..Default::default()
+        };
+        let track_start = file.stream_position()?;
+        if track_start + 12 <= end_pos && file.read_u32::<LittleEndian>()? == TAG_KEVT {
+            let count = file.read_u32::<LittleEndian>()?;
+            event.has_track = true;
+            event.global_seq_id = file.read_i32::<LittleEndian>()?;
+            event.frames = (0..count)
+                .map(|_| file.read_u32::<LittleEndian>())
+                .collect::<Result<_, _>>()?;
+        } else {
+            file.seek(SeekFrom::Start(track_start))?;
+        }
+
+        model.event_objects.push(event);
+    }
+
+    verbose!("Loaded {} event objects", model.event_objects.len());
+    Ok(())
+}
+
+pub(crate) fn read_collision_shapes<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
+
+    while file.stream_position()? < end_pos {
+        // Shapes have no inclusiveSize of their own, only the node has one
+        let node = read_node(file, model, end_pos, false)?;
+
+        // The size of the shape depends on its type, so unknown types can't be skipped
+        let shape_type = file.read_u32::<LittleEndian>()?;
+        if shape_type > COLLISION_SHAPE_CYLINDER {
+            return Err(MdlError::new("mdx-unknown-collision-shape")
+                .with_arg("name", node.name)
+                .with_arg("type", shape_type));
+        }
+        let vertices = (0..CollisionShape::vertex_count(shape_type))
+            .map(|_| read_f32_array(file))
+            .collect::<Result<Vec<[f32; 3]>, MdlError>>()?;
+        let radius = if CollisionShape::has_radius(shape_type) {
+            file.read_f32::<LittleEndian>()?
+        } else {
+            0.0
+        };
+
+        model.collision_shapes.push(CollisionShape {
+            name: node.name,
+            object_id: node.object_id,
+            parent_id: node.parent_id,
+            flags: node.flags & !NODE_FLAG_COLLISION_SHAPE,
+            type_flag_missing: node.flags & NODE_FLAG_COLLISION_SHAPE == 0,
+            shape_type,
+            vertices,
+            radius,
+            translation_idx: node.translation_idx,
+            rotation_idx: node.rotation_idx,
+            scaling_idx: node.scaling_idx,
+            unknown_chunks: node.unknown_chunks,
+            node_track_order: node.track_order,
         });
     }
 
-    println!("Loaded {} helpers", model.helpers.len());
+    verbose!("Loaded {} collision shapes", model.collision_shapes.len());
     Ok(())
 }
 
-pub(crate) fn read_materials(
-    file: &mut File,
+pub(crate) fn read_materials<R: Read + Seek>(
+    file: &mut R,
     model: &mut Model,
     size: u32,
 ) -> Result<(), MdlError> {
     let start_pos = file.seek(SeekFrom::Current(0))?;
     let end_pos = start_pos + size as u64;
 
+    // inclusiveSize, priority plane, flags and the LAYS tag
+    const HEADER_SIZE: u64 = 4 * 4;
+    // inclusiveSize, filter mode, shading flags, texture, texture animation, CoordId, alpha
+    const LAYER_HEADER_SIZE: u64 = 7 * 4;
+
     // Each material has inclusiveSize at the start
     while file.seek(SeekFrom::Current(0))? < end_pos {
-        let material_size = file.read_u32::<LittleEndian>()?;
         let material_start = file.seek(SeekFrom::Current(0))?;
-        let material_end = material_start + (material_size as u64) - 4; // -4 because we already read size
+        let material_size = file.read_u32::<LittleEndian>()?;
+        let material_end = checked_end(material_start, material_size, HEADER_SIZE, end_pos)?;
 
-        // Skip priority plane and flags
-        file.seek(SeekFrom::Current(8))?;
+        let priority_plane = file.read_i32::<LittleEndian>()?;
+        let flags = file.read_u32::<LittleEndian>()?;
 
         // Read LAYS tag
         let mut tag = [0u8; 4];
@@ -355,20 +1266,24 @@ pub(crate) fn read_materials(
         }
 
         let layers_count = file.read_u32::<LittleEndian>()?;
-        let mut material = Material::default();
+        let mut material = Material {
+            priority_plane,
+            flags,
+            layers: Vec::new(),
+        };
 
         // Read each layer
         for _ in 0..layers_count {
-            let layer_size = file.read_u32::<LittleEndian>()?;
             let layer_start = file.seek(SeekFrom::Current(0))?;
-            let layer_end = layer_start + (layer_size as u64) - 4;
+            let layer_size = file.read_u32::<LittleEndian>()?;
+            let layer_end = checked_end(layer_start, layer_size, LAYER_HEADER_SIZE, material_end)?;
 
             // Read layer data
             let filter_mode_val = file.read_u32::<LittleEndian>()?;
             let shading_flags_bits = file.read_u32::<LittleEndian>()?;
             let texture_id = file.read_u32::<LittleEndian>()?;
-            let _texture_animation_id = file.read_u32::<LittleEndian>()?;
-            let _coord_id = file.read_u32::<LittleEndian>()?;
+            let texture_animation_id = file.read_i32::<LittleEndian>()?;
+            let coord_id = file.read_u32::<LittleEndian>()?;
             let alpha = file.read_f32::<LittleEndian>()?;
 
             // Parse filter mode using FilterMode::from_u32
@@ -377,11 +1292,23 @@ pub(crate) fn read_materials(
             // Parse shading flags once during loading
             let shading_flags = ShadingFlags::from_bits(shading_flags_bits);
 
+            let Tracks {
+                indices: [alpha_idx, texture_id_idx],
+                unknown: unknown_chunks,
+                order: track_order,
+            } = read_tracks(file, model, layer_end, [TAG_KMTA, TAG_KMTF])?;
+
             let layer = Layer {
                 texture_id: Some(texture_id as usize),
                 filter_mode,
                 shading_flags,
                 alpha,
+                texture_animation_id: usize::try_from(texture_animation_id).ok(),
+                coord_id,
+                texture_id_idx,
+                alpha_idx,
+                unknown_chunks,
+                track_order,
                 // Initialize runtime fields
                 enabled: true,
                 alpha_override: None,
@@ -390,13 +1317,13 @@ pub(crate) fn read_materials(
             };
             material.layers.push(layer);
 
-            // Skip to end of layer (may contain optional track chunks KMTF, KMTA, etc.)
+            // Skip to end of layer
             file.seek(SeekFrom::Start(layer_end))?;
         }
 
         if let Some(layer) = material.layers.first() {
             if let Some(tex_id) = layer.texture_id {
-                println!(
+                verbose!(
                     "  Material {}: texture_id = {}, filter_mode = {:?}, alpha = {}",
                     model.materials.len(),
                     tex_id,
@@ -412,35 +1339,770 @@ pub(crate) fn read_materials(
         file.seek(SeekFrom::Start(material_end))?;
     }
 
-    println!("Loaded {} materials", model.materials.len());
+    verbose!("Loaded {} materials", model.materials.len());
+
+    Ok(())
+}
+
+pub(crate) fn read_texture_animations<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
+
+    while file.stream_position()? < end_pos {
+        let anim_start = file.stream_position()?;
+        let inclusive_size = file.read_u32::<LittleEndian>()?;
+        let anim_end = checked_end(anim_start, inclusive_size, 4, end_pos)?;
+
+        let Tracks {
+            indices: [translation_idx, rotation_idx, scaling_idx],
+            unknown: unknown_chunks,
+            order: track_order,
+        } = read_tracks(file, model, anim_end, [TAG_KTAT, TAG_KTAR, TAG_KTAS])?;
+
+        model.texture_animations.push(TextureAnimation {
+            translation_idx,
+            rotation_idx,
+            scaling_idx,
+            unknown_chunks,
+            track_order,
+        });
+
+        file.seek(SeekFrom::Start(anim_end))?;
+    }
+
+    verbose!(
+        "Loaded {} texture animations",
+        model.texture_animations.len()
+    );
+    Ok(())
+}
+
+pub(crate) fn read_geoset_animations<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
+    let start_pos = file.stream_position()?;
+    let end_pos = start_pos + size as u64;
+
+    while file.stream_position()? < end_pos {
+        let anim_start = file.stream_position()?;
+        let inclusive_size = file.read_u32::<LittleEndian>()?;
+        // Size, alpha, flags, color and geoset id come before the tracks
+        let anim_end = checked_end(anim_start, inclusive_size, 4 + 4 + 4 + 12 + 4, end_pos)?;
+
+        let alpha = file.read_f32::<LittleEndian>()?;
+        let flags = file.read_u32::<LittleEndian>()?;
+        let mut color = [0.0; 3];
+        file.read_f32_into::<LittleEndian>(&mut color)?;
+        let geoset_id = file.read_u32::<LittleEndian>()?;
+
+        let Tracks {
+            indices: [alpha_idx, color_idx],
+            unknown: unknown_chunks,
+            order: track_order,
+        } = read_tracks(file, model, anim_end, [TAG_KGAO, TAG_KGAC])?;
+
+        model.geoset_animations.push(GeosetAnimation {
+            geoset_id,
+            alpha,
+            flags,
+            color,
+            alpha_idx,
+            color_idx,
+            unknown_chunks,
+            track_order,
+        });
 
+        file.seek(SeekFrom::Start(anim_end))?;
+    }
+
+    verbose!("Loaded {} geoset animations", model.geoset_animations.len());
     Ok(())
 }
 
-pub(crate) fn read_pivots(file: &mut File, model: &mut Model, size: u32) -> Result<(), MdlError> {
+pub(crate) fn read_pivots<R: Read + Seek>(
+    file: &mut R,
+    model: &mut Model,
+    size: u32,
+) -> Result<(), MdlError> {
     let count = size / (4 * 3); // Each pivot point is 3 floats
 
-    for i in 0..count as usize {
+    // Pivots are indexed by ObjectID across all node types, keep the whole table
+    model.pivot_points.clear();
+    for _ in 0..count {
         let x = file.read_f32::<LittleEndian>()?;
         let y = file.read_f32::<LittleEndian>()?;
         let z = file.read_f32::<LittleEndian>()?;
+        model.pivot_points.push([x, y, z]);
+    }
 
-        // Assign to bones first, then helpers
-        if i < model.bones.len() {
-            model.bones[i].pivot_point = [x, y, z];
-        } else {
-            let helper_idx = i - model.bones.len();
-            if helper_idx < model.helpers.len() {
-                model.helpers[helper_idx].pivot_point = [x, y, z];
+    verbose!("Loaded {} pivot points", count);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::MaterialFlags;
+    use crate::model::collision::{COLLISION_SHAPE_BOX, COLLISION_SHAPE_SPHERE};
+    use crate::model::emitter::{
+        PARTICLE_FLAG_EMITTER_USES_MDL, PARTICLE2_BOTH, PARTICLE2_FILTER_ADDITIVE,
+        RIBBON_FLAG_ALT_TYPE,
+    };
+    use crate::model::geoset::PRIMITIVE_TRIANGLES;
+    use crate::model::light::LIGHT_TYPE_DIRECTIONAL;
+    use crate::model::model::Model;
+    use crate::model::skeleton::{
+        NODE_FLAG_ATTACHMENT, NODE_FLAG_BILLBOARDED, NODE_FLAG_COLLISION_SHAPE, NODE_FLAG_EVENT,
+        NODE_FLAG_LIGHT, NODE_FLAG_PARTICLE_EMITTER, NODE_FLAG_RIBBON_EMITTER,
+    };
+    use crate::parser::load::load_from_bytes;
+    use crate::parser::mdl::load_mdl_from_str;
+    use crate::writer::save::save_to_bytes;
+
+    /// Little endian fixture bytes
+    #[derive(Default)]
+    struct Bytes(Vec<u8>);
+
+    impl Bytes {
+        fn u32(mut self, value: u32) -> Self {
+            self.0.extend(value.to_le_bytes());
+            self
+        }
+
+        fn i32(mut self, value: i32) -> Self {
+            self.0.extend(value.to_le_bytes());
+            self
+        }
+
+        fn f32s(mut self, values: &[f32]) -> Self {
+            for value in values {
+                self.0.extend(value.to_le_bytes());
             }
+            self
+        }
+
+        fn raw(mut self, bytes: &[u8]) -> Self {
+            self.0.extend(bytes);
+            self
+        }
+
+        fn name(self, name: &str, len: usize) -> Self {
+            let mut field = vec![0; len];
+            field[..name.len()].copy_from_slice(name.as_bytes());
+            self.raw(&field)
+        }
+
+        /// Prefixed with its size, the size field included
+        fn inclusive(self) -> Self {
+            Bytes::default().u32(self.0.len() as u32 + 4).raw(&self.0)
         }
     }
 
-    println!(
-        "Loaded {} pivot points ({} bones + {} helpers)",
-        count,
-        model.bones.len(),
-        model.helpers.len()
-    );
-    Ok(())
+    /// Node structure with its inclusive size, `tracks` are the KGTR/KGRT/KGSC ones
+    fn node(name: &str, object_id: u32, parent_id: i32, flags: u32, tracks: Bytes) -> Bytes {
+        Bytes::default()
+            .name(name, 0x50)
+            .u32(object_id)
+            .i32(parent_id)
+            .u32(flags)
+            .raw(&tracks.0)
+            .inclusive()
+    }
+
+    /// Linear track with a single key
+    fn track(tag: &[u8; 4], frame: i32, values: &[f32]) -> Bytes {
+        Bytes::default()
+            .raw(tag)
+            .u32(1)
+            .u32(1)
+            .i32(-1)
+            .i32(frame)
+            .f32s(values)
+    }
+
+    /// MDX holding `chunk` after a version and an empty model header
+    fn mdx_with(tag: &[u8; 4], chunk: Bytes) -> Bytes {
+        let mut file = Bytes::default().raw(b"MDLX");
+        for (tag, body) in [
+            (b"VERS", Bytes::default().u32(800)),
+            (b"MODL", Bytes::default().raw(&[0; 0x174])),
+            (tag, chunk),
+        ] {
+            file = file.raw(tag).u32(body.0.len() as u32).raw(&body.0);
+        }
+        file
+    }
+
+    /// Loads an MDX holding `chunk`
+    fn load_chunk(tag: &[u8; 4], chunk: Bytes) -> Model {
+        load_from_bytes(&mdx_with(tag, chunk).0).unwrap()
+    }
+
+    #[test]
+    fn saves_chunks_unchanged() {
+        for (tag, chunk) in [
+            (b"MTLS", material()),
+            (b"HELP", helper()),
+            (b"GEOS", geoset()),
+            (b"LITE", light()),
+            (b"CAMS", camera()),
+            (b"CLID", shapes()),
+            (b"PRE2", particle_emitter2()),
+            (b"RIBB", ribbon(NODE_FLAG_RIBBON_EMITTER)),
+            (b"RIBB", ribbon(RIBBON_FLAG_ALT_TYPE)),
+            (b"PREM", particle_emitter()),
+            (b"ATCH", attachment()),
+            (b"TXAN", texture_animation()),
+            (b"GEOA", geoset_animation()),
+            (b"EVTS", events()),
+        ] {
+            let file = mdx_with(tag, chunk);
+            let model = load_from_bytes(&file.0).unwrap();
+            assert_eq!(
+                save_to_bytes(&model).unwrap(),
+                file.0,
+                "{}",
+                String::from_utf8_lossy(tag)
+            );
+        }
+    }
+
+    /// Material with a flag bit that has no name, and a layer storing KMTF before KMTA
+    fn material() -> Bytes {
+        let layer = Bytes::default()
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            .i32(-1)
+            .u32(0)
+            .f32s(&[1.0])
+            .raw(b"KMTF")
+            .u32(1)
+            .u32(0)
+            .i32(-1)
+            .i32(0)
+            .u32(2)
+            .raw(&track(b"KMTA", 0, &[0.5]).0)
+            .inclusive();
+        Bytes::default()
+            .i32(2)
+            .u32(MaterialFlags::ConstantColor as u32 | 0x100)
+            .raw(b"LAYS")
+            .u32(1)
+            .raw(&layer.0)
+            .inclusive()
+    }
+
+    #[test]
+    fn materials() {
+        let model = load_chunk(b"MTLS", material());
+
+        let material = &model.materials[0];
+        assert_eq!(material.priority_plane, 2);
+        assert_eq!(material.flags, 0x101);
+        assert_eq!(material.flag_list(), [MaterialFlags::ConstantColor]);
+
+        let layer = &material.layers[0];
+        assert_eq!((layer.texture_id_idx, layer.alpha_idx), (0, 1));
+        assert!(layer.unknown_chunks.is_empty());
+        assert_eq!(layer.track_order, [*b"KMTF", *b"KMTA"]);
+        assert_eq!(model.controllers[0].keyframes[0].data, [2.0]);
+    }
+
+    /// Helper storing KGRT before KGTR
+    fn helper() -> Bytes {
+        let tracks = Bytes::default()
+            .raw(&track(b"KGRT", 0, &[0.0, 0.0, 0.0, 1.0]).0)
+            .raw(&track(b"KGTR", 0, &[0.0, 0.0, 10.0]).0);
+        node("Helper01", 0, -1, 0, tracks)
+    }
+
+    #[test]
+    fn helpers() {
+        let model = load_chunk(b"HELP", helper());
+
+        let helper = &model.helpers[0];
+        assert_eq!(helper.name, "Helper01");
+        assert_eq!((helper.translation_idx, helper.rotation_idx), (1, 0));
+        assert_eq!((helper.scaling_idx, helper.visibility_idx), (-1, -1));
+        assert!(helper.unknown_chunks.is_empty());
+        assert_eq!(helper.node_track_order, [*b"KGRT", *b"KGTR"]);
+        assert_eq!(model.controllers[1].keyframes[0].data, [0.0, 0.0, 10.0]);
+    }
+
+    /// Directional light with color, visibility and intensity tracks
+    fn light() -> Bytes {
+        node("Light01", 3, 1, NODE_FLAG_LIGHT, Bytes::default())
+            .u32(LIGHT_TYPE_DIRECTIONAL)
+            .f32s(&[80.0, 200.0])
+            .f32s(&[0.25, 0.5, 1.0])
+            .f32s(&[1.5])
+            .f32s(&[0.0, 0.0, 0.5])
+            .f32s(&[0.2])
+            .raw(&track(b"KLAC", 100, &[1.0, 0.0, 0.0]).0)
+            .raw(&track(b"KLAV", 0, &[1.0]).0)
+            .raw(&track(b"KLAI", 0, &[2.0]).0)
+            .inclusive()
+    }
+
+    #[test]
+    fn lights() {
+        let model = load_chunk(b"LITE", light());
+
+        let light = &model.lights[0];
+        assert_eq!(light.name, "Light01");
+        assert_eq!((light.object_id, light.parent_id), (3, 1));
+        assert_eq!(light.flags, 0);
+        assert!(!light.type_flag_missing);
+        assert_eq!(light.light_type, LIGHT_TYPE_DIRECTIONAL);
+        assert_eq!(
+            (light.attenuation_start, light.attenuation_end),
+            (80.0, 200.0)
+        );
+        // Colors stay blue first, as stored
+        assert_eq!(light.color, [0.25, 0.5, 1.0]);
+        assert_eq!(light.amb_color, [0.0, 0.0, 0.5]);
+        // KLAI follows KLAV here
+        assert_eq!(
+            (light.color_idx, light.visibility_idx, light.intensity_idx),
+            (0, 1, 2)
+        );
+        assert!(light.unknown_tracks.is_empty());
+        assert_eq!(light.track_order, [*b"KLAC", *b"KLAV", *b"KLAI"]);
+        assert_eq!(light.attenuation_start_idx, -1);
+        assert_eq!(model.controllers[0].keyframes[0].data, [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn mdl_lights_are_saved_with_their_type_bit() {
+        let text = "Light \"Glow\" {\n\tObjectId 0,\n\tOmnidirectional,\n}\n";
+        let file = save_to_bytes(&load_mdl_from_str(text).unwrap()).unwrap();
+
+        // Tag, chunk, light and node sizes, name, ObjectID and parent come before the flags
+        let lite = file.windows(4).position(|tag| tag == b"LITE").unwrap();
+        let flags = lite + 16 + 0x50 + 8;
+        assert_eq!(file[flags..flags + 4], NODE_FLAG_LIGHT.to_le_bytes());
+    }
+
+    /// Camera storing its target track before its rotation track
+    fn camera() -> Bytes {
+        Bytes::default()
+            .name("Portrait", 0x50)
+            .f32s(&[120.0, 0.0, 80.0])
+            .f32s(&[0.75, 1000.0, 8.0])
+            .f32s(&[0.0, 0.0, 60.0])
+            .raw(&track(b"KTTR", 0, &[0.0, 0.0, 20.0]).0)
+            .raw(&track(b"KCRL", 0, &[0.5]).0)
+            .inclusive()
+    }
+
+    #[test]
+    fn cameras() {
+        let model = load_chunk(b"CAMS", camera());
+
+        let camera = &model.cameras[0];
+        assert_eq!(camera.name, "Portrait");
+        assert_eq!(camera.position, [120.0, 0.0, 80.0]);
+        assert_eq!(
+            (camera.field_of_view, camera.far_clip, camera.near_clip),
+            (0.75, 1000.0, 8.0)
+        );
+        assert_eq!(camera.target_position, [0.0, 0.0, 60.0]);
+        assert_eq!((camera.translation_idx, camera.rotation_idx), (-1, 1));
+        assert_eq!(camera.target_translation_idx, 0);
+        assert_eq!(camera.track_order, [*b"KTTR", *b"KCRL"]);
+    }
+
+    /// A box then a sphere
+    fn shapes() -> Bytes {
+        // No inclusive size around the shapes, only around their nodes
+        node("Box01", 0, -1, NODE_FLAG_COLLISION_SHAPE, Bytes::default())
+            .u32(COLLISION_SHAPE_BOX)
+            .f32s(&[-10.0, -10.0, 0.0, 10.0, 10.0, 50.0])
+            .raw(
+                &node(
+                    "Sphere01",
+                    1,
+                    0,
+                    NODE_FLAG_COLLISION_SHAPE,
+                    Bytes::default(),
+                )
+                .0,
+            )
+            .u32(COLLISION_SHAPE_SPHERE)
+            .f32s(&[0.0, 0.0, 30.0, 25.0])
+    }
+
+    #[test]
+    fn collision_shapes() {
+        let model = load_chunk(b"CLID", shapes());
+
+        let [cube, sphere] = &model.collision_shapes[..] else {
+            panic!("expected two shapes");
+        };
+        assert_eq!(cube.shape_type, COLLISION_SHAPE_BOX);
+        assert_eq!(cube.vertices, [[-10.0, -10.0, 0.0], [10.0, 10.0, 50.0]]);
+        assert_eq!(cube.flags, 0);
+        assert_eq!((sphere.object_id, sphere.parent_id), (1, 0));
+        assert_eq!(sphere.vertices, [[0.0, 0.0, 30.0]]);
+        assert_eq!(sphere.radius, 25.0);
+    }
+
+    #[test]
+    fn unknown_collision_shape_is_an_error() {
+        let mut file = Bytes::default().raw(b"MDLX");
+        let shape = node("Shape", 0, -1, NODE_FLAG_COLLISION_SHAPE, Bytes::default()).u32(7);
+        file = file.raw(b"CLID").u32(shape.0.len() as u32).raw(&shape.0);
+        assert!(load_from_bytes(&file.0).is_err());
+    }
+
+    #[test]
+    fn oversized_chunk_is_an_error() {
+        let file = Bytes::default()
+            .raw(b"MDLX")
+            .raw(b"XTRA")
+            .u32(u32::MAX)
+            .raw(&[1, 2, 3]);
+        assert!(load_from_bytes(&file.0).is_err());
+    }
+
+    /// One triangle on a single vertex, with a Selectable value other than 0 or 4
+    fn geoset() -> Bytes {
+        Bytes::default()
+            .raw(b"VRTX")
+            .u32(1)
+            .f32s(&[1.0, 2.0, 3.0])
+            .raw(b"NRMS")
+            .u32(1)
+            .f32s(&[0.0, 0.0, 1.0])
+            .raw(b"PTYP")
+            .u32(1)
+            .u32(PRIMITIVE_TRIANGLES)
+            .raw(b"PCNT")
+            .u32(1)
+            .u32(3)
+            .raw(b"PVTX")
+            .u32(3)
+            .raw(&[0; 6])
+            .raw(b"GNDX")
+            .u32(1)
+            .raw(&[0])
+            .raw(b"MTGC")
+            .u32(1)
+            .u32(1)
+            .raw(b"MATS")
+            .u32(1)
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            .u32(1)
+            .f32s(&[3.0, 1.0, 2.0, 3.0, 1.0, 2.0, 3.0])
+            .u32(0)
+            .raw(b"UVAS")
+            .u32(1)
+            .raw(b"UVBS")
+            .u32(1)
+            .f32s(&[0.5, 0.5])
+            .inclusive()
+    }
+
+    #[test]
+    fn geosets() {
+        let model = load_chunk(b"GEOS", geoset());
+
+        let geoset = &model.geosets[0];
+        assert_eq!(geoset.faces.len(), 1);
+        assert_eq!(geoset.selectable, 1);
+        assert!(!geoset.is_unselectable());
+    }
+
+    #[test]
+    fn geoset_with_fewer_uv_sets_than_announced() {
+        let geoset = Bytes::default()
+            .raw(b"VRTX")
+            .u32(1)
+            .f32s(&[1.0, 2.0, 3.0])
+            .raw(b"UVAS")
+            .u32(2)
+            .raw(b"UVBS")
+            .u32(1)
+            .f32s(&[0.25, 0.75])
+            .raw(b"GNDX")
+            .u32(1)
+            .raw(&[0])
+            .inclusive();
+        let model = load_from_bytes(&mdx_with(b"GEOS", geoset).0).unwrap();
+
+        let geoset = &model.geosets[0];
+        assert_eq!(geoset.tex_coords[0].uv, [0.25, 0.75]);
+        assert!(geoset.secondary_tex_coords.is_empty());
+        // The tag after the last set is still read as its own sub-chunk
+        assert_eq!(geoset.vertex_groups, [0]);
+        assert!(geoset.unknown_chunks.is_empty());
+    }
+
+    #[test]
+    fn oversized_uv_set_is_an_error() {
+        let geoset = Bytes::default()
+            .raw(b"VRTX")
+            .u32(0)
+            .raw(b"UVAS")
+            .u32(1)
+            .raw(b"UVBS")
+            .u32(u32::MAX)
+            .inclusive();
+        assert!(load_from_bytes(&mdx_with(b"GEOS", geoset).0).is_err());
+    }
+
+    /// Emitter with emission rate and speed tracks
+    fn particle_emitter2() -> Bytes {
+        node("Smoke", 8, 0, NODE_FLAG_PARTICLE_EMITTER, Bytes::default())
+            .f32s(&[60.0, 0.1, 0.3, -20.0, 2.0, 15.0, 40.0, 30.0])
+            .u32(PARTICLE2_FILTER_ADDITIVE)
+            .u32(4)
+            .u32(4)
+            .u32(PARTICLE2_BOTH)
+            .f32s(&[1.0, 0.5])
+            .f32s(&[0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0])
+            .raw(&[255, 128, 0])
+            .f32s(&[10.0, 20.0, 30.0])
+            .raw(&Bytes::default().u32(0).u32(3).u32(1).0.repeat(4))
+            .u32(2)
+            .u32(1)
+            .i32(0)
+            .u32(0)
+            .raw(&track(b"KP2E", 0, &[15.0]).0)
+            .raw(&track(b"KP2S", 0, &[80.0]).0)
+            .inclusive()
+    }
+
+    #[test]
+    fn particle_emitters2() {
+        let model = load_chunk(b"PRE2", particle_emitter2());
+
+        let emitter = &model.particle_emitters2[0];
+        assert_eq!(emitter.flags, 0);
+        assert_eq!((emitter.speed, emitter.lifespan), (60.0, 2.0));
+        assert_eq!((emitter.length, emitter.width), (40.0, 30.0));
+        assert_eq!(emitter.filter_mode, PARTICLE2_FILTER_ADDITIVE);
+        assert_eq!(emitter.head_or_tail, PARTICLE2_BOTH);
+        // Segment colors stay blue first, as stored
+        assert_eq!(emitter.segment_colors[0], [0.0, 0.0, 1.0]);
+        assert_eq!(emitter.segment_alpha, [255, 128, 0]);
+        assert_eq!(emitter.head_intervals, [0, 3, 1]);
+        assert_eq!((emitter.texture_id, emitter.squirt), (2, true));
+        assert_eq!((emitter.emission_rate_idx, emitter.speed_idx), (0, 1));
+        assert!(emitter.unknown_tracks.is_empty());
+        assert_eq!(emitter.track_order, [*b"KP2E", *b"KP2S"]);
+    }
+
+    #[test]
+    fn zero_inclusive_size_is_an_error() {
+        for tag in [
+            b"BONE", b"HELP", b"MTLS", b"PRE2", b"LITE", b"PREM", b"RIBB", b"ATCH", b"TXAN",
+            b"GEOA", b"CAMS", b"EVTS", b"CLID",
+        ] {
+            let file = mdx_with(tag, Bytes::default().u32(0).raw(&[0; 0x200]));
+            assert!(load_from_bytes(&file.0).is_err());
+        }
+    }
+
+    /// Ribbon with color and height tracks
+    fn ribbon(flags: u32) -> Bytes {
+        node("Ribbon01", 2, 0, flags, Bytes::default())
+            .f32s(&[20.0, 10.0, 0.8])
+            .f32s(&[1.0, 0.5, 0.0])
+            .f32s(&[0.6])
+            .u32(0)
+            .u32(30)
+            .u32(2)
+            .u32(1)
+            .u32(0)
+            .f32s(&[50.0])
+            .raw(&track(b"KRCO", 0, &[0.0, 0.0, 1.0]).0)
+            .raw(&track(b"KRHA", 0, &[30.0]).0)
+            .inclusive()
+    }
+
+    #[test]
+    fn ribbon_emitters() {
+        let model = load_chunk(b"RIBB", ribbon(NODE_FLAG_RIBBON_EMITTER));
+
+        let emitter = &model.ribbon_emitters[0];
+        assert_eq!(emitter.flags, 0);
+        assert!(!emitter.type_flag_missing);
+        assert_eq!(
+            (emitter.height_above, emitter.height_below, emitter.alpha),
+            (20.0, 10.0, 0.8)
+        );
+        assert_eq!(emitter.color, [1.0, 0.5, 0.0]);
+        assert_eq!((emitter.lifespan, emitter.emission_rate), (0.6, 30));
+        assert_eq!((emitter.rows, emitter.columns), (2, 1));
+        assert_eq!(emitter.gravity, 50.0);
+        assert_eq!((emitter.color_idx, emitter.height_above_idx), (0, 1));
+        assert!(emitter.unknown_tracks.is_empty());
+        assert_eq!(emitter.track_order, [*b"KRCO", *b"KRHA"]);
+
+        // Some tools write another bit in place of the type bit, both are kept as loaded
+        let model = load_chunk(b"RIBB", ribbon(RIBBON_FLAG_ALT_TYPE));
+        let emitter = &model.ribbon_emitters[0];
+        assert_eq!(emitter.flags, RIBBON_FLAG_ALT_TYPE);
+        assert!(emitter.type_flag_missing);
+    }
+
+    /// Emitter spawning a model, with a translation and three emitter tracks
+    fn particle_emitter() -> Bytes {
+        let flags = NODE_FLAG_PARTICLE_EMITTER | PARTICLE_FLAG_EMITTER_USES_MDL;
+        node("Blood", 4, 0, flags, track(b"KGTR", 0, &[0.0, 0.0, 10.0]))
+            .f32s(&[12.0, 9.8, 0.1, 0.2])
+            .name("Objects\\Spawnmodels\\Blood.mdl", 0x104)
+            .f32s(&[1.5, 100.0])
+            .raw(&track(b"KPEE", 0, &[20.0]).0)
+            .raw(&track(b"KPEV", 0, &[1.0]).0)
+            .raw(&track(b"KPES", 0, &[50.0]).0)
+            .inclusive()
+    }
+
+    #[test]
+    fn particle_emitters() {
+        let model = load_chunk(b"PREM", particle_emitter());
+
+        let emitter = &model.particle_emitters[0];
+        assert_eq!(emitter.name, "Blood");
+        assert_eq!(emitter.flags, PARTICLE_FLAG_EMITTER_USES_MDL);
+        assert_eq!(emitter.path, "Objects\\Spawnmodels\\Blood.mdl");
+        assert_eq!(
+            (emitter.emission_rate, emitter.gravity, emitter.latitude),
+            (12.0, 9.8, 0.2)
+        );
+        assert_eq!((emitter.lifespan, emitter.speed), (1.5, 100.0));
+        assert_eq!(
+            (
+                emitter.translation_idx,
+                emitter.emission_rate_idx,
+                emitter.visibility_idx
+            ),
+            (0, 1, 2)
+        );
+        assert_eq!(emitter.speed_idx, 3);
+        assert!(emitter.unknown_tracks.is_empty());
+        assert_eq!(emitter.track_order, [*b"KPEE", *b"KPEV", *b"KPES"]);
+    }
+
+    /// Attachment with visibility and an unknown track
+    fn attachment() -> Bytes {
+        node(
+            "Hand Left Ref",
+            5,
+            2,
+            NODE_FLAG_ATTACHMENT,
+            Bytes::default(),
+        )
+        .name("", 0x104)
+        .u32(3)
+        .raw(&track(b"KATV", 0, &[0.0]).0)
+        .raw(b"KATX")
+        .u32(1)
+        .inclusive()
+    }
+
+    #[test]
+    fn attachments() {
+        let model = load_chunk(b"ATCH", attachment());
+
+        let attachment = &model.attachments[0];
+        assert_eq!(attachment.name, "Hand Left Ref");
+        assert_eq!((attachment.object_id, attachment.parent_id), (5, 2));
+        assert_eq!(attachment.flags, 0);
+        assert_eq!(attachment.attachment_id, 3);
+        assert_eq!(attachment.visibility_idx, 0);
+        // Unknown tag after the visibility, kept with everything after it
+        assert_eq!(attachment.unknown_tracks[0].tag, *b"KATX");
+        assert_eq!(attachment.unknown_tracks[0].data, [1, 0, 0, 0]);
+    }
+
+    /// Rotation track before the translation one
+    fn texture_animation() -> Bytes {
+        Bytes::default()
+            .raw(&track(b"KTAR", 0, &[0.0, 0.0, 0.0, 1.0]).0)
+            .raw(&track(b"KTAT", 100, &[0.5, 0.0, 0.0]).0)
+            .inclusive()
+    }
+
+    #[test]
+    fn texture_animations() {
+        let model = load_chunk(b"TXAN", texture_animation());
+
+        let animation = &model.texture_animations[0];
+        assert_eq!((animation.translation_idx, animation.rotation_idx), (1, 0));
+        assert!(animation.unknown_chunks.is_empty());
+        assert_eq!(animation.track_order, [*b"KTAR", *b"KTAT"]);
+        assert_eq!(model.controllers[1].keyframes[0].data, [0.5, 0.0, 0.0]);
+    }
+
+    /// Color track before the alpha one
+    fn geoset_animation() -> Bytes {
+        Bytes::default()
+            .f32s(&[0.5])
+            .u32(0)
+            .f32s(&[1.0, 1.0, 1.0])
+            .u32(2)
+            .raw(&track(b"KGAC", 0, &[0.0, 0.0, 1.0]).0)
+            .raw(&track(b"KGAO", 100, &[0.0]).0)
+            .inclusive()
+    }
+
+    #[test]
+    fn geoset_animations() {
+        let model = load_chunk(b"GEOA", geoset_animation());
+
+        let animation = &model.geoset_animations[0];
+        assert_eq!((animation.alpha, animation.geoset_id), (0.5, 2));
+        assert_eq!((animation.alpha_idx, animation.color_idx), (1, 0));
+        assert!(animation.unknown_chunks.is_empty());
+        assert_eq!(animation.track_order, [*b"KGAC", *b"KGAO"]);
+        assert_eq!(model.controllers[1].keyframes[0].data, [0.0]);
+    }
+
+    /// An event with a track, then one without
+    fn events() -> Bytes {
+        // Events have no inclusive size either, and a track of bare frames
+        node("SNDxDEAT", 6, 0, NODE_FLAG_EVENT, Bytes::default())
+            .raw(b"KEVT")
+            .u32(2)
+            .i32(-1)
+            .u32(1200)
+            .u32(3400)
+            .raw(
+                &node(
+                    "FTPxLEFT",
+                    7,
+                    -1,
+                    NODE_FLAG_EVENT | NODE_FLAG_BILLBOARDED,
+                    Bytes::default(),
+                )
+                .0,
+            )
+    }
+
+    #[test]
+    fn event_objects() {
+        let model = load_chunk(b"EVTS", events());
+
+        let [sound, footprint] = &model.event_objects[..] else {
+            panic!("expected two events");
+        };
+        assert!(sound.has_track);
+        assert_eq!(
+            (sound.global_seq_id, &sound.frames[..]),
+            (-1, &[1200, 3400][..])
+        );
+        assert!(!footprint.has_track);
+        assert_eq!(footprint.flags, NODE_FLAG_BILLBOARDED);
+    }
 }
@@ -0,0 +1,308 @@
+use crate::error::MdlError;
+
+/// A single lexical element of the MDL text format
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Word(String), // Keywords and numbers
+    Str(String),  // "Quoted string"
+    OpenBrace,
+    CloseBrace,
+    Comma,
+    Colon,
+}
+
+/// Splits MDL text into tokens and provides typed reads over them
+pub(crate) struct Tokenizer {
+    tokens: Vec<(Token, usize)>, // Token and the line it started on
+    pos: usize,
+}
+
+impl Tokenizer {
+    pub fn new(text: &str) -> Result<Self, MdlError> {
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+        let mut line = 1;
+
+        while let Some(&c) = chars.peek() {
+            match c {
+                '\n' => {
+                    line += 1;
+                    chars.next();
+                }
+                c if c.is_whitespace() => {
+                    chars.next();
+                }
+                '/' => {
+                    // Comment until end of line
+                    chars.next();
+                    if chars.peek() != Some(&'/') {
+                        return Err(MdlError::new("mdl-syntax-error")
+                            .with_arg("line", line)
+                            .with_arg("found", "/"));
+                    }
+                    while let Some(&c) = chars.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        chars.next();
+                    }
+                }
+                '{' => {
+                    tokens.push((Token::OpenBrace, line));
+                    chars.next();
+                }
+                '}' => {
+                    tokens.push((Token::CloseBrace, line));
+                    chars.next();
+                }
+                ',' => {
+                    tokens.push((Token::Comma, line));
+                    chars.next();
+                }
+                ':' => {
+                    tokens.push((Token::Colon, line));
+                    chars.next();
+                }
+                '"' => {
+                    let start_line = line;
+                    chars.next();
+                    let mut s = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            // Only `\"` and `\\` are escapes, paths keep their single backslashes
+                            Some('\\') if matches!(chars.peek(), Some('"' | '\\')) => {
+                                s.extend(chars.next());
+                            }
+                            Some(c) => {
+                                if c == '\n' {
+                                    line += 1;
+                                }
+                                s.push(c);
+                            }
+                            None => {
+                                return Err(MdlError::new("mdl-syntax-error")
+                                    .with_arg("line", start_line)
+                                    .with_arg("found", "unterminated string"));
+                            }
+                        }
+                    }
+                    tokens.push((Token::Str(s), start_line));
+                }
+                _ => {
+                    let mut word = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || matches!(c, '{' | '}' | ',' | ':' | '"' | '/') {
+                            break;
+                        }
+                        word.push(c);
+                        chars.next();
+                    }
+                    tokens.push((Token::Word(word), line));
+                }
+            }
+        }
+
+        Ok(Self { tokens, pos: 0 })
+    }
+
+    pub fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    pub fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    /// Line of the current token (or of the last one at end of input)
+    pub fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(0, |(_, l)| *l)
+    }
+
+    pub fn error(&self, expected: &str) -> MdlError {
+        let found = match self.peek() {
+            Some(Token::Word(w)) => w.clone(),
+            Some(Token::Str(s)) => format!("\"{}\"", s),
+            Some(Token::OpenBrace) => "{".to_string(),
+            Some(Token::CloseBrace) => "}".to_string(),
+            Some(Token::Comma) => ",".to_string(),
+            Some(Token::Colon) => ":".to_string(),
+            None => "end of file".to_string(),
+        };
+        MdlError::new("mdl-syntax-error")
+            .with_arg("line", self.line())
+            .with_arg("expected", expected)
+            .with_arg("found", found)
+    }
+
+    pub fn expect(&mut self, token: Token) -> Result<(), MdlError> {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(match token {
+                Token::OpenBrace => "{",
+                Token::CloseBrace => "}",
+                Token::Comma => ",",
+                Token::Colon => ":",
+                Token::Word(_) | Token::Str(_) => "word",
+            }))
+        }
+    }
+
+    /// Consumes the closing brace if it is next, used as the loop condition for blocks
+    pub fn close_brace(&mut self) -> bool {
+        if self.peek() == Some(&Token::CloseBrace) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the given keyword if it is next
+    pub fn accept_word(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Word(w)) if w == word) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes an optional trailing comma
+    pub fn skip_comma(&mut self) {
+        if self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+        }
+    }
+
+    pub fn read_word(&mut self) -> Result<String, MdlError> {
+        match self.peek() {
+            Some(Token::Word(w)) => {
+                let w = w.clone();
+                self.pos += 1;
+                Ok(w)
+            }
+            _ => Err(self.error("word")),
+        }
+    }
+
+    pub fn read_string(&mut self) -> Result<String, MdlError> {
+        match self.peek() {
+            Some(Token::Str(s)) => {
+                let s = s.clone();
+                self.pos += 1;
+                Ok(s)
+            }
+            _ => Err(self.error("string")),
+        }
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, MdlError> {
+        match self.peek() {
+            Some(Token::Word(w)) => match w.parse::<f32>() {
+                Ok(v) => {
+                    self.pos += 1;
+                    Ok(v)
+                }
+                Err(_) => Err(self.error("number")),
+            },
+            _ => Err(self.error("number")),
+        }
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, MdlError> {
+        match self.peek() {
+            Some(Token::Word(w)) => match w.parse::<i64>() {
+                // 0xFFFFFFFF is written as 4294967295 by some tools and means -1
+                Ok(v) if (i32::MIN as i64..=u32::MAX as i64).contains(&v) => {
+                    self.pos += 1;
+                    Ok(v as i32)
+                }
+                _ => Err(self.error("integer")),
+            },
+            _ => Err(self.error("integer")),
+        }
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, MdlError> {
+        Ok(self.read_i32()? as u32)
+    }
+
+    /// Reads `{ <float>, <float>, ... }` of any length
+    pub fn read_float_list(&mut self) -> Result<Vec<f32>, MdlError> {
+        self.expect(Token::OpenBrace)?;
+        let mut values = Vec::new();
+        while !self.close_brace() {
+            values.push(self.read_f32()?);
+            self.skip_comma();
+        }
+        Ok(values)
+    }
+
+    /// Reads `{ <float>, ... }` and checks it has exactly N values
+    pub fn read_floats<const N: usize>(&mut self) -> Result<[f32; N], MdlError> {
+        let line = self.line();
+        let values = self.read_float_list()?;
+        values.try_into().map_err(|v: Vec<f32>| {
+            MdlError::new("mdl-syntax-error")
+                .with_arg("line", line)
+                .with_arg("expected", format!("{} values", N))
+                .with_arg("found", format!("{} values", v.len()))
+        })
+    }
+
+    /// Reads `{ <long>, <long>, ... }` of any length
+    pub fn read_int_list(&mut self) -> Result<Vec<i32>, MdlError> {
+        self.expect(Token::OpenBrace)?;
+        let mut values = Vec::new();
+        while !self.close_brace() {
+            values.push(self.read_i32()?);
+            self.skip_comma();
+        }
+        Ok(values)
+    }
+
+    /// Skips a `{ ... }` block including all nested blocks
+    pub fn skip_block(&mut self) -> Result<(), MdlError> {
+        self.expect(Token::OpenBrace)?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.advance() {
+                Some(Token::OpenBrace) => depth += 1,
+                Some(Token::CloseBrace) => depth -= 1,
+                Some(_) => {}
+                None => return Err(self.error("}")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips the rest of a field whose key was already read: everything up to
+    /// and including the next comma, or up to and including a complete block
+    pub fn skip_value(&mut self) -> Result<(), MdlError> {
+        loop {
+            match self.peek() {
+                None | Some(Token::CloseBrace) => return Ok(()),
+                Some(Token::Comma) => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(Token::OpenBrace) => {
+                    self.skip_block()?;
+                    self.skip_comma();
+                    return Ok(());
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+}
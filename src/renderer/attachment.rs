@@ -0,0 +1,78 @@
+use crate::animation::AnimationSystem;
+use crate::model::model::Model;
+use crate::renderer::line_vertex::LineVertex;
+use crate::renderer::renderer::Renderer;
+use wgpu::util::DeviceExt;
+
+impl Renderer {
+    /// Crosses on the attachment points, at their animated position when an animation
+    /// system is given. Their names are kept in `attachment_labels` for the UI
+    pub(crate) fn generate_attachment_markers(
+        &mut self,
+        model: &Model,
+        animation_system: Option<&AnimationSystem>,
+    ) {
+        let marker_color = [0.2, 1.0, 0.4]; // Green for attachment points
+        let size = self.camera.distance * 0.015; // Same size on screen at any zoom
+
+        self.attachment_labels.clear();
+        let mut vertices = Vec::new();
+        for (idx, attachment) in model.attachments.iter().enumerate() {
+            let state = animation_system
+                .and_then(|system| system.attachments.get(idx))
+                .filter(|state| state.is_ready);
+            if state.is_some_and(|state| !state.visible) {
+                continue;
+            }
+            let position = match state {
+                Some(state) => [state.abs_vector.x, state.abs_vector.y, state.abs_vector.z],
+                None => model.pivot_point(attachment.object_id),
+            };
+            // Lines are not mirrored by the shader, the mesh is
+            let center = [position[0], -position[1], position[2]];
+
+            for axis in 0..3 {
+                let mut start = center;
+                let mut end = center;
+                start[axis] -= size;
+                end[axis] += size;
+                vertices.push(LineVertex {
+                    position: start,
+                    color: marker_color,
+                });
+                vertices.push(LineVertex {
+                    position: end,
+                    color: marker_color,
+                });
+            }
+            self.attachment_labels
+                .push((attachment.name.trim().to_string(), center));
+        }
+
+        self.num_attachment_lines = (vertices.len() / 2) as u32;
+        if vertices.is_empty() {
+            return;
+        }
+
+        // Markers move every frame, so the buffer is only recreated when it grows
+        let contents: &[u8] = bytemuck::cast_slice(&vertices);
+        if self.attachment_vertex_buffer.size() < contents.len() as u64 {
+            self.attachment_vertex_buffer =
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Attachment Vertex Buffer"),
+                        contents,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    });
+        } else {
+            self.queue
+                .write_buffer(&self.attachment_vertex_buffer, 0, contents);
+        }
+    }
+
+    /// Hide the attachment markers and their labels
+    pub(crate) fn clear_attachment_markers(&mut self) {
+        self.num_attachment_lines = 0;
+        self.attachment_labels.clear();
+    }
+}
@@ -3,6 +3,46 @@ use crate::renderer::line_vertex::LineVertex;
 use crate::renderer::renderer::Renderer;
 use wgpu::util::DeviceExt;
 
+/// Line list for the 12 edges of an axis-aligned box
+fn box_lines(min: [f32; 3], max: [f32; 3], color: [f32; 3]) -> Vec<LineVertex> {
+    let vertices = [
+        // Bottom face (Z = min[2])
+        [min[0], min[1], min[2]], // 0: min corner
+        [max[0], min[1], min[2]], // 1: +X
+        [max[0], max[1], min[2]], // 2: +X+Y
+        [min[0], max[1], min[2]], // 3: +Y
+        // Top face (Z = max[2])
+        [min[0], min[1], max[2]], // 4: +Z
+        [max[0], min[1], max[2]], // 5: +X+Z
+        [max[0], max[1], max[2]], // 6: +X+Y+Z (max corner)
+        [min[0], max[1], max[2]], // 7: +Y+Z
+    ];
+
+    let mut edges = Vec::new();
+    for i in 0..4 {
+        // Bottom face, top face and the vertical edge connecting them
+        edges.push((i, (i + 1) % 4));
+        edges.push((4 + i, 4 + (i + 1) % 4));
+        edges.push((i, i + 4));
+    }
+
+    edges
+        .into_iter()
+        .flat_map(|(a, b)| {
+            [
+                LineVertex {
+                    position: vertices[a],
+                    color,
+                },
+                LineVertex {
+                    position: vertices[b],
+                    color,
+                },
+            ]
+        })
+        .collect()
+}
+
 impl Renderer {
     pub(crate) fn generate_bounding_box_lines(&mut self, model: &Model) {
         let bbox_color = [1.0, 1.0, 0.0]; // Yellow for bounding box - will be updated from settings
@@ -14,7 +54,7 @@ impl Renderer {
         model: &Model,
         bbox_color: [f32; 3],
     ) {
-        let mut bbox_vertices = Vec::new();
+        let mut bbox_vertices: Vec<LineVertex> = Vec::new();
 
         // Calculate overall model bounding box
         let mut overall_min = [f32::INFINITY, f32::INFINITY, f32::INFINITY];
@@ -39,57 +79,7 @@ impl Renderer {
         }
 
         if has_valid_bbox {
-            // Create wireframe cube for the overall model bounding box
-            let vertices = [
-                // Bottom face (Z = min[2])
-                [overall_min[0], overall_min[1], overall_min[2]], // 0: min corner
-                [overall_max[0], overall_min[1], overall_min[2]], // 1: +X
-                [overall_max[0], overall_max[1], overall_min[2]], // 2: +X+Y
-                [overall_min[0], overall_max[1], overall_min[2]], // 3: +Y
-                // Top face (Z = max[2])
-                [overall_min[0], overall_min[1], overall_max[2]], // 4: +Z
-                [overall_max[0], overall_min[1], overall_max[2]], // 5: +X+Z
-                [overall_max[0], overall_max[1], overall_max[2]], // 6: +X+Y+Z (max corner)
-                [overall_min[0], overall_max[1], overall_max[2]], // 7: +Y+Z
-            ];
-
-            // Bottom face edges
-            for i in 0..4 {
-                let next = (i + 1) % 4;
-                bbox_vertices.push(LineVertex {
-                    position: vertices[i],
-                    color: bbox_color,
-                });
-                bbox_vertices.push(LineVertex {
-                    position: vertices[next],
-                    color: bbox_color,
-                });
-            }
-
-            // Top face edges
-            for i in 4..8 {
-                let next = 4 + ((i - 4 + 1) % 4);
-                bbox_vertices.push(LineVertex {
-                    position: vertices[i],
-                    color: bbox_color,
-                });
-                bbox_vertices.push(LineVertex {
-                    position: vertices[next],
-                    color: bbox_color,
-                });
-            }
-
-            // Vertical edges connecting bottom to top
-            for i in 0..4 {
-                bbox_vertices.push(LineVertex {
-                    position: vertices[i],
-                    color: bbox_color,
-                });
-                bbox_vertices.push(LineVertex {
-                    position: vertices[i + 4],
-                    color: bbox_color,
-                });
-            }
+            bbox_vertices = box_lines(overall_min, overall_max, bbox_color);
 
             let box_size = [
                 overall_max[0] - overall_min[0],
@@ -122,4 +112,36 @@ impl Renderer {
             println!("No valid bounding boxes found in geosets");
         }
     }
+
+    /// Box of the per-sequence extent (SEQS), drawn for the selected sequence
+    pub(crate) fn generate_sequence_extent_lines(
+        &mut self,
+        model: &Model,
+        sequence_idx: usize,
+        color: [f32; 3],
+    ) {
+        self.sequence_extent_index = Some(sequence_idx);
+
+        // Sequences without an extent have min == max (usually all zeros)
+        let vertices = model
+            .sequences
+            .get(sequence_idx)
+            .filter(|seq| seq.minimum_extent != seq.maximum_extent)
+            .map(|seq| box_lines(seq.minimum_extent, seq.maximum_extent, color))
+            .unwrap_or_default();
+
+        if vertices.is_empty() {
+            self.num_sequence_extent_lines = 0;
+            return;
+        }
+
+        self.sequence_extent_vertex_buffer =
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Sequence Extent Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+        self.num_sequence_extent_lines = (vertices.len() / 2) as u32;
+    }
 }
@@ -178,6 +178,12 @@ impl CameraController {
         self.state.target[2] = offset_z; // Z не меняется при горизонтальном вращении
     }
 
+    /// Frame a bounding sphere, Reset Camera returns to this view afterwards
+    pub fn frame(&mut self, center: [f32; 3], radius: f32) {
+        self.state.frame(center, radius);
+        self.last_mouse_pos = None;
+    }
+
     /// Reset camera to defaults
     pub fn reset(&mut self) {
         self.state.reset();
@@ -9,6 +9,9 @@ pub struct CameraState {
     pub default_pitch: f32,
     pub default_distance: f32,
     pub default_target: [f32; 3],
+    pub roll: f32,                // Radians around the view direction
+    pub field_of_view: f32,       // Vertical, in radians
+    pub clip: Option<(f32, f32)>, // Near and far planes, the display settings ones when None
 }
 
 impl CameraState {
@@ -22,6 +25,29 @@ impl CameraState {
             default_pitch: pitch,
             default_distance: distance,
             default_target: target,
+            roll: 0.0,
+            field_of_view: 45.0_f32.to_radians(),
+            clip: None,
+        }
+    }
+
+    /// View from `eye` towards `target`, as a model camera (CAMS) sees the world
+    pub fn look_through(
+        eye: [f32; 3],
+        target: [f32; 3],
+        roll: f32,
+        field_of_view: f32,
+        clip: (f32, f32),
+    ) -> Self {
+        let offset = nalgebra_glm::make_vec3(&eye) - nalgebra_glm::make_vec3(&target);
+        let distance = nalgebra_glm::length(&offset).max(1e-3);
+        let yaw = offset.y.atan2(offset.x);
+        let pitch = (offset.z / distance).clamp(-1.0, 1.0).asin();
+        Self {
+            roll,
+            field_of_view,
+            clip: Some(clip),
+            ..Self::new(yaw, pitch, distance, target)
         }
     }
 
@@ -32,6 +58,15 @@ impl CameraState {
         self.target = self.default_target;
     }
 
+    /// Center the view on a bounding sphere and make it the new default view
+    pub fn frame(&mut self, center: [f32; 3], radius: f32) {
+        // Fit the sphere into the 45 degree field of view
+        let distance = radius / (45.0_f32.to_radians() / 2.0).sin();
+        self.default_target = center;
+        self.default_distance = distance.clamp(10.0, 1000.0);
+        self.reset();
+    }
+
     pub fn get_orientation(&self) -> (f32, f32) {
         (self.yaw, self.pitch)
     }
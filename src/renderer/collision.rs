@@ -0,0 +1,163 @@
+use crate::animation::AnimationSystem;
+use crate::model::collision::{
+    COLLISION_SHAPE_BOX, COLLISION_SHAPE_CYLINDER, COLLISION_SHAPE_PLANE, COLLISION_SHAPE_SPHERE,
+    CollisionShape,
+};
+use crate::model::model::Model;
+use crate::renderer::line_vertex::LineVertex;
+use crate::renderer::renderer::Renderer;
+use nalgebra_glm as glm;
+use wgpu::util::DeviceExt;
+
+/// Segments of the circles spheres and cylinders are drawn with
+const CIRCLE_SEGMENTS: usize = 24;
+
+impl Renderer {
+    /// Wireframes of the collision shapes, moved by their nodes when an animation
+    /// system is given
+    pub(crate) fn generate_collision_shapes(
+        &mut self,
+        model: &Model,
+        animation_system: Option<&AnimationSystem>,
+    ) {
+        let shape_color = [1.0, 0.6, 0.1]; // Orange for collision shapes
+
+        let mut vertices = Vec::new();
+        for (idx, shape) in model.collision_shapes.iter().enumerate() {
+            let state = animation_system
+                .and_then(|system| system.collision_shapes.get(idx))
+                .filter(|state| state.is_ready);
+            let pivot = glm::make_vec3(&model.pivot_point(shape.object_id));
+            // Shape points are carried by the node like skinned vertices are by their bone.
+            // Lines are not mirrored by the shader, the mesh is
+            let place = |point: glm::Vec3| {
+                let point = match state {
+                    Some(state) => state.abs_matrix * (point - pivot) + state.abs_vector,
+                    None => point,
+                };
+                [point.x, -point.y, point.z]
+            };
+
+            for (start, end) in shape_edges(shape) {
+                vertices.push(LineVertex {
+                    position: place(start),
+                    color: shape_color,
+                });
+                vertices.push(LineVertex {
+                    position: place(end),
+                    color: shape_color,
+                });
+            }
+        }
+
+        self.num_collision_lines = (vertices.len() / 2) as u32;
+        if vertices.is_empty() {
+            return;
+        }
+
+        // Shapes move every frame, so the buffer is only recreated when it grows
+        let contents: &[u8] = bytemuck::cast_slice(&vertices);
+        if self.collision_vertex_buffer.size() < contents.len() as u64 {
+            self.collision_vertex_buffer =
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Collision Vertex Buffer"),
+                        contents,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    });
+        } else {
+            self.queue
+                .write_buffer(&self.collision_vertex_buffer, 0, contents);
+        }
+    }
+
+    /// Hide the collision shapes
+    pub(crate) fn clear_collision_shapes(&mut self) {
+        self.num_collision_lines = 0;
+    }
+}
+
+/// Lines of a shape in the model space of its bind pose
+fn shape_edges(shape: &CollisionShape) -> Vec<(glm::Vec3, glm::Vec3)> {
+    let vertex = |idx: usize| {
+        shape
+            .vertices
+            .get(idx)
+            .map_or_else(glm::Vec3::zeros, |v| glm::make_vec3(v))
+    };
+    let (a, b) = (vertex(0), vertex(1));
+
+    let mut edges = Vec::new();
+    match shape.shape_type {
+        COLLISION_SHAPE_BOX => {
+            // 12 edges between the corners picked from either vertex per axis
+            let corner = |bits: usize| {
+                glm::vec3(
+                    if bits & 1 != 0 { b.x } else { a.x },
+                    if bits & 2 != 0 { b.y } else { a.y },
+                    if bits & 4 != 0 { b.z } else { a.z },
+                )
+            };
+            for bits in 0..8 {
+                for axis in [1, 2, 4] {
+                    if bits & axis == 0 {
+                        edges.push((corner(bits), corner(bits | axis)));
+                    }
+                }
+            }
+        }
+        COLLISION_SHAPE_PLANE => {
+            // Rectangle in the XY plane between the two corners
+            let corners = [a, glm::vec3(b.x, a.y, a.z), b, glm::vec3(a.x, b.y, b.z)];
+            for idx in 0..4 {
+                edges.push((corners[idx], corners[(idx + 1) % 4]));
+            }
+        }
+        COLLISION_SHAPE_SPHERE => {
+            // One circle around each axis
+            let (x, y, z) = (glm::Vec3::x(), glm::Vec3::y(), glm::Vec3::z());
+            for (u, v) in [(x, y), (y, z), (z, x)] {
+                push_circle(&mut edges, a, u * shape.radius, v * shape.radius);
+            }
+        }
+        COLLISION_SHAPE_CYLINDER => {
+            // Circles around both ends, joined by four lines along the axis
+            let axis = b - a;
+            let axis = if glm::length(&axis) > 1e-6 {
+                glm::normalize(&axis)
+            } else {
+                glm::Vec3::z()
+            };
+            let helper = if axis.z.abs() < 0.9 {
+                glm::Vec3::z()
+            } else {
+                glm::Vec3::x()
+            };
+            let u = glm::normalize(&glm::cross(&axis, &helper)) * shape.radius;
+            let v = glm::cross(&axis, &u);
+            push_circle(&mut edges, a, u, v);
+            push_circle(&mut edges, b, u, v);
+            for side in [u, v, -u, -v] {
+                edges.push((a + side, b + side));
+            }
+        }
+        _ => {}
+    }
+    edges
+}
+
+/// Circle around `center` in the plane of `u` and `v`, whose lengths are the radius
+fn push_circle(
+    edges: &mut Vec<(glm::Vec3, glm::Vec3)>,
+    center: glm::Vec3,
+    u: glm::Vec3,
+    v: glm::Vec3,
+) {
+    let point = |segment: usize| {
+        let angle = segment as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+        center + u * angle.cos() + v * angle.sin()
+    };
+    for segment in 0..CIRCLE_SEGMENTS {
+        edges.push((point(segment), point(segment + 1)));
+    }
+}
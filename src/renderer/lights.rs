@@ -0,0 +1,206 @@
+use crate::animation::AnimationSystem;
+use crate::animation::interpolation::bgr_to_rgb;
+use crate::model::light::{LIGHT_TYPE_DIRECTIONAL, Light};
+use crate::model::model::Model;
+use crate::renderer::line_vertex::LineVertex;
+use crate::renderer::renderer::Renderer;
+use nalgebra_glm as glm;
+use wgpu::util::DeviceExt;
+
+/// Lights beyond this count are left out of the lighting pass
+pub const MAX_LIGHTS: usize = 8;
+
+/// One light as the shader sees it, mirrored in Y like the mesh
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightData {
+    pub position: [f32; 4],  // xyz + LIGHT_TYPE_*
+    pub direction: [f32; 4], // xyz: where directional lights shine
+    pub color: [f32; 4],     // rgb * intensity + attenuation start
+    pub ambient: [f32; 4],   // ambient rgb * ambient intensity + attenuation end
+}
+
+/// Lights uniform (group 0, binding 1), read by the mesh fragment shader
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniform {
+    pub count: [u32; 4], // x = lights in use
+    pub lights: [LightData; MAX_LIGHTS],
+}
+
+/// A light at the current frame, in model space
+struct LightView {
+    light_type: u32,
+    position: glm::Vec3,
+    direction: glm::Vec3,
+    color: glm::Vec3,
+    intensity: f32,
+    amb_color: glm::Vec3,
+    amb_intensity: f32,
+    attenuation_start: f32,
+    attenuation_end: f32,
+}
+
+impl LightView {
+    /// Lights with nothing to add are not worth a slot
+    fn is_active(&self) -> bool {
+        self.intensity > 0.0 && self.color.max() > 0.0
+            || self.amb_intensity > 0.0 && self.amb_color.max() > 0.0
+    }
+}
+
+impl Renderer {
+    /// Uploads the first `MAX_LIGHTS` active lights of the model, animated when an
+    /// animation system is given. Without a model only the default light is left
+    pub(crate) fn update_lights(
+        &mut self,
+        model: Option<&Model>,
+        animation_system: Option<&AnimationSystem>,
+    ) {
+        let mut uniform: LightUniform = bytemuck::Zeroable::zeroed();
+        let views = model.map_or_else(Vec::new, |model| light_views(model, animation_system));
+        for view in views.iter().filter(|view| view.is_active()) {
+            let Some(slot) = uniform.lights.get_mut(uniform.count[0] as usize) else {
+                break;
+            };
+            // The mesh is mirrored by the shader, the lights are mirrored here
+            let color = view.color * view.intensity;
+            let ambient = view.amb_color * view.amb_intensity;
+            *slot = LightData {
+                position: [
+                    view.position.x,
+                    -view.position.y,
+                    view.position.z,
+                    view.light_type as f32,
+                ],
+                direction: [view.direction.x, -view.direction.y, view.direction.z, 0.0],
+                color: [color.x, color.y, color.z, view.attenuation_start],
+                ambient: [ambient.x, ambient.y, ambient.z, view.attenuation_end],
+            };
+            uniform.count[0] += 1;
+        }
+        self.queue
+            .write_buffer(&self.light_buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    /// Crosses on the lights in their color, with a line along the direction
+    /// directional lights shine to
+    pub(crate) fn generate_light_gizmos(
+        &mut self,
+        model: &Model,
+        animation_system: Option<&AnimationSystem>,
+    ) {
+        let size = self.camera.distance * 0.02; // Same size on screen at any zoom
+
+        let mut vertices = Vec::new();
+        for view in light_views(model, animation_system) {
+            // Dark lights are brightened so their gizmo stays visible
+            let brightest = view.color.max();
+            let color = if brightest > 0.0 {
+                (view.color / brightest).into()
+            } else {
+                [1.0, 1.0, 1.0]
+            };
+            // Lines are not mirrored by the shader, the mesh is
+            let mirror = |v: glm::Vec3| [v.x, -v.y, v.z];
+            let mut line = |start: glm::Vec3, end: glm::Vec3| {
+                vertices.push(LineVertex {
+                    position: mirror(start),
+                    color,
+                });
+                vertices.push(LineVertex {
+                    position: mirror(end),
+                    color,
+                });
+            };
+
+            for axis in 0..3 {
+                let mut offset = glm::Vec3::zeros();
+                offset[axis] = size;
+                line(view.position - offset, view.position + offset);
+            }
+            if view.light_type == LIGHT_TYPE_DIRECTIONAL {
+                line(view.position, view.position + view.direction * size * 4.0);
+            }
+        }
+
+        self.num_light_lines = (vertices.len() / 2) as u32;
+        if vertices.is_empty() {
+            return;
+        }
+
+        // Gizmos move every frame, so the buffer is only recreated when it grows
+        let contents: &[u8] = bytemuck::cast_slice(&vertices);
+        if self.light_vertex_buffer.size() < contents.len() as u64 {
+            self.light_vertex_buffer =
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Light Vertex Buffer"),
+                        contents,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    });
+        } else {
+            self.queue
+                .write_buffer(&self.light_vertex_buffer, 0, contents);
+        }
+    }
+
+    /// Hide the light gizmos
+    pub(crate) fn clear_light_gizmos(&mut self) {
+        self.num_light_lines = 0;
+    }
+}
+
+/// The visible lights of the model, at the current frame of the animation system or
+/// with their static values at their pivots. Directional lights shine down their node
+/// Z axis
+fn light_views(model: &Model, animation_system: Option<&AnimationSystem>) -> Vec<LightView> {
+    let down = glm::vec3(0.0, 0.0, -1.0);
+    model
+        .lights
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, light)| {
+            let state = animation_system
+                .and_then(|system| system.lights.get(idx))
+                .filter(|state| state.node.is_ready);
+            let Some(state) = state else {
+                return Some(static_view(model, light, down));
+            };
+            if !state.node.visible {
+                return None;
+            }
+            let node = &state.node;
+            let direction = node.abs_matrix * down;
+            Some(LightView {
+                light_type: light.light_type,
+                position: node.abs_vector,
+                direction: if glm::length(&direction) > 1e-6 {
+                    glm::normalize(&direction)
+                } else {
+                    down
+                },
+                color: state.color,
+                intensity: state.intensity,
+                amb_color: state.amb_color,
+                amb_intensity: state.amb_intensity,
+                attenuation_start: state.attenuation_start,
+                attenuation_end: state.attenuation_end,
+            })
+        })
+        .collect()
+}
+
+fn static_view(model: &Model, light: &Light, direction: glm::Vec3) -> LightView {
+    LightView {
+        light_type: light.light_type,
+        position: glm::make_vec3(&model.pivot_point(light.object_id)),
+        direction,
+        color: bgr_to_rgb(&light.color),
+        intensity: light.intensity,
+        amb_color: bgr_to_rgb(&light.amb_color),
+        amb_intensity: light.amb_intensity,
+        attenuation_start: light.attenuation_start,
+        attenuation_end: light.attenuation_end,
+    }
+}
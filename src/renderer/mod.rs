@@ -5,4 +5,11 @@ mod line_vertex;
 mod geoset_render_info;
 mod team_color;
 mod render;
-mod bounding_box;
\ No newline at end of file
+mod bounding_box;
+mod attachment;
+mod particle_vertex;
+mod particles;
+mod ribbons;
+pub mod spawns;
+mod lights;
+mod collision;
\ No newline at end of file
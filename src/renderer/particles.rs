@@ -0,0 +1,300 @@
+use crate::animation::particles::Particle;
+use crate::material::FilterMode;
+use crate::model::emitter::{
+    PARTICLE2_FILTER_ADDITIVE, PARTICLE2_FILTER_ALPHA_KEY, PARTICLE2_FILTER_BLEND,
+    PARTICLE2_FILTER_MODULATE, PARTICLE2_FILTER_MODULATE_2X, PARTICLE2_FLAG_SORT_PRIMS_FAR_Z,
+    PARTICLE2_FLAG_XY_QUAD, PARTICLE2_HEAD, PARTICLE2_TAIL,
+};
+use crate::model::model::Model;
+use crate::renderer::particle_vertex::ParticleVertex;
+use crate::renderer::renderer::Renderer;
+use nalgebra_glm as glm;
+use std::ops::Range;
+use wgpu::util::DeviceExt;
+
+/// Particles of one emitter, drawn with its texture and filter mode
+pub struct ParticleBatch {
+    pub(crate) filter_mode: u32, // PARTICLE2_FILTER_*
+    pub(crate) texture_id: usize,
+    pub(crate) vertices: Range<u32>,
+}
+
+impl Renderer {
+    /// Quads for the particles, facing the camera unless their emitter lays them flat.
+    /// Emitters are drawn by priority plane, then in model order
+    pub(crate) fn generate_particles(&mut self, model: &Model, particles: &[Particle]) {
+        self.particle_batches.clear();
+
+        // Camera frame, as set up by render()
+        let (yaw, pitch) = (self.camera.yaw, self.camera.pitch);
+        let to_eye = glm::vec3(
+            yaw.cos() * pitch.cos(),
+            yaw.sin() * pitch.cos(),
+            pitch.sin(),
+        );
+        let eye = glm::make_vec3(&self.camera.target) + to_eye * self.camera.distance;
+        let forward = -to_eye;
+        let camera_right = glm::cross(&forward, &glm::vec3(0.0, 0.0, 1.0));
+        let camera_right = if glm::length(&camera_right) > 1e-6 {
+            glm::normalize(&camera_right)
+        } else {
+            glm::vec3(yaw.sin(), -yaw.cos(), 0.0)
+        };
+        let camera_up = glm::cross(&camera_right, &forward);
+
+        // Particles are not mirrored by the shader, the mesh is
+        let mirror = |v: &glm::Vec3| glm::vec3(v.x, -v.y, v.z);
+        let depth = |particle: &Particle| glm::dot(&(mirror(&particle.position) - eye), &forward);
+
+        let mut order: Vec<usize> = (0..model.particle_emitters2.len()).collect();
+        order.sort_by_key(|&idx| model.particle_emitters2[idx].priority_plane);
+
+        let mut vertices = Vec::new();
+        for idx in order {
+            let emitter = &model.particle_emitters2[idx];
+            let mut emitted: Vec<&Particle> =
+                particles.iter().filter(|p| p.emitter == idx).collect();
+            if emitted.is_empty() {
+                continue;
+            }
+            if emitter.has_flag(PARTICLE2_FLAG_SORT_PRIMS_FAR_Z) {
+                // Farthest first
+                emitted.sort_by(|a, b| depth(b).total_cmp(&depth(a)));
+            }
+
+            let first = vertices.len() as u32;
+            for particle in emitted {
+                let position = mirror(&particle.position);
+                let color: [f32; 4] = particle.color.into();
+                let size = particle.scale;
+
+                if emitter.head_or_tail != PARTICLE2_TAIL {
+                    let (right, up) = if emitter.has_flag(PARTICLE2_FLAG_XY_QUAD) {
+                        (glm::vec3(size, 0.0, 0.0), glm::vec3(0.0, size, 0.0))
+                    } else {
+                        (camera_right * size, camera_up * size)
+                    };
+                    push_quad(
+                        &mut vertices,
+                        [
+                            position - right - up,
+                            position + right - up,
+                            position + right + up,
+                            position - right + up,
+                        ],
+                        atlas_cell_uv(emitter.rows, emitter.columns, particle.head_cell),
+                        color,
+                    );
+                }
+
+                if emitter.head_or_tail != PARTICLE2_HEAD {
+                    // The tail stretches back along the velocity, across the view
+                    let tail = mirror(&particle.velocity) * -emitter.tail_length;
+                    let side = glm::cross(&tail, &forward);
+                    if glm::length(&side) > 1e-6 {
+                        let side = glm::normalize(&side) * size;
+                        push_quad(
+                            &mut vertices,
+                            [
+                                position + tail - side,
+                                position + tail + side,
+                                position + side,
+                                position - side,
+                            ],
+                            atlas_cell_uv(emitter.rows, emitter.columns, particle.tail_cell),
+                            color,
+                        );
+                    }
+                }
+            }
+
+            let last = vertices.len() as u32;
+            if last > first {
+                self.particle_batches.push(ParticleBatch {
+                    filter_mode: emitter.filter_mode,
+                    texture_id: emitter.texture_id as usize,
+                    vertices: first..last,
+                });
+            }
+        }
+
+        if vertices.is_empty() {
+            return;
+        }
+
+        // Particles change every frame, so the buffer is only recreated when it grows
+        let contents: &[u8] = bytemuck::cast_slice(&vertices);
+        if self.particle_vertex_buffer.size() < contents.len() as u64 {
+            self.particle_vertex_buffer =
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Particle Vertex Buffer"),
+                        contents,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    });
+        } else {
+            self.queue
+                .write_buffer(&self.particle_vertex_buffer, 0, contents);
+        }
+    }
+
+    /// Stop drawing particles
+    pub(crate) fn clear_particles(&mut self) {
+        self.particle_batches.clear();
+    }
+
+    /// Pipeline for a particle filter mode. Alpha keyed particles are added like
+    /// additive ones, their texture alpha already cuts them out
+    pub(crate) fn particle_pipeline(&self, filter_mode: u32) -> &wgpu::RenderPipeline {
+        let filter_mode = match filter_mode {
+            PARTICLE2_FILTER_ALPHA_KEY => PARTICLE2_FILTER_ADDITIVE,
+            mode => mode,
+        };
+        self.particle_pipelines
+            .get(filter_mode as usize)
+            .unwrap_or(&self.particle_pipelines[0])
+    }
+}
+
+/// Two triangles for the corners, given counter-clockwise from the bottom left
+fn push_quad(
+    vertices: &mut Vec<ParticleVertex>,
+    corners: [glm::Vec3; 4],
+    [u0, v0, u1, v1]: [f32; 4],
+    color: [f32; 4],
+) {
+    let uvs = [[u0, v1], [u1, v1], [u1, v0], [u0, v0]];
+    for corner in [0, 1, 2, 0, 2, 3] {
+        vertices.push(ParticleVertex {
+            position: corners[corner].into(),
+            uv: uvs[corner],
+            color,
+        });
+    }
+}
+
+/// UV rectangle (left, top, right, bottom) of a cell of an emitter texture,
+/// which is split into rows and columns numbered left to right, top to bottom
+pub(super) fn atlas_cell_uv(rows: u32, columns: u32, cell: u32) -> [f32; 4] {
+    let columns = columns.max(1);
+    let rows = rows.max(1);
+    let column = (cell % columns) as f32;
+    let row = ((cell / columns) % rows) as f32;
+    let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+    [
+        column * width,
+        row * height,
+        (column + 1.0) * width,
+        (row + 1.0) * height,
+    ]
+}
+
+/// Particle pipeline that blends like a material layer
+pub(super) fn particle_filter_mode(filter_mode: &FilterMode) -> u32 {
+    match filter_mode {
+        FilterMode::Additive | FilterMode::AddAlpha => PARTICLE2_FILTER_ADDITIVE,
+        FilterMode::Modulate => PARTICLE2_FILTER_MODULATE,
+        FilterMode::Modulate2x => PARTICLE2_FILTER_MODULATE_2X,
+        FilterMode::None | FilterMode::Transparent | FilterMode::Blend => PARTICLE2_FILTER_BLEND,
+    }
+}
+
+/// One pipeline per particle filter mode, in PARTICLE2_FILTER_* order from Blend to
+/// Modulate2x. Particles are depth tested against the model but do not hide each other
+pub(crate) fn create_particle_pipelines(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    layout: &wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+) -> Vec<wgpu::RenderPipeline> {
+    let keep_alpha = wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::Zero,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    };
+    let blend_states = [
+        ("Particle Blend Pipeline", wgpu::BlendState::ALPHA_BLENDING),
+        (
+            "Particle Additive Pipeline",
+            wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            },
+        ),
+        (
+            "Particle Modulate Pipeline",
+            wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::Src,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            },
+        ),
+        (
+            "Particle Modulate 2x Pipeline",
+            wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Src,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            },
+        ),
+    ];
+
+    blend_states
+        .into_iter()
+        .map(|(label, blend)| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: Some("vs_particle"),
+                    buffers: &[ParticleVertex::desc()],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: Some("fs_particle"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(blend),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Cw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: wgpu::TextureFormat::Depth32Float,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+                cache: None,
+            })
+        })
+        .collect()
+}
@@ -1,7 +1,8 @@
-use crate::material::{FilterMode, MaterialUniform, ShadingFlags};
+use crate::material::{FilterMode, Layer, MaterialFlags, MaterialUniform, ShadingFlags};
 use crate::model::model::Model;
 use crate::renderer::geoset_render_info::GeosetRenderInfo;
 use crate::renderer::renderer::Renderer;
+use crate::renderer::vertex::UV_SETS;
 use egui_wgpu::ScreenDescriptor;
 
 impl Renderer {
@@ -11,6 +12,7 @@ impl Renderer {
         show_skeleton: bool,
         show_grid: bool,
         show_bounding_box: bool,
+        show_sequence_extent: bool,
         wireframe_mode: bool,
         far_plane: f32,
         show_geosets: &Vec<bool>,
@@ -29,7 +31,8 @@ impl Renderer {
 
         // Update camera matrix with correct aspect ratio for viewport
         let aspect = viewport_width / viewport_height;
-        let proj = nalgebra_glm::perspective(aspect, 45.0_f32.to_radians(), 0.1, far_plane);
+        let (near, far) = self.camera.clip.unwrap_or((0.1, far_plane));
+        let proj = nalgebra_glm::perspective(aspect, self.camera.field_of_view, near, far);
 
         let eye = nalgebra_glm::vec3(
             self.camera.target[0]
@@ -43,7 +46,12 @@ impl Renderer {
             self.camera.target[1],
             self.camera.target[2],
         );
-        let up = nalgebra_glm::vec3(0.0, 0.0, 1.0); // Z-up coordinate system
+        // Z-up coordinate system, tilted by the roll of model cameras
+        let up = nalgebra_glm::rotate_vec3(
+            &nalgebra_glm::vec3(0.0, 0.0, 1.0),
+            self.camera.roll,
+            &(center - eye),
+        );
         let view = nalgebra_glm::look_at(&eye, &center, &up);
 
         let view_proj = proj * view;
@@ -56,35 +64,38 @@ impl Renderer {
 
         // Helper closure to update material uniform for specific geoset
         // Works with optional model (model_opt): if None, returns defaults
-        let update_material_uniform =
-            |geoset: &GeosetRenderInfo, layer_index: usize| -> MaterialUniform {
-                let (filter_mode, replaceable_id, layer_alpha, shading_flags) =
-                    if let Some(mat_id) = geoset.material_id {
-                        if let Some(m) = model_opt {
-                            if mat_id < m.materials.len() {
-                                let material = &m.materials[mat_id];
-                                if layer_index < material.layers.len() {
-                                    let layer = &material.layers[layer_index];
-                                    let rid = if let Some(tex_id) = layer.texture_id {
-                                        if tex_id < m.textures.len() {
-                                            m.textures[tex_id].replaceable_id
-                                        } else {
-                                            0
-                                        }
+        let update_material_uniform = |geoset_idx: usize, layer_index: usize| -> MaterialUniform {
+            let geoset = &self.geosets[geoset_idx];
+            let (filter_mode, replaceable_id, layer_alpha, shading_flags) =
+                if let Some(mat_id) = geoset.material_id {
+                    if let Some(m) = model_opt {
+                        if mat_id < m.materials.len() {
+                            let material = &m.materials[mat_id];
+                            if layer_index < material.layers.len() {
+                                let layer = &material.layers[layer_index];
+                                let rid = if let Some(tex_id) =
+                                    self.layer_texture_id(mat_id, layer_index, layer)
+                                {
+                                    if tex_id < m.textures.len() {
+                                        m.textures[tex_id].replaceable_id
                                     } else {
                                         0
-                                    };
-                                    // Use get_filter_mode() to respect overrides!
-                                    let filter_mode = layer.get_filter_mode();
-                                    // Use layer methods to get effective values (with overrides)
-                                    let flags = layer.get_shading_flags();
-                                    let shading_bits = ShadingFlags::get_bits(&flags);
-                                    let alpha = layer.get_alpha();
-
-                                    (filter_mode, rid, alpha, shading_bits)
+                                    }
                                 } else {
-                                    (FilterMode::None, 0, 1.0, 0)
-                                }
+                                    0
+                                };
+                                // Use get_filter_mode() to respect overrides!
+                                let filter_mode = layer.get_filter_mode();
+                                // Use layer methods to get effective values (with overrides)
+                                let flags = layer.get_shading_flags();
+                                let shading_bits = ShadingFlags::get_bits(&flags);
+                                // UI override first, then the animated value
+                                let alpha = layer.alpha_override.unwrap_or_else(|| {
+                                    self.layer_state(mat_id, layer_index)
+                                        .map_or(layer.alpha, |state| state.alpha)
+                                });
+
+                                (filter_mode, rid, alpha, shading_bits)
                             } else {
                                 (FilterMode::None, 0, 1.0, 0)
                             }
@@ -93,17 +104,74 @@ impl Renderer {
                         }
                     } else {
                         (FilterMode::None, 0, 1.0, 0)
-                    };
+                    }
+                } else {
+                    (FilterMode::None, 0, 1.0, 0)
+                };
+
+            let mut material_uniform = MaterialUniform::new(
+                self.team_color,
+                replaceable_id,
+                wireframe_mode,
+                filter_mode,
+                layer_alpha,
+                shading_flags,
+            );
+
+            // Geoset animation (GEOA)
+            if let Some(anim) = self.geoset_anim(geoset_idx) {
+                material_uniform.geoset_color =
+                    [anim.color.x, anim.color.y, anim.color.z, anim.alpha];
+            }
 
-                MaterialUniform::new(
-                    self.team_color,
-                    replaceable_id,
-                    wireframe_mode,
-                    filter_mode,
-                    layer_alpha,
-                    shading_flags,
-                )
-            };
+            // Texture animation (TXAN) of the layer
+            if let Some(mat_id) = geoset.material_id
+                && let Some(state) = self.layer_state(mat_id, layer_index)
+            {
+                let m = &state.uv_transform;
+                material_uniform.uv_transform = [
+                    [m[(0, 0)], m[(0, 1)], m[(0, 2)], 0.0],
+                    [m[(1, 0)], m[(1, 1)], m[(1, 2)], 0.0],
+                ];
+            }
+
+            // UV set of the layer (CoordId)
+            if let Some(layer) = geoset
+                .material_id
+                .and_then(|mat_id| model_opt?.materials.get(mat_id))
+                .and_then(|material| material.layers.get(layer_index))
+            {
+                material_uniform.uv_set[0] = layer.coord_id.min(UV_SETS - 1) as f32;
+            }
+            material_uniform
+        };
+
+        // Uniforms of every geoset layer are uploaded before the pass, each in its own
+        // slot: a queue write between draws would land before all of them
+        let mut material_slots: Vec<Vec<u32>> = Vec::with_capacity(self.geosets.len());
+        let mut material_data: Vec<u8> = Vec::new();
+        for (geoset_idx, geoset) in self.geosets.iter().enumerate() {
+            let layer_count = geoset
+                .material_id
+                .and_then(|mat_id| model_opt?.materials.get(mat_id))
+                .map_or(0, |material| material.layers.len());
+            let mut slots = Vec::with_capacity(layer_count);
+            for layer_index in 0..layer_count {
+                slots.push(material_data.len() as u32);
+                let material_uniform = update_material_uniform(geoset_idx, layer_index);
+                material_data.extend_from_slice(bytemuck::bytes_of(&material_uniform));
+                material_data.resize(
+                    material_data
+                        .len()
+                        .next_multiple_of(self.material_slot_size as usize),
+                    0,
+                );
+            }
+            material_slots.push(slots);
+        }
+        if !material_data.is_empty() {
+            self.write_material_slots(&material_data);
+        }
 
         let output = self.surface.get_current_texture()?;
         let view = output
@@ -187,15 +255,27 @@ impl Renderer {
                     // Helper closure to render a geoset with a specific layer
                     let render_geoset_layer =
                         |render_pass: &mut wgpu::RenderPass,
-                         geoset: &GeosetRenderInfo,
+                         geoset_idx: usize,
                          layer_index: usize| {
+                            let geoset = &self.geosets[geoset_idx];
+
+                            // Geosets faded out by their animation are not drawn at all
+                            if self
+                                .geoset_anim(geoset_idx)
+                                .is_some_and(|anim| anim.alpha <= 0.0)
+                            {
+                                return;
+                            }
+
                             // Determine texture bind group for this specific layer
                             let texture_bind_group = if let Some(mat_id) = geoset.material_id {
                                 if mat_id < model.materials.len() {
                                     let material = &model.materials[mat_id];
                                     if layer_index < material.layers.len() {
                                         let layer = &material.layers[layer_index];
-                                        if let Some(tex_id) = layer.texture_id {
+                                        if let Some(tex_id) =
+                                            self.layer_texture_id(mat_id, layer_index, layer)
+                                        {
                                             if tex_id < self.texture_bind_groups.len() {
                                                 &self.texture_bind_groups[tex_id]
                                             } else {
@@ -214,17 +294,15 @@ impl Renderer {
                                 &self.texture_bind_groups[0]
                             };
 
-                            // Update material uniform for this specific geoset
-                            let material_uniform = update_material_uniform(geoset, layer_index);
-
-                            self.queue.write_buffer(
-                                &self.material_buffer,
-                                0,
-                                bytemuck::cast_slice(&[material_uniform]),
-                            );
+                            // Material uniform slot of this geoset layer
+                            let material_offset = material_slots[geoset_idx][layer_index];
 
                             render_pass.set_bind_group(1, texture_bind_group, &[]);
-                            render_pass.set_bind_group(2, &self.material_bind_group, &[]);
+                            render_pass.set_bind_group(
+                                2,
+                                &self.material_bind_group,
+                                &[material_offset],
+                            );
                             render_pass.draw_indexed(
                                 geoset.index_start..(geoset.index_start + geoset.index_count),
                                 0,
@@ -232,6 +310,9 @@ impl Renderer {
                             );
                         };
 
+                    // Geosets ordered by material priority plane (and depth for SortPrimsFarZ)
+                    let draw_order = self.geoset_draw_order(model, &eye);
+
                     // PASS 1: Render opaque materials with depth write enabled
                     let opaque_pipeline = if wireframe_mode {
                         &self.wireframe_pipeline
@@ -240,7 +321,8 @@ impl Renderer {
                     };
                     render_pass.set_pipeline(opaque_pipeline);
 
-                    for (geoset_idx, geoset) in self.geosets.iter().enumerate() {
+                    for &geoset_idx in &draw_order {
+                        let geoset = &self.geosets[geoset_idx];
                         // Skip if geoset is hidden via UI
                         if geoset_idx < show_geosets.len() && !show_geosets[geoset_idx] {
                             continue;
@@ -264,7 +346,11 @@ impl Renderer {
                                     if current_filter_mode == FilterMode::None
                                         || current_filter_mode == FilterMode::Transparent
                                     {
-                                        render_geoset_layer(&mut render_pass, geoset, layer_idx);
+                                        render_geoset_layer(
+                                            &mut render_pass,
+                                            geoset_idx,
+                                            layer_idx,
+                                        );
                                     }
                                 }
                             }
@@ -279,7 +365,8 @@ impl Renderer {
                     };
                     render_pass.set_pipeline(transparent_pipeline);
 
-                    for (geoset_idx, geoset) in self.geosets.iter().enumerate() {
+                    for &geoset_idx in &draw_order {
+                        let geoset = &self.geosets[geoset_idx];
                         // Skip if geoset is hidden via UI
                         if geoset_idx < show_geosets.len() && !show_geosets[geoset_idx] {
                             continue;
@@ -301,7 +388,11 @@ impl Renderer {
                                     // Render ONLY Blend layers in second pass
                                     // Transparent is rendered in first pass with alpha testing
                                     if current_filter_mode == FilterMode::Blend {
-                                        render_geoset_layer(&mut render_pass, geoset, layer_idx);
+                                        render_geoset_layer(
+                                            &mut render_pass,
+                                            geoset_idx,
+                                            layer_idx,
+                                        );
                                     }
                                 }
                             }
@@ -316,7 +407,8 @@ impl Renderer {
                     };
                     render_pass.set_pipeline(additive_pipeline);
 
-                    for (geoset_idx, geoset) in self.geosets.iter().enumerate() {
+                    for &geoset_idx in &draw_order {
+                        let geoset = &self.geosets[geoset_idx];
                         // Skip if geoset is hidden via UI
                         if geoset_idx < show_geosets.len() && !show_geosets[geoset_idx] {
                             continue;
@@ -339,7 +431,11 @@ impl Renderer {
                                     if current_filter_mode == FilterMode::Additive
                                         || current_filter_mode == FilterMode::AddAlpha
                                     {
-                                        render_geoset_layer(&mut render_pass, geoset, layer_idx);
+                                        render_geoset_layer(
+                                            &mut render_pass,
+                                            geoset_idx,
+                                            layer_idx,
+                                        );
                                     }
                                 }
                             }
@@ -347,6 +443,51 @@ impl Renderer {
                     }
                 }
 
+                // Particles after the model, they are depth tested but do not write depth
+                if !self.particle_batches.is_empty() {
+                    render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, self.particle_vertex_buffer.slice(..));
+                    for batch in &self.particle_batches {
+                        let texture_bind_group = self
+                            .texture_bind_groups
+                            .get(batch.texture_id)
+                            .unwrap_or(&self.texture_bind_groups[0]);
+                        render_pass.set_pipeline(self.particle_pipeline(batch.filter_mode));
+                        render_pass.set_bind_group(1, texture_bind_group, &[]);
+                        render_pass.draw(batch.vertices.clone(), 0..1);
+                    }
+                }
+
+                // Ribbons the same way, with their emitter material
+                if !self.ribbon_batches.is_empty() {
+                    render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, self.ribbon_vertex_buffer.slice(..));
+                    for batch in &self.ribbon_batches {
+                        let texture_bind_group = self
+                            .texture_bind_groups
+                            .get(batch.texture_id)
+                            .unwrap_or(&self.texture_bind_groups[0]);
+                        render_pass.set_pipeline(self.particle_pipeline(batch.filter_mode));
+                        render_pass.set_bind_group(1, texture_bind_group, &[]);
+                        render_pass.draw(batch.vertices.clone(), 0..1);
+                    }
+                }
+
+                // PREM copies last, they are drawn like particles too
+                if !self.spawn_batches.is_empty() {
+                    render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, self.spawn_vertex_buffer.slice(..));
+                    for batch in &self.spawn_batches {
+                        let texture_bind_group = self
+                            .texture_bind_groups
+                            .get(batch.texture_id)
+                            .unwrap_or(&self.texture_bind_groups[0]);
+                        render_pass.set_pipeline(self.particle_pipeline(batch.filter_mode));
+                        render_pass.set_bind_group(1, texture_bind_group, &[]);
+                        render_pass.draw(batch.vertices.clone(), 0..1);
+                    }
+                }
+
                 // Draw skeleton on top (only if model is present)
                 if model_opt.is_some() && show_skeleton && self.num_skeleton_lines > 0 {
                     render_pass.set_pipeline(&self.line_pipeline);
@@ -362,6 +503,39 @@ impl Renderer {
                     render_pass.set_vertex_buffer(0, self.bounding_box_vertex_buffer.slice(..));
                     render_pass.draw(0..(self.num_bounding_box_lines * 2), 0..1);
                 }
+
+                // Draw the extent of the selected sequence
+                if model_opt.is_some() && show_sequence_extent && self.num_sequence_extent_lines > 0
+                {
+                    render_pass.set_pipeline(&self.line_pipeline);
+                    render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, self.sequence_extent_vertex_buffer.slice(..));
+                    render_pass.draw(0..(self.num_sequence_extent_lines * 2), 0..1);
+                }
+
+                // Draw attachment point markers (only generated while they are shown)
+                if model_opt.is_some() && self.num_attachment_lines > 0 {
+                    render_pass.set_pipeline(&self.line_pipeline);
+                    render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, self.attachment_vertex_buffer.slice(..));
+                    render_pass.draw(0..(self.num_attachment_lines * 2), 0..1);
+                }
+
+                // Draw light gizmos (only generated while they are shown)
+                if model_opt.is_some() && self.num_light_lines > 0 {
+                    render_pass.set_pipeline(&self.line_pipeline);
+                    render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, self.light_vertex_buffer.slice(..));
+                    render_pass.draw(0..(self.num_light_lines * 2), 0..1);
+                }
+
+                // Draw collision shapes (only generated while they are shown)
+                if model_opt.is_some() && self.num_collision_lines > 0 {
+                    render_pass.set_pipeline(&self.line_pipeline);
+                    render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, self.collision_vertex_buffer.slice(..));
+                    render_pass.draw(0..(self.num_collision_lines * 2), 0..1);
+                }
             }
         }
 
@@ -414,4 +588,53 @@ impl Renderer {
 
         Ok(())
     }
+
+    /// Texture of a layer, following its flipbook (KMTF) while animating
+    pub(crate) fn layer_texture_id(
+        &self,
+        material_id: usize,
+        layer_index: usize,
+        layer: &Layer,
+    ) -> Option<usize> {
+        self.layer_state(material_id, layer_index)
+            .map_or(layer.texture_id, |state| state.texture_id)
+    }
+
+    /// Order in which geosets are drawn within each pass: materials with a lower
+    /// priority plane go first. Inside a plane, geosets whose material has
+    /// SortPrimsFarZ come after the others and are sorted far to near
+    fn geoset_draw_order(&self, model: &Model, eye: &nalgebra_glm::Vec3) -> Vec<usize> {
+        let sort_key = |geoset: &GeosetRenderInfo| -> (i32, bool, f32) {
+            let Some(material) = geoset.material_id.and_then(|id| model.materials.get(id)) else {
+                return (0, false, 0.0);
+            };
+            if !material.has_flag(MaterialFlags::SortPrimsFarZ) || geoset.vertices.is_empty() {
+                return (material.priority_plane, false, 0.0);
+            }
+
+            let sum = geoset
+                .vertices
+                .iter()
+                .fold(nalgebra_glm::Vec3::zeros(), |sum, v| {
+                    sum + nalgebra_glm::vec3(v[0], v[1], v[2])
+                });
+            let center = sum / geoset.vertices.len() as f32;
+            // Negated so that the farthest geoset sorts first
+            (
+                material.priority_plane,
+                true,
+                -nalgebra_glm::distance(&center, eye),
+            )
+        };
+
+        let keys: Vec<(i32, bool, f32)> = self.geosets.iter().map(sort_key).collect();
+        let mut order: Vec<usize> = (0..self.geosets.len()).collect();
+        // Stable, so geosets with equal keys keep their file order
+        order.sort_by(|&a, &b| {
+            keys[a]
+                .partial_cmp(&keys[b])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        order
+    }
 }
@@ -1,11 +1,14 @@
+use crate::animation::types::{GeosetAnim, LayerState};
 use crate::error::MdlError;
 use crate::material::{FilterMode, Material, MaterialUniform};
 use crate::model::model::Model;
 use crate::model::texture::Texture;
 use crate::renderer::camera::CameraState;
 use crate::renderer::geoset_render_info::GeosetRenderInfo;
+use crate::renderer::lights::LightUniform;
 use crate::renderer::line_vertex::LineVertex;
-use crate::renderer::vertex::Vertex;
+use crate::renderer::particles::{ParticleBatch, create_particle_pipelines};
+use crate::renderer::vertex::{UV_SETS, Vertex};
 use crate::settings::Settings;
 use wgpu::util::DeviceExt;
 use winit::window::Window;
@@ -22,6 +25,7 @@ pub struct Renderer {
     pub additive_pipeline: wgpu::RenderPipeline,
     pub wireframe_additive_pipeline: wgpu::RenderPipeline,
     pub line_pipeline: wgpu::RenderPipeline,
+    pub particle_pipelines: Vec<wgpu::RenderPipeline>, // Per particle filter mode
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub num_indices: u32,
@@ -34,14 +38,33 @@ pub struct Renderer {
     pub num_skeleton_lines: u32,
     pub bounding_box_vertex_buffer: wgpu::Buffer,
     pub num_bounding_box_lines: u32,
+    pub sequence_extent_vertex_buffer: wgpu::Buffer,
+    pub num_sequence_extent_lines: u32,
+    pub sequence_extent_index: Option<usize>, // Sequence the extent lines were built for
+    pub attachment_vertex_buffer: wgpu::Buffer,
+    pub num_attachment_lines: u32,
+    pub attachment_labels: Vec<(String, [f32; 3])>, // Name and position of each marker
+    pub light_vertex_buffer: wgpu::Buffer,
+    pub num_light_lines: u32,
+    pub collision_vertex_buffer: wgpu::Buffer,
+    pub num_collision_lines: u32,
+    pub particle_vertex_buffer: wgpu::Buffer,
+    pub particle_batches: Vec<ParticleBatch>, // One draw per emitter, empty when hidden
+    pub ribbon_vertex_buffer: wgpu::Buffer,
+    pub ribbon_batches: Vec<ParticleBatch>, // One draw per emitter layer, empty when hidden
+    pub spawn_vertex_buffer: wgpu::Buffer,
+    pub spawn_batches: Vec<ParticleBatch>, // PREM copies, merged by texture and filter mode
     pub camera_buffer: wgpu::Buffer,
     pub camera_bind_group: wgpu::BindGroup,
+    pub light_buffer: wgpu::Buffer, // Model lights (LITE), next to the camera in group 0
     pub texture_bind_groups: Vec<wgpu::BindGroup>, // One bind group per texture
     texture_views: Vec<Option<wgpu::TextureView>>, // Store texture views for egui
     texture_bind_group_layout: wgpu::BindGroupLayout,
-    // Material uniform - single bind group for all materials
+    // Material uniforms - one slot per geoset layer in a single buffer
     pub material_buffer: wgpu::Buffer,
     pub material_bind_group: wgpu::BindGroup,
+    material_bind_group_layout: wgpu::BindGroupLayout,
+    pub material_slot_size: u64, // Slot stride, aligned for dynamic offsets
     // Store white texture components to create bind groups for missing textures
     white_texture_view: wgpu::TextureView,
     white_texture_sampler: wgpu::Sampler,
@@ -57,6 +80,10 @@ pub struct Renderer {
     original_vertices: Vec<Vertex>,
     // Store model for accessing vertex groups during animation
     model: Option<Model>,
+    // Animated layer values per material, empty when animation is off
+    pub layer_states: Vec<Vec<LayerState>>,
+    // Animated geoset alpha and color, empty when animation is off
+    pub geoset_anims: Vec<GeosetAnim>,
 }
 
 impl Renderer {
@@ -143,31 +170,60 @@ impl Renderer {
             mapped_at_creation: false,
         });
 
+        // Create light uniform buffer, no lights until a model sets them
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Light Buffer"),
+            contents: bytemuck::bytes_of(&<LightUniform as bytemuck::Zeroable>::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Camera Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
             });
 
         let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Camera Bind Group"),
             layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                },
+            ],
         });
 
         // Create material uniform buffer
+        // Every drawn geoset layer gets its own slot, picked with a dynamic offset
+        let material_slot_size = (std::mem::size_of::<MaterialUniform>() as u64)
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+
         let material_uniform =
             MaterialUniform::new([1.0, 0.0, 0.0], 0, false, FilterMode::None, 1.0, 0);
 
@@ -185,21 +241,17 @@ impl Renderer {
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<MaterialUniform>() as u64,
+                        ),
                     },
                     count: None,
                 }],
             });
 
-        let material_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Material Bind Group"),
-            layout: &material_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: material_buffer.as_entire_binding(),
-            }],
-        });
+        let material_bind_group =
+            create_material_bind_group(&device, &material_bind_group_layout, &material_buffer);
 
         // Create default white 1x1 texture (for non-team-color materials)
         let texture_size = wgpu::Extent3d {
@@ -347,6 +399,14 @@ impl Renderer {
             push_constant_ranges: &[],
         });
 
+        // Particles are textured but take their color from the vertices
+        let particle_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Particle Pipeline Layout"),
+                bind_group_layouts: &[&camera_bind_group_layout, &texture_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
         let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&render_pipeline_layout),
@@ -685,6 +745,9 @@ impl Renderer {
             cache: None,
         });
 
+        let particle_pipelines =
+            create_particle_pipelines(&device, &shader, &particle_pipeline_layout, config.format);
+
         // Create axes and grid
         let mut line_vertices = Vec::new();
 
@@ -808,6 +871,62 @@ impl Renderer {
             mapped_at_creation: false,
         });
 
+        // Create empty sequence extent buffer initially
+        let sequence_extent_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sequence Extent Vertex Buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        // Create empty attachment marker buffer initially
+        let attachment_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Attachment Vertex Buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Create empty light gizmo buffer initially
+        let light_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Light Vertex Buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Create empty collision shape buffer initially
+        let collision_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Collision Vertex Buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Create empty particle buffer initially
+        let particle_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Vertex Buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Create empty ribbon buffer initially
+        let ribbon_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ribbon Vertex Buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Create empty buffer for the PREM copies initially
+        let spawn_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Spawn Vertex Buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         // Initialize egui
         let egui_renderer = egui_wgpu::Renderer::new(&device, config.format, Default::default());
 
@@ -823,6 +942,7 @@ impl Renderer {
             additive_pipeline,
             wireframe_additive_pipeline,
             line_pipeline,
+            particle_pipelines,
             vertex_buffer,
             index_buffer,
             num_indices: 0,
@@ -835,13 +955,32 @@ impl Renderer {
             num_skeleton_lines: 0,
             bounding_box_vertex_buffer,
             num_bounding_box_lines: 0,
+            sequence_extent_vertex_buffer,
+            num_sequence_extent_lines: 0,
+            sequence_extent_index: None,
+            attachment_vertex_buffer,
+            num_attachment_lines: 0,
+            attachment_labels: Vec::new(),
+            light_vertex_buffer,
+            num_light_lines: 0,
+            collision_vertex_buffer,
+            num_collision_lines: 0,
+            particle_vertex_buffer,
+            particle_batches: Vec::new(),
+            ribbon_vertex_buffer,
+            ribbon_batches: Vec::new(),
+            spawn_vertex_buffer,
+            spawn_batches: Vec::new(),
             camera_buffer,
             camera_bind_group,
+            light_buffer,
             texture_bind_groups,
             texture_views: Vec::new(),
             texture_bind_group_layout,
             material_buffer,
             material_bind_group,
+            material_bind_group_layout,
+            material_slot_size,
             white_texture_view: diffuse_texture_view,
             white_texture_sampler: diffuse_sampler,
             team_color: [1.0, 0.0, 0.0],       // Red by default
@@ -859,6 +998,8 @@ impl Renderer {
             view_proj_matrix: nalgebra_glm::Mat4::identity(),
             original_vertices: Vec::new(),
             model: None,
+            layer_states: Vec::new(),
+            geoset_anims: Vec::new(),
         })
     }
 
@@ -878,6 +1019,21 @@ impl Renderer {
         // Store model for animation
         self.model = Some(model.clone());
 
+        let beyond_uv_sets = model
+            .materials
+            .iter()
+            .flat_map(|material| &material.layers)
+            .filter(|layer| layer.coord_id >= UV_SETS)
+            .count();
+        if beyond_uv_sets > 0 {
+            eprintln!(
+                "[mdlvis-rs] {} layers use a CoordId beyond the {} UV sets drawn, they sample UV set {}",
+                beyond_uv_sets,
+                UV_SETS,
+                UV_SETS - 1
+            );
+        }
+
         let mut all_vertices: Vec<Vertex> = Vec::new();
         let mut all_indices: Vec<u16> = Vec::new();
         let mut geosets_info: Vec<GeosetRenderInfo> = Vec::new();
@@ -893,6 +1049,8 @@ impl Renderer {
                 } else {
                     [0.0, 0.0] // Default UV if not available
                 };
+                // Without a second set, CoordId 1 layers fall back to the first one
+                let uv1 = geoset.tex_coord_set(1).get(i).map_or(uv, |tex_coord| tex_coord.uv);
 
                 all_vertices.push(Vertex {
                     position: geoset.vertices[i].position,
@@ -902,6 +1060,7 @@ impl Renderer {
                         [0.0, 0.0, 1.0] // Default normal
                     },
                     uv,
+                    uv1,
                 });
             }
 
@@ -929,10 +1088,11 @@ impl Renderer {
             });
 
             println!(
-                "  Geoset {}: added {} vertices, {} UVs, {} faces, material_id: {:?}",
+                "  Geoset {}: added {} vertices, {} UVs ({} sets), {} faces, material_id: {:?}",
                 geoset_idx,
                 geoset.vertices.len(),
                 geoset.tex_coords.len(),
+                1 + geoset.secondary_tex_coords.len(),
                 geoset.faces.len(),
                 geoset.material_id
             );
@@ -1012,22 +1172,7 @@ impl Renderer {
                 if object_id < 0 {
                     return None;
                 }
-
-                // Search in bones first
-                if let Some(bone) = model.bones.iter().find(|b| b.object_id == object_id as u32) {
-                    return Some(bone.pivot_point);
-                }
-
-                // Then search in helpers
-                if let Some(helper) = model
-                    .helpers
-                    .iter()
-                    .find(|h| h.object_id == object_id as u32)
-                {
-                    return Some(helper.pivot_point);
-                }
-
-                None
+                Some(model.pivot_point(object_id as u32))
             };
 
             // Process bones
@@ -1038,7 +1183,7 @@ impl Renderer {
                         color: bone_color,
                     });
                     skeleton_vertices.push(LineVertex {
-                        position: bone.pivot_point,
+                        position: model.pivot_point(bone.object_id),
                         color: bone_color,
                     });
                 }
@@ -1052,7 +1197,7 @@ impl Renderer {
                         color: helper_color,
                     });
                     skeleton_vertices.push(LineVertex {
-                        position: helper.pivot_point,
+                        position: model.pivot_point(helper.object_id),
                         color: helper_color,
                     });
                 }
@@ -1087,10 +1232,17 @@ impl Renderer {
 
         // Generate bounding box lines from geosets
         self.generate_bounding_box_lines(model);
+
+        // Sequence extent lines are built on demand for the selected sequence
+        self.sequence_extent_index = None;
+        self.num_sequence_extent_lines = 0;
     }
 
     /// Reset vertex buffer to original parsed vertices (no animation)
     pub fn reset_to_original_vertices(&mut self) {
+        self.layer_states.clear();
+        self.geoset_anims.clear();
+
         if self.original_vertices.is_empty() {
             return;
         }
@@ -1106,6 +1258,9 @@ impl Renderer {
     /// Update vertex buffer with animated vertices
     /// Based on CalcAnimCoords from mdlDraw.pas (line 2310)
     pub fn update_animation(&mut self, animation_system: &crate::animation::AnimationSystem) {
+        self.layer_states.clone_from(&animation_system.layers);
+        self.geoset_anims.clone_from(&animation_system.geoset_anims);
+
         if self.original_vertices.is_empty() || animation_system.bones.is_empty() {
             return;
         }
@@ -1175,12 +1330,12 @@ impl Renderer {
                         }
                     };
 
-                    // Get pivot point for this bone
-                    let pivot = if bone_idx < animation_system.pivot_points.len() {
-                        animation_system.pivot_points[bone_idx]
-                    } else {
-                        nalgebra_glm::vec3(0.0, 0.0, 0.0)
-                    };
+                    // Get pivot point for this bone (the table is indexed by ObjectID)
+                    let pivot = animation_system
+                        .pivot_points
+                        .get(bone.object_id as usize)
+                        .copied()
+                        .unwrap_or_else(|| nalgebra_glm::vec3(0.0, 0.0, 0.0));
 
                     // Transform vertex: (pos - pivot) * matrix + abs_vector
                     // Based on Delphi code lines 2379-2400
@@ -1244,6 +1399,13 @@ impl Renderer {
                     settings.colors.bounding_box_color,
                 );
             }
+            if let Some(sequence_idx) = self.sequence_extent_index {
+                self.generate_sequence_extent_lines(
+                    model,
+                    sequence_idx,
+                    settings.colors.sequence_extent_color,
+                );
+            }
         }
     }
 
@@ -1364,10 +1526,20 @@ impl Renderer {
         );
 
         let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Like the game: textures clamp unless the TEXS wrap flags ask for tiling
+        let address_mode = |wrap: bool| {
+            if wrap {
+                wgpu::AddressMode::Repeat
+            } else {
+                wgpu::AddressMode::ClampToEdge
+            }
+        };
+        let model_texture = self.textures.get(texture_id);
         let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Texture Sampler"),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_u: address_mode(model_texture.is_some_and(|t| t.wrap_width())),
+            address_mode_v: address_mode(model_texture.is_some_and(|t| t.wrap_height())),
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Linear,
@@ -1439,4 +1611,55 @@ impl Renderer {
         }
         None
     }
+
+    /// Upload material uniforms laid out `material_slot_size` apart,
+    /// growing the buffer when the model needs more slots
+    pub fn write_material_slots(&mut self, data: &[u8]) {
+        if data.len() as u64 > self.material_buffer.size() {
+            self.material_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Material Buffer"),
+                size: data.len() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            self.material_bind_group = create_material_bind_group(
+                &self.device,
+                &self.material_bind_group_layout,
+                &self.material_buffer,
+            );
+        }
+        self.queue.write_buffer(&self.material_buffer, 0, data);
+    }
+
+    /// Animated alpha and color of a geoset, None when it has no GEOA or animation is off
+    pub fn geoset_anim(&self, geoset_idx: usize) -> Option<&GeosetAnim> {
+        self.geoset_anims
+            .iter()
+            .find(|anim| anim.geoset_id == geoset_idx as i32)
+    }
+
+    /// Animated state of a material layer, None when animation is off
+    pub fn layer_state(&self, material_id: usize, layer_index: usize) -> Option<&LayerState> {
+        self.layer_states.get(material_id)?.get(layer_index)
+    }
+}
+
+/// Bind group exposing one MaterialUniform of the buffer at a dynamic offset
+fn create_material_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Material Bind Group"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer,
+                offset: 0,
+                size: wgpu::BufferSize::new(std::mem::size_of::<MaterialUniform>() as u64),
+            }),
+        }],
+    })
 }
@@ -0,0 +1,121 @@
+use crate::animation::ribbons::{Ribbon, RibbonEdge};
+use crate::model::model::Model;
+use crate::renderer::particle_vertex::ParticleVertex;
+use crate::renderer::particles::{ParticleBatch, atlas_cell_uv, particle_filter_mode};
+use crate::renderer::renderer::Renderer;
+use nalgebra_glm as glm;
+use wgpu::util::DeviceExt;
+
+impl Renderer {
+    /// Strips for the ribbons, drawn once per enabled layer of their emitter material
+    /// with the particle pipelines
+    pub(crate) fn generate_ribbons(&mut self, model: &Model, ribbons: &[Ribbon]) {
+        self.ribbon_batches.clear();
+
+        let mut vertices = Vec::new();
+        for (idx, emitter) in model.ribbon_emitters.iter().enumerate() {
+            let Some(material) = model.materials.get(emitter.material_id as usize) else {
+                continue;
+            };
+
+            for (layer_index, layer) in material.layers.iter().enumerate() {
+                if !layer.is_enabled() {
+                    continue;
+                }
+                let state = self.layer_state(emitter.material_id as usize, layer_index);
+                // UI override first, then the animated value
+                let alpha = layer
+                    .alpha_override
+                    .unwrap_or_else(|| state.map_or(layer.alpha, |state| state.alpha));
+                let uv_transform =
+                    state.map_or_else(glm::Mat3::identity, |state| state.uv_transform);
+
+                let first = vertices.len() as u32;
+                for ribbon in ribbons.iter().filter(|ribbon| ribbon.emitter == idx) {
+                    for pair in ribbon.edges.windows(2) {
+                        push_segment(
+                            &mut vertices,
+                            [&pair[0], &pair[1]],
+                            |edge, top| {
+                                // Along the ribbon as it ages, across it from above to below
+                                let [left, v0, right, v1] =
+                                    atlas_cell_uv(emitter.rows, emitter.columns, edge.texture_slot);
+                                let u = left + (right - left) * edge.life;
+                                let v = if top { v0 } else { v1 };
+                                let uv = uv_transform * glm::vec3(u, v, 1.0);
+                                [uv.x, uv.y]
+                            },
+                            alpha,
+                        );
+                    }
+                }
+
+                let last = vertices.len() as u32;
+                if last > first {
+                    self.ribbon_batches.push(ParticleBatch {
+                        filter_mode: particle_filter_mode(&layer.get_filter_mode()),
+                        texture_id: self
+                            .layer_texture_id(emitter.material_id as usize, layer_index, layer)
+                            .unwrap_or(usize::MAX),
+                        vertices: first..last,
+                    });
+                }
+            }
+        }
+
+        if vertices.is_empty() {
+            return;
+        }
+
+        // Ribbons change every frame, so the buffer is only recreated when it grows
+        let contents: &[u8] = bytemuck::cast_slice(&vertices);
+        if self.ribbon_vertex_buffer.size() < contents.len() as u64 {
+            self.ribbon_vertex_buffer =
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Ribbon Vertex Buffer"),
+                        contents,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    });
+        } else {
+            self.queue
+                .write_buffer(&self.ribbon_vertex_buffer, 0, contents);
+        }
+    }
+
+    /// Stop drawing ribbons
+    pub(crate) fn clear_ribbons(&mut self) {
+        self.ribbon_batches.clear();
+    }
+}
+
+/// Two triangles between two consecutive edges. Ribbons are not mirrored by the shader,
+/// the mesh is
+fn push_segment(
+    vertices: &mut Vec<ParticleVertex>,
+    edges: [&RibbonEdge; 2],
+    uv: impl Fn(&RibbonEdge, bool) -> [f32; 2],
+    alpha: f32,
+) {
+    let mirror = |v: &glm::Vec3| [v.x, -v.y, v.z];
+    let corner = |edge: &RibbonEdge, top: bool| ParticleVertex {
+        position: mirror(if top { &edge.above } else { &edge.below }),
+        uv: uv(edge, top),
+        color: [
+            edge.color.x,
+            edge.color.y,
+            edge.color.z,
+            edge.color.w * alpha,
+        ],
+    };
+    let [older, newer] = edges;
+    let corners = [
+        corner(older, false),
+        corner(newer, false),
+        corner(newer, true),
+        corner(older, true),
+    ];
+    for idx in [0, 1, 2, 0, 2, 3] {
+        vertices.push(corners[idx]);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::animation::particles::SpawnedParticle;
+use crate::animation::system::AnimationSystem;
+use crate::model::emitter::PARTICLE2_FILTER_BLEND;
+use crate::model::geoset::Geoset;
+use crate::model::model::Model;
+use crate::renderer::particle_vertex::ParticleVertex;
+use crate::renderer::particles::{ParticleBatch, particle_filter_mode};
+use crate::renderer::renderer::Renderer;
+use nalgebra_glm as glm;
+use wgpu::util::DeviceExt;
+
+/// PREM emitters give no size to their particles, texture ones are drawn this big
+pub const TEXTURE_PARTICLE_SIZE: f32 = 16.0;
+
+/// What a PREM emitter of the shown model throws, loaded along with the model
+pub enum SpawnSource {
+    Model(Box<SpawnedModel>),
+    Texture(usize), // Texture id, after the model ones
+}
+
+/// Model thrown by an emitter, with its own animation and textures
+pub struct SpawnedModel {
+    pub model: Model,
+    pub animation: AnimationSystem,
+    pub texture_offset: usize, // Texture id of its first texture
+}
+
+impl Renderer {
+    /// Copies of the emitter models at their particles, each one played from the start
+    /// of its first sequence as it ages, and camera-facing quads for texture emitters.
+    /// `time` and `camera_orientation` are the ones given to `AnimationSystem::update`
+    pub(crate) fn generate_spawned_particles(
+        &mut self,
+        sources: &mut [Option<SpawnSource>],
+        particles: &[SpawnedParticle],
+        time: f64,
+        camera_orientation: (f32, f32),
+    ) {
+        self.spawn_batches.clear();
+
+        // Camera frame, as set up by render()
+        let (yaw, pitch) = (self.camera.yaw, self.camera.pitch);
+        let forward = -glm::vec3(
+            yaw.cos() * pitch.cos(),
+            yaw.sin() * pitch.cos(),
+            pitch.sin(),
+        );
+        let camera_right = glm::cross(&forward, &glm::vec3(0.0, 0.0, 1.0));
+        let camera_right = if glm::length(&camera_right) > 1e-6 {
+            glm::normalize(&camera_right)
+        } else {
+            glm::vec3(yaw.sin(), -yaw.cos(), 0.0)
+        };
+        let camera_up = glm::cross(&camera_right, &forward);
+
+        let mut vertices = Vec::new();
+        let mut batches: Vec<ParticleBatch> = Vec::new();
+        for particle in particles {
+            let Some(source) = sources.get_mut(particle.emitter).and_then(Option::as_mut) else {
+                continue;
+            };
+
+            match source {
+                SpawnSource::Texture(texture_id) => {
+                    // Quads are not mirrored by the shader, the mesh is
+                    let p = &particle.position;
+                    let center = glm::vec3(p.x, -p.y, p.z);
+                    let right = camera_right * TEXTURE_PARTICLE_SIZE;
+                    let up = camera_up * TEXTURE_PARTICLE_SIZE;
+                    let corners = [
+                        (center - right - up, [0.0, 1.0]),
+                        (center + right - up, [1.0, 1.0]),
+                        (center + right + up, [1.0, 0.0]),
+                        (center - right + up, [0.0, 0.0]),
+                    ];
+                    let first = vertices.len() as u32;
+                    for corner in [0, 1, 2, 0, 2, 3] {
+                        let (position, uv) = corners[corner];
+                        vertices.push(ParticleVertex {
+                            position: position.into(),
+                            uv,
+                            color: [1.0; 4],
+                        });
+                    }
+                    push_batch(
+                        &mut batches,
+                        ParticleBatch {
+                            filter_mode: PARTICLE2_FILTER_BLEND,
+                            texture_id: *texture_id,
+                            vertices: first..vertices.len() as u32,
+                        },
+                    );
+                }
+                SpawnSource::Model(spawned) => push_model_copy(
+                    &mut vertices,
+                    &mut batches,
+                    spawned,
+                    particle,
+                    time,
+                    camera_orientation,
+                ),
+            }
+        }
+        self.spawn_batches = batches;
+
+        if vertices.is_empty() {
+            return;
+        }
+
+        // Copies change every frame, so the buffer is only recreated when it grows
+        let contents: &[u8] = bytemuck::cast_slice(&vertices);
+        if self.spawn_vertex_buffer.size() < contents.len() as u64 {
+            self.spawn_vertex_buffer =
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Spawn Vertex Buffer"),
+                        contents,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    });
+        } else {
+            self.queue
+                .write_buffer(&self.spawn_vertex_buffer, 0, contents);
+        }
+    }
+
+    /// Stop drawing PREM particles
+    pub(crate) fn clear_spawned_particles(&mut self) {
+        self.spawn_batches.clear();
+    }
+}
+
+/// Triangles of a spawned model at the age of a particle, one batch per geoset layer
+fn push_model_copy(
+    vertices: &mut Vec<ParticleVertex>,
+    batches: &mut Vec<ParticleBatch>,
+    spawned: &mut SpawnedModel,
+    particle: &SpawnedParticle,
+    time: f64,
+    camera_orientation: (f32, f32),
+) {
+    let SpawnedModel {
+        model,
+        animation,
+        texture_offset,
+    } = spawned;
+
+    // One-shot: the copy stays on the last frame of the sequence
+    let frame = model.sequences.first().map_or(0.0, |sequence| {
+        let age = (particle.age * 1000.0) as u32;
+        (sequence.start_frame + age).min(sequence.end_frame) as f32
+    });
+    animation.update(frame, time, camera_orientation);
+
+    for (geoset_idx, geoset) in model.geosets.iter().enumerate() {
+        let geoset_color = animation
+            .geoset_anims
+            .iter()
+            .find(|anim| anim.geoset_id == geoset_idx as i32)
+            .map_or(glm::vec4(1.0, 1.0, 1.0, 1.0), |anim| {
+                glm::vec4(anim.color.x, anim.color.y, anim.color.z, anim.alpha)
+            });
+        if geoset_color.w <= 0.0 {
+            continue;
+        }
+        let Some(material_id) = geoset.material_id else {
+            continue;
+        };
+        let Some(material) = model.materials.get(material_id) else {
+            continue;
+        };
+
+        // Skinned the way the model itself is, then carried to the particle.
+        // Copies are not mirrored by the shader, the mesh is
+        let positions: Vec<[f32; 3]> = (0..geoset.vertices.len())
+            .map(|vertex| {
+                let local = skin_vertex(animation, geoset, vertex);
+                let position = particle.orientation * local + particle.position;
+                [position.x, -position.y, position.z]
+            })
+            .collect();
+
+        for (layer_index, layer) in material.layers.iter().enumerate() {
+            if !layer.is_enabled() {
+                continue;
+            }
+            let state = animation
+                .layers
+                .get(material_id)
+                .and_then(|layers| layers.get(layer_index));
+            // UI override first, then the animated value
+            let alpha = layer
+                .alpha_override
+                .unwrap_or_else(|| state.map_or(layer.alpha, |state| state.alpha));
+            let uv_transform = state.map_or_else(glm::Mat3::identity, |state| state.uv_transform);
+            let texture_id = state.map_or(layer.texture_id, |state| state.texture_id);
+            let color: [f32; 4] = glm::vec4(1.0, 1.0, 1.0, alpha)
+                .component_mul(&geoset_color)
+                .into();
+
+            let first = vertices.len() as u32;
+            for vertex in geoset.faces.iter().flat_map(|face| face.vertices) {
+                let vertex = vertex as usize;
+                let Some(&position) = positions.get(vertex) else {
+                    continue;
+                };
+                let uv = geoset.tex_coords.get(vertex).map_or([0.0; 2], |tex| {
+                    let uv = uv_transform * glm::vec3(tex.uv[0], tex.uv[1], 1.0);
+                    [uv.x, uv.y]
+                });
+                vertices.push(ParticleVertex {
+                    position,
+                    uv,
+                    color,
+                });
+            }
+            push_batch(
+                batches,
+                ParticleBatch {
+                    filter_mode: particle_filter_mode(&layer.get_filter_mode()),
+                    texture_id: texture_id.map_or(usize::MAX, |id| *texture_offset + id),
+                    vertices: first..vertices.len() as u32,
+                },
+            );
+        }
+    }
+}
+
+/// Adds a batch, or grows the previous one when it draws the same way
+fn push_batch(batches: &mut Vec<ParticleBatch>, batch: ParticleBatch) {
+    if batch.vertices.is_empty() {
+        return;
+    }
+    if let Some(last) = batches.last_mut()
+        && last.filter_mode == batch.filter_mode
+        && last.texture_id == batch.texture_id
+        && last.vertices.end == batch.vertices.start
+    {
+        last.vertices.end = batch.vertices.end;
+        return;
+    }
+    batches.push(batch);
+}
+
+/// Position of a vertex moved by its bones, averaged like in `update_animation`.
+/// Unskinned vertices keep their position
+fn skin_vertex(animation: &AnimationSystem, geoset: &Geoset, vertex: usize) -> glm::Vec3 {
+    let position = glm::make_vec3(&geoset.vertices[vertex].position);
+    let Some(bone_indices) = geoset
+        .vertex_groups
+        .get(vertex)
+        .and_then(|&group| geoset.matrix_groups.get(group as usize))
+    else {
+        return position;
+    };
+
+    let mut sum = glm::Vec3::zeros();
+    let mut count = 0;
+    for &bone_idx in bone_indices {
+        let bone_idx = bone_idx as usize;
+        let Some(bone) = animation.bones.get(bone_idx).or_else(|| {
+            animation
+                .helpers
+                .get(bone_idx.checked_sub(animation.bones.len())?)
+        }) else {
+            continue;
+        };
+        let pivot = animation
+            .pivot_points
+            .get(bone.object_id as usize)
+            .copied()
+            .unwrap_or_else(glm::Vec3::zeros);
+        sum += bone.abs_matrix * (position - pivot) + bone.abs_vector;
+        count += 1;
+    }
+    if count == 0 {
+        position
+    } else {
+        sum / count as f32
+    }
+}
@@ -1,9 +1,13 @@
+/// UV sets uploaded per vertex, layers with a higher CoordId sample the last one
+pub(crate) const UV_SETS: u32 = 2;
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub(crate) position: [f32; 3],
     pub(crate) normal: [f32; 3],
     pub(crate) uv: [f32; 2],
+    pub(crate) uv1: [f32; 2], // Second UV set, for layers with CoordId 1
 }
 
 impl Vertex {
@@ -27,6 +31,12 @@ impl Vertex {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                wgpu::VertexAttribute {
+                    offset: (size_of::<[f32; 3]>() * 2 + size_of::<[f32; 2]>())
+                        as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
             ],
         }
     }
@@ -3,11 +3,18 @@ use crate::CONFY_APP_NAME;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)] // Settings saved by older versions lack the newer fields
 pub struct DisplaySettings {
     pub show_skeleton: bool,
     pub wireframe_mode: bool,
     pub show_grid: bool,
     pub show_bounding_box: bool,
+    pub show_sequence_extent: bool,
+    pub show_attachments: bool,
+    pub show_particles: bool,
+    pub show_ribbons: bool,
+    pub show_lights: bool,
+    pub show_collision_shapes: bool,
     pub far_plane: f32,
 }
 
@@ -18,6 +25,12 @@ impl Default for DisplaySettings {
             wireframe_mode: false,
             show_grid: true,
             show_bounding_box: false,
+            show_sequence_extent: false,
+            show_attachments: true,
+            show_particles: true,
+            show_ribbons: true,
+            show_lights: false,
+            show_collision_shapes: false,
             far_plane: 1000.0,
         }
     }
@@ -34,12 +47,14 @@ impl DisplaySettings {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ColorSettings {
     pub team_color: [f32; 3],
     pub skybox_color: [f32; 3],
     pub grid_major_color: [f32; 3],
     pub grid_minor_color: [f32; 3],
     pub bounding_box_color: [f32; 3],
+    pub sequence_extent_color: [f32; 3],
 }
 
 impl Default for ColorSettings {
@@ -50,6 +65,7 @@ impl Default for ColorSettings {
             grid_major_color: [0.06, 0.06, 0.06],
             grid_minor_color: [0.04, 0.04, 0.04],
             bounding_box_color: [1.0, 1.0, 0.0],
+            sequence_extent_color: [1.0, 0.0, 1.0],
         }
     }
 }
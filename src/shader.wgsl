@@ -6,11 +6,28 @@ struct CameraUniform {
 struct MaterialUniform {
     team_color: vec4<f32>, // team_color.rgb + replaceable_id (0=none, 1=team_color, 2=team_glow)
     material_type_and_wireframe: vec4<f32>, // filter_mode + wireframe_mode + layer_alpha + shading_flags
-    extra_padding: vec4<f32>, // Padding for alignment
+    geoset_color: vec4<f32>, // Geoset animation color.rgb + alpha
+    uv_transform: array<vec4<f32>, 2>, // Rows of the 2D affine UV transform (texture animation)
+    uv_set: vec4<f32>, // x = layer CoordId (UV set to sample with)
+};
+
+// Model light (LITE), mirrored in Y like the mesh
+struct Light {
+    position: vec4<f32>, // xyz + type (0=omni, 1=directional, 2=ambient)
+    direction: vec4<f32>, // xyz: where directional lights shine
+    color: vec4<f32>, // rgb * intensity + attenuation start
+    ambient: vec4<f32>, // ambient rgb * ambient intensity + attenuation end
+};
+
+struct LightUniform {
+    count: vec4<u32>, // x = lights in use
+    lights: array<Light, 8>, // MAX_LIGHTS
 };
 
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
+@group(0) @binding(1)
+var<uniform> lights: LightUniform;
 
 @group(1) @binding(0)
 var t_diffuse: texture_2d<f32>;
@@ -24,6 +41,7 @@ struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) uv: vec2<f32>,
+    @location(3) uv1: vec2<f32>,
 };
 
 struct VertexOutput {
@@ -31,6 +49,7 @@ struct VertexOutput {
     @location(0) world_pos: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) uv: vec2<f32>,
+    @location(3) uv1: vec2<f32>,
 };
 
 @vertex
@@ -45,9 +64,37 @@ fn vs_main(
     out.clip_position = camera.view_proj * vec4<f32>(pos, 1.0);
     out.normal = model.normal;
     out.uv = model.uv;
+    out.uv1 = model.uv1;
     return out;
 }
 
+// Light the model lights add at a point, full up to their attenuation start
+// and none past its end. Directional lights are not attenuated
+fn model_lighting(world_pos: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    var total = vec3<f32>(0.0, 0.0, 0.0);
+    for (var i = 0u; i < lights.count.x; i = i + 1u) {
+        let light = lights.lights[i];
+        let light_type = u32(light.position.w);
+        let to_light = light.position.xyz - world_pos;
+
+        var attenuation = 1.0;
+        if (light_type != 1u) {
+            let start = light.color.w;
+            let end = light.ambient.w;
+            attenuation = clamp((end - length(to_light)) / max(end - start, 0.001), 0.0, 1.0);
+        }
+
+        var diffuse = 1.0; // Ambient lights reach every side
+        if (light_type == 0u) {
+            diffuse = max(dot(normal, to_light / max(length(to_light), 0.001)), 0.0);
+        } else if (light_type == 1u) {
+            diffuse = max(dot(normal, -light.direction.xyz), 0.0);
+        }
+        total = total + (light.color.rgb * diffuse + light.ambient.rgb) * attenuation;
+    }
+    return total;
+}
+
 // Fragment shader with texture sampling
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
@@ -71,8 +118,20 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         return vec4<f32>(wireframe_color, 1.0);
     }
     
+    // The layer CoordId picks the UV set, only the first two are uploaded
+    var base_uv = in.uv;
+    if (material.uv_set.x > 0.5) {
+        base_uv = in.uv1;
+    }
+
+    // Texture animation moves the UVs before sampling
+    let uv = vec2<f32>(
+        dot(material.uv_transform[0].xyz, vec3<f32>(base_uv, 1.0)),
+        dot(material.uv_transform[1].xyz, vec3<f32>(base_uv, 1.0)),
+    );
+
     // Sample texture (for RID=1/2 textures are already generated with team color)
-    var tex_color = textureSample(t_diffuse, s_diffuse, in.uv);
+    var tex_color = textureSample(t_diffuse, s_diffuse, uv);
     
     // Filter mode handling:
     // 0 = None - no transparency
@@ -89,9 +148,10 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     }
 
     
-    // Apply layer_alpha to texture
+    // Apply layer_alpha and the geoset animation to texture
     var layer_tex_color = tex_color;
-    layer_tex_color.a = tex_color.a * layer_alpha;
+    layer_tex_color.a = tex_color.a * layer_alpha * material.geoset_color.a;
+    layer_tex_color = vec4<f32>(layer_tex_color.rgb * material.geoset_color.rgb, layer_tex_color.a);
     
     // Apply lighting only to non-glow materials AND if not unshaded
     var final_color = layer_tex_color;
@@ -102,7 +162,9 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         let diffuse = max(dot(normal, light_dir), 0.0);
         let ambient = 0.3;
         let brightness = ambient + (1.0 - ambient) * diffuse;
-        final_color = vec4<f32>(layer_tex_color.rgb * brightness, layer_tex_color.a);
+        // Model lights add to the default one, up to twice the texture color
+        let lighting = min(vec3<f32>(brightness) + model_lighting(in.world_pos, normal), vec3<f32>(2.0));
+        final_color = vec4<f32>(layer_tex_color.rgb * lighting, layer_tex_color.a);
     }
     
     return final_color;
@@ -133,3 +195,33 @@ fn vs_line(
 fn fs_line(in: LineVertexOutput) -> @location(0) vec4<f32> {
     return vec4<f32>(in.color, 1.0);
 }
+
+// Particle rendering shaders
+struct ParticleVertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct ParticleVertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_particle(
+    model: ParticleVertexInput,
+) -> ParticleVertexOutput {
+    var out: ParticleVertexOutput;
+    // Particle quads are built facing the camera, already mirrored like the mesh
+    out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
+    out.uv = model.uv;
+    out.color = model.color;
+    return out;
+}
+
+@fragment
+fn fs_particle(in: ParticleVertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_diffuse, s_diffuse, in.uv) * in.color;
+}
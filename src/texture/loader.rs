@@ -1,4 +1,6 @@
 use crate::error::MdlError;
+use crate::model::model::Model;
+use crate::parser::load::load_from_bytes;
 use std::path::Path;
 
 const TEXTURE_BASE_URL: &str = "https://github.com/WarRaft/War3.mpq/raw/refs/heads/main/lowercase";
@@ -72,3 +74,10 @@ pub async fn load_texture(path: &str) -> Result<(Vec<u8>, u32, u32), MdlError> {
     let blp_data = download_texture(path).await?;
     decode_blp(&blp_data)
 }
+
+/// Download a model from the repository, which only holds MDX files
+pub async fn load_remote_model(path: &str) -> Result<Model, MdlError> {
+    let mdx_path = Path::new(path).with_extension("mdx");
+    let data = download_texture(&mdx_path.to_string_lossy()).await?;
+    load_from_bytes(&data)
+}
@@ -105,25 +105,52 @@ impl TextureManager {
         }
     }
 
+    /// Register a texture that is not part of the model, such as the ones of a model
+    /// thrown by its particle emitters, and return its id
+    pub fn add_texture(&mut self, filename: String, replaceable_id: u32) -> usize {
+        let id = self.textures.len();
+        self.textures.push(TextureInfo::new(id, filename, replaceable_id));
+        id
+    }
+
     /// Try to find texture locally in model directory
     /// Case-insensitive search with automatic .blp extension
     pub fn find_local_path(&self, filename: &str) -> Option<PathBuf> {
-        if let Some(model_dir) = &self.model_directory {
-            // Normalize filename: lowercase, forward slashes
-            let normalized = filename.to_lowercase().replace('\\', "/");
+        // Normalize filename: lowercase, forward slashes
+        let normalized = filename.to_lowercase().replace('\\', "/");
+
+        // Add .blp extension if not present
+        let with_extension = if !normalized.ends_with(".blp") {
+            format!("{}.blp", normalized)
+        } else {
+            normalized.clone()
+        };
 
-            // Add .blp extension if not present
-            let with_extension = if !normalized.ends_with(".blp") {
-                format!("{}.blp", normalized)
-            } else {
-                normalized.clone()
-            };
+        self.find_local_file(&with_extension)
+    }
+
+    /// Try to find a model locally in model directory, as MDX first whatever the
+    /// extension of `filename`, then as MDL
+    pub fn find_local_model(&self, filename: &str) -> Option<PathBuf> {
+        let normalized = filename.to_lowercase().replace('\\', "/");
+        let stem = normalized
+            .strip_suffix(".mdl")
+            .or_else(|| normalized.strip_suffix(".mdx"))
+            .unwrap_or(&normalized);
+
+        self.find_local_file(&format!("{}.mdx", stem))
+            .or_else(|| self.find_local_file(&format!("{}.mdl", stem)))
+    }
 
+    /// Case-insensitive search for the file name of a lowercase game path in the model
+    /// directory and the usual places next to it
+    fn find_local_file(&self, normalized: &str) -> Option<PathBuf> {
+        if let Some(model_dir) = &self.model_directory {
             // Extract just the filename without path
-            let filename_only = Path::new(&with_extension)
+            let filename_only = Path::new(normalized)
                 .file_name()
                 .and_then(|f| f.to_str())
-                .unwrap_or(&with_extension);
+                .unwrap_or(normalized);
 
             // Directories to search in
             let search_dirs = vec![
@@ -11,6 +11,11 @@ pub struct Ui {
     use_animation: bool,
     last_update_time: f64,
     last_frame_time: f64,
+    selected_attachment: usize,
+    attached_to: Option<usize>, // Attachment point the preview model hangs from
+    attach_requested: bool,
+    detach_requested: bool,
+    camera_view: Option<usize>, // Model camera the viewport looks through, orbit when None
 }
 
 impl Ui {
@@ -24,6 +29,11 @@ impl Ui {
             use_animation: false,
             last_update_time: 0.0,
             last_frame_time: 0.0,
+            selected_attachment: 0,
+            attached_to: None,
+            attach_requested: false,
+            detach_requested: false,
+            camera_view: None,
         }
     }
 
@@ -34,6 +44,7 @@ impl Ui {
         self.use_animation = false; // Back to original parsed data
         self.last_update_time = 0.0;
         self.last_frame_time = 0.0;
+        self.camera_view = None; // Cameras of the previous model
 
         // Set current_frame to start of first sequence
         if let Some(model) = model {
@@ -47,6 +58,34 @@ impl Ui {
         }
     }
 
+    /// Index of the sequence selected in the Animation window
+    pub fn selected_sequence(&self) -> usize {
+        self.selected_sequence
+    }
+
+    /// Model camera selected to look through, None for the orbit camera
+    pub fn camera_view(&self) -> Option<usize> {
+        self.camera_view
+    }
+
+    /// Attachment point to attach a model to, if one was requested since the last call
+    pub fn take_attach_request(&mut self) -> Option<usize> {
+        std::mem::take(&mut self.attach_requested).then_some(self.selected_attachment)
+    }
+
+    /// True if detaching the preview model was requested since the last call
+    pub fn take_detach_request(&mut self) -> bool {
+        std::mem::take(&mut self.detach_requested)
+    }
+
+    /// Attachment point the preview model currently hangs from
+    pub fn set_attached(&mut self, attachment: Option<usize>) {
+        self.attached_to = attachment;
+        if let Some(attachment) = attachment {
+            self.selected_attachment = attachment;
+        }
+    }
+
     /// Update animation playback - advances current_frame based on time
     /// Should be called every frame BEFORE show()
     pub fn animate(&mut self, model: &Option<Model>, current_time: f64) {
@@ -98,11 +137,12 @@ impl Ui {
         camera_pitch: f32,
         settings: &mut Settings,
         renderer: &mut crate::renderer::renderer::Renderer,
-    ) -> (bool, f32, Vec<bool>, bool, bool, bool) {
-        // reset_camera, current_frame, show_geosets, colors_changed, open_model, use_animation
+    ) -> (bool, f32, Vec<bool>, bool, bool, bool, bool) {
+        // reset_camera, current_frame, show_geosets, colors_changed, open_model, save_model, use_animation
         let mut reset_camera = false;
         let mut colors_changed = false;
         let mut open_model = false;
+        let mut save_model = false;
 
         // Top menu bar
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
@@ -112,6 +152,14 @@ impl Ui {
                     open_model = true;
                 }
 
+                // Save Model button
+                if ui
+                    .add_enabled(model.is_some(), egui::Button::new("💾 Save Model"))
+                    .clicked()
+                {
+                    save_model = true;
+                }
+
                 ui.separator();
                 ui.label("📋 Windows:");
 
@@ -226,6 +274,10 @@ impl Ui {
             self.show_animation_window(ctx, model, &mut settings.ui);
         }
 
+        if settings.display.show_attachments {
+            Self::draw_attachment_labels(ctx, renderer);
+        }
+
         // Draw axis gizmo in bottom-right corner (Blender-style)
         let gizmo_size = 100.0;
         let gizmo_margin = 20.0;
@@ -327,10 +379,41 @@ impl Ui {
             self.show_geosets.clone(),
             colors_changed,
             open_model,
+            save_model,
             self.use_animation,
         )
     }
 
+    /// Names next to the attachment markers, behind every window
+    fn draw_attachment_labels(ctx: &egui::Context, renderer: &crate::renderer::renderer::Renderer) {
+        let screen_rect = ctx.viewport_rect();
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Background,
+            egui::Id::new("attachment_labels_painter"),
+        ));
+        let font_id = egui::FontId::proportional(12.0);
+
+        for (name, position) in &renderer.attachment_labels {
+            let clip = renderer.view_proj_matrix
+                * nalgebra_glm::vec4(position[0], position[1], position[2], 1.0);
+            if clip.w <= 0.0 {
+                continue; // Behind the camera
+            }
+            let ndc = egui::vec2(clip.x / clip.w, clip.y / clip.w);
+            let screen_pos = egui::pos2(
+                screen_rect.min.x + (ndc.x + 1.0) * 0.5 * screen_rect.width(),
+                screen_rect.min.y + (1.0 - ndc.y) * 0.5 * screen_rect.height(),
+            );
+            painter.text(
+                screen_pos + egui::vec2(6.0, -6.0),
+                egui::Align2::LEFT_BOTTOM,
+                name,
+                font_id.clone(),
+                egui::Color32::from_rgb(120, 255, 150),
+            );
+        }
+    }
+
     fn show_display_settings_window(
         &mut self,
         ctx: &egui::Context,
@@ -357,6 +440,30 @@ impl Ui {
                 changed |= ui
                     .checkbox(&mut settings.display.show_bounding_box, "Show Bounding Box")
                     .changed();
+                changed |= ui
+                    .checkbox(
+                        &mut settings.display.show_sequence_extent,
+                        "Show Sequence Extent",
+                    )
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut settings.display.show_attachments, "Show Attachments")
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut settings.display.show_particles, "Show Particles")
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut settings.display.show_ribbons, "Show Ribbons")
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut settings.display.show_lights, "Show Lights")
+                    .changed();
+                changed |= ui
+                    .checkbox(
+                        &mut settings.display.show_collision_shapes,
+                        "Show Collision Shapes",
+                    )
+                    .changed();
 
                 ui.separator();
                 ui.label("Far Plane (View Distance):");
@@ -421,6 +528,11 @@ impl Ui {
                     .color_edit_button_rgb(&mut settings.colors.bounding_box_color)
                     .changed();
 
+                ui.label("Sequence Extent Color:");
+                changed |= ui
+                    .color_edit_button_rgb(&mut settings.colors.sequence_extent_color)
+                    .changed();
+
                 ui.separator();
 
                 if ui.button("Reset to Defaults").clicked() {
@@ -454,6 +566,14 @@ impl Ui {
             .show(ctx, |ui| {
                 if let Some(model) = model {
                     ui.label(format!("Name: {}", model.name));
+                    if !model.animation_file.is_empty() {
+                        ui.label(format!("Animation file: {}", model.animation_file));
+                    }
+                    ui.label(format!("Blend time: {}", model.blend_time));
+                    ui.label(format!(
+                        "Extent: min {:?}, max {:?}, radius {}",
+                        model.minimum_extent, model.maximum_extent, model.bounds_radius
+                    ));
                     ui.separator();
 
                     ui.label(format!("Geosets: {}", model.geosets.len()));
@@ -468,8 +588,159 @@ impl Ui {
                     ui.label(format!("Materials: {}", model.materials.len()));
                     ui.label(format!("Textures: {}", model.textures.len()));
                     ui.label(format!("Sequences: {}", model.sequences.len()));
+                    ui.label(format!(
+                        "Global sequences: {}",
+                        model.global_sequences.len()
+                    ));
                     ui.label(format!("Bones: {}", model.bones.len()));
                     ui.label(format!("Helpers: {}", model.helpers.len()));
+                    ui.label(format!("Lights: {}", model.lights.len()));
+                    ui.label(format!("Attachments: {}", model.attachments.len()));
+                    ui.label(format!(
+                        "Particle emitters: {}",
+                        model.particle_emitters.len()
+                    ));
+                    ui.label(format!(
+                        "Particle emitters 2: {}",
+                        model.particle_emitters2.len()
+                    ));
+                    ui.label(format!("Ribbon emitters: {}", model.ribbon_emitters.len()));
+                    ui.label(format!("Cameras: {}", model.cameras.len()));
+                    ui.label(format!("Event objects: {}", model.event_objects.len()));
+                    ui.label(format!(
+                        "Collision shapes: {}",
+                        model.collision_shapes.len()
+                    ));
+
+                    if !model.attachments.is_empty() {
+                        ui.separator();
+                        egui::CollapsingHeader::new("Attachment points")
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for (idx, attachment) in model.attachments.iter().enumerate() {
+                                    let mut label = attachment.name.trim().to_string();
+                                    if self.attached_to == Some(idx) {
+                                        label.push_str(" (attached)");
+                                    }
+                                    ui.selectable_value(&mut self.selected_attachment, idx, label);
+                                }
+                            });
+                        ui.horizontal(|ui| {
+                            if ui.button("Attach model...").clicked() {
+                                self.attach_requested = true;
+                            }
+                            if ui
+                                .add_enabled(
+                                    self.attached_to.is_some(),
+                                    egui::Button::new("Detach"),
+                                )
+                                .clicked()
+                            {
+                                self.detach_requested = true;
+                            }
+                        });
+                    }
+
+                    if !model.cameras.is_empty() {
+                        ui.separator();
+                        egui::CollapsingHeader::new("Cameras")
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                ui.radio_value(&mut self.camera_view, None, "Orbit camera");
+                                for (idx, camera) in model.cameras.iter().enumerate() {
+                                    ui.radio_value(
+                                        &mut self.camera_view,
+                                        Some(idx),
+                                        format!("Look through {}", camera.name.trim()),
+                                    );
+                                }
+                            });
+                    }
+
+                    // Chunks the viewer does not understand are kept and written back on save
+                    let sub_chunks: usize = model
+                        .geosets
+                        .iter()
+                        .map(|g| g.unknown_chunks.len())
+                        .chain(model.bones.iter().map(|b| b.unknown_chunks.len()))
+                        .chain(model.helpers.iter().map(|h| h.unknown_chunks.len()))
+                        .chain(
+                            model
+                                .lights
+                                .iter()
+                                .map(|l| l.unknown_chunks.len() + l.unknown_tracks.len()),
+                        )
+                        .chain(
+                            model
+                                .attachments
+                                .iter()
+                                .map(|a| a.unknown_chunks.len() + a.unknown_tracks.len()),
+                        )
+                        .chain(
+                            model
+                                .particle_emitters
+                                .iter()
+                                .map(|e| e.unknown_chunks.len() + e.unknown_tracks.len()),
+                        )
+                        .chain(
+                            model
+                                .particle_emitters2
+                                .iter()
+                                .map(|e| e.unknown_chunks.len() + e.unknown_tracks.len()),
+                        )
+                        .chain(
+                            model
+                                .ribbon_emitters
+                                .iter()
+                                .map(|e| e.unknown_chunks.len() + e.unknown_tracks.len()),
+                        )
+                        .chain(
+                            model
+                                .geoset_animations
+                                .iter()
+                                .map(|g| g.unknown_chunks.len()),
+                        )
+                        .chain(
+                            model
+                                .texture_animations
+                                .iter()
+                                .map(|t| t.unknown_chunks.len()),
+                        )
+                        .chain(model.cameras.iter().map(|c| c.unknown_chunks.len()))
+                        .chain(model.event_objects.iter().map(|e| e.unknown_chunks.len()))
+                        .chain(
+                            model
+                                .collision_shapes
+                                .iter()
+                                .map(|c| c.unknown_chunks.len()),
+                        )
+                        .chain(
+                            model
+                                .materials
+                                .iter()
+                                .flat_map(|m| &m.layers)
+                                .map(|l| l.unknown_chunks.len()),
+                        )
+                        .sum();
+                    if !model.unknown_chunks.is_empty() || sub_chunks > 0 {
+                        ui.separator();
+                        egui::CollapsingHeader::new(format!(
+                            "Unparsed chunks: {}",
+                            model.unknown_chunks.len()
+                        ))
+                        .show(ui, |ui| {
+                            for chunk in &model.unknown_chunks {
+                                ui.label(format!(
+                                    "{}: {} bytes",
+                                    chunk.tag_name(),
+                                    chunk.data.len()
+                                ));
+                            }
+                            if sub_chunks > 0 {
+                                ui.label(format!("Unparsed sub-chunks: {}", sub_chunks));
+                            }
+                        });
+                    }
                 } else {
                     ui.label("No model loaded");
                 }
@@ -555,8 +826,8 @@ impl Ui {
                                     {
                                         // Build JSON representation
                                         let mut json = format!(
-                                            "{{\n  \"material_id\": {},\n  \"layers\": [\n",
-                                            mat_id
+                                            "{{\n  \"material_id\": {},\n  \"priority_plane\": {},\n  \"layers\": [\n",
+                                            mat_id, material.priority_plane
                                         );
 
                                         for (layer_id, layer) in material.layers.iter().enumerate()
@@ -627,12 +898,22 @@ impl Ui {
                                 });
 
                                 ui.label(format!("Layers: {}", material.layers.len()));
+                                ui.label(format!("Priority plane: {}", material.priority_plane));
+                                let flags = material.flag_list();
+                                if !flags.is_empty() {
+                                    let names: Vec<&str> =
+                                        flags.iter().map(|f| f.name()).collect();
+                                    ui.label(format!("Flags: {}", names.join(", ")));
+                                }
 
                                 // No need to initialize - data is in the model now
 
                                 for (layer_id, layer) in material.layers.iter_mut().enumerate() {
                                     ui.separator();
 
+                                    // Animated values, only present while animation plays
+                                    let layer_state = renderer.layer_state(mat_id, layer_id).cloned();
+
                                     // Layer header with checkbox - edit model directly
                                     ui.horizontal(|ui| {
                                         ui.checkbox(&mut layer.enabled, "");
@@ -1050,6 +1331,35 @@ impl Ui {
                                                 );
                                             }
                                         });
+
+                                        if let Some(anim_id) = layer.texture_animation_id {
+                                            ui.label(format!("    Texture animation: #{}", anim_id));
+                                        }
+                                        if layer.coord_id != 0 {
+                                            ui.label(format!("    UV set (CoordId): {}", layer.coord_id));
+                                        }
+
+                                        // Current value of animated tracks next to the static one
+                                        if layer.texture_id_idx >= 0 {
+                                            let current = layer_state
+                                                .as_ref()
+                                                .and_then(|state| state.texture_id)
+                                                .or(layer.texture_id);
+                                            ui.label(format!(
+                                                "    Texture ID (KMTF): {} (static: {})",
+                                                current.map_or("None".to_string(), |id| id.to_string()),
+                                                layer.texture_id.map_or("None".to_string(), |id| id.to_string())
+                                            ));
+                                        }
+                                        if layer.alpha_idx >= 0 {
+                                            let current = layer_state
+                                                .as_ref()
+                                                .map_or(layer.alpha, |state| state.alpha);
+                                            ui.label(format!(
+                                                "    Alpha (KMTA): {:.2} (static: {:.2})",
+                                                current, layer.alpha
+                                            ));
+                                        }
                                     }); // end add_enabled_ui
                                 }
                             }); // end CollapsingHeader
@@ -1191,9 +1501,16 @@ impl Ui {
                             ui.label("⚠ Non-looping");
                         }
 
-                        if let Some(rarity) = seq.rarity {
-                            ui.label(format!("Rarity: {}", rarity));
+                        if seq.rarity != 0.0 {
+                            ui.label(format!("Rarity: {}", seq.rarity));
+                        }
+                        if seq.move_speed != 0.0 {
+                            ui.label(format!("Move speed: {}", seq.move_speed));
                         }
+                        ui.label(format!(
+                            "Extent: min {:?}, max {:?}, radius {}",
+                            seq.minimum_extent, seq.maximum_extent, seq.bounds_radius
+                        ));
 
                         ui.separator();
 
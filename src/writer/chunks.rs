@@ -0,0 +1,835 @@
+use crate::error::MdlError;
+use crate::model::collision::CollisionShape;
+use crate::model::emitter::{ParticleEmitter, ParticleEmitter2, RibbonEmitter};
+use crate::model::event::EventObject;
+use crate::model::model::Model;
+use crate::model::raw_chunk::RawChunk;
+use crate::model::skeleton::{
+    NODE_FLAG_ATTACHMENT, NODE_FLAG_BONE, NODE_FLAG_COLLISION_SHAPE, NODE_FLAG_EVENT,
+    NODE_FLAG_HELPER, NODE_FLAG_LIGHT, NODE_FLAG_PARTICLE_EMITTER, NODE_FLAG_RIBBON_EMITTER,
+};
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::io::Write;
+
+/// Writes a string into a fixed-size, zero-padded field
+pub(crate) fn write_name(buf: &mut Vec<u8>, name: &str, len: usize) -> Result<(), MdlError> {
+    let bytes = name.as_bytes();
+    let used = bytes.len().min(len);
+    buf.write_all(&bytes[..used])?;
+    buf.resize(buf.len() + (len - used), 0);
+    Ok(())
+}
+
+/// Writes a string into a fixed-size field, followed by the bytes that were found after
+/// its end when it was read. Padding that no longer fits is left out
+fn write_padded_name(
+    buf: &mut Vec<u8>,
+    name: &str,
+    padding: &[u8],
+    len: usize,
+) -> Result<(), MdlError> {
+    let start = buf.len();
+    write_name(buf, name, len)?;
+    let offset = name.len() + 1;
+    if !padding.is_empty() && offset + padding.len() <= len {
+        buf[start + offset..start + offset + padding.len()].copy_from_slice(padding);
+    }
+    Ok(())
+}
+
+/// Writes a structure prefixed with its inclusiveSize (the size INCLUDING this u32)
+pub(crate) fn write_inclusive(
+    buf: &mut Vec<u8>,
+    body: impl FnOnce(&mut Vec<u8>) -> Result<(), MdlError>,
+) -> Result<(), MdlError> {
+    let start = buf.len();
+    buf.write_u32::<LittleEndian>(0)?;
+    body(buf)?;
+    let size = (buf.len() - start) as u32;
+    buf[start..start + 4].copy_from_slice(&size.to_le_bytes());
+    Ok(())
+}
+
+/// Writes a controller track chunk (KGTR, KGRT, KGSC, KGAO...) if the index points
+/// to a controller, does nothing for -1 (static value)
+pub(crate) fn write_controller(
+    buf: &mut Vec<u8>,
+    model: &Model,
+    tag: &[u8; 4],
+    controller_idx: i32,
+) -> Result<(), MdlError> {
+    write_controller_as(buf, model, tag, controller_idx, f32::to_bits)
+}
+
+/// Same as write_controller for tracks of integers (KMTF) kept as f32 in the model
+pub(crate) fn write_int_controller(
+    buf: &mut Vec<u8>,
+    model: &Model,
+    tag: &[u8; 4],
+    controller_idx: i32,
+) -> Result<(), MdlError> {
+    write_controller_as(buf, model, tag, controller_idx, |value| value as u32)
+}
+
+fn write_controller_as(
+    buf: &mut Vec<u8>,
+    model: &Model,
+    tag: &[u8; 4],
+    controller_idx: i32,
+    encode: fn(f32) -> u32,
+) -> Result<(), MdlError> {
+    let Some(controller) = usize::try_from(controller_idx)
+        .ok()
+        .and_then(|idx| model.controllers.get(idx))
+    else {
+        return Ok(());
+    };
+
+    buf.write_all(tag)?;
+    buf.write_u32::<LittleEndian>(controller.keyframes.len() as u32)?;
+    buf.write_u32::<LittleEndian>(controller.interpolation_type)?;
+    buf.write_i32::<LittleEndian>(controller.global_seq_id)?;
+
+    // Tangents are present only for Hermite (2) and Bezier (3)
+    let has_tangents = controller.interpolation_type == 2 || controller.interpolation_type == 3;
+
+    for keyframe in &controller.keyframes {
+        buf.write_i32::<LittleEndian>(keyframe.frame)?;
+        for &value in &keyframe.data {
+            buf.write_u32::<LittleEndian>(encode(value))?;
+        }
+        if has_tangents {
+            for i in 0..keyframe.data.len() {
+                buf.write_u32::<LittleEndian>(encode(
+                    keyframe.in_tan.get(i).copied().unwrap_or(0.0),
+                ))?;
+            }
+            for i in 0..keyframe.data.len() {
+                buf.write_u32::<LittleEndian>(encode(
+                    keyframe.out_tan.get(i).copied().unwrap_or(0.0),
+                ))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes sub-chunks kept by the parser back as tag + data
+pub(crate) fn write_raw_sub_chunks(buf: &mut Vec<u8>, chunks: &[RawChunk]) -> Result<(), MdlError> {
+    for chunk in chunks {
+        buf.write_all(&chunk.tag)?;
+        buf.write_all(&chunk.data)?;
+    }
+    Ok(())
+}
+
+// Tracks whose keyframes hold integers, kept as f32 in the model
+const INT_TRACKS: [&[u8; 4]; 4] = [b"KMTF", b"KLAS", b"KLAE", b"KRTX"];
+
+/// Writes the tracks of an object and the raw tracks kept with it. They follow the order
+/// they were loaded in, or when there is none, `tracks` order followed by the raw ones
+pub(crate) fn write_tracks(
+    buf: &mut Vec<u8>,
+    model: &Model,
+    tracks: &[(&[u8; 4], i32)],
+    unknown: &[RawChunk],
+    order: &[[u8; 4]],
+) -> Result<(), MdlError> {
+    let mut written: Vec<&[u8; 4]> = Vec::new();
+    let mut write_track = |buf: &mut Vec<u8>, tag: &[u8; 4]| -> Result<bool, MdlError> {
+        let Some(&(tag, controller_idx)) = tracks.iter().find(|(t, _)| *t == tag) else {
+            return Ok(false);
+        };
+        if written.contains(&tag) {
+            return Ok(false);
+        }
+        written.push(tag);
+        if INT_TRACKS.contains(&tag) {
+            write_int_controller(buf, model, tag, controller_idx)?;
+        } else {
+            write_controller(buf, model, tag, controller_idx)?;
+        }
+        Ok(true)
+    };
+
+    let mut raw_tracks = unknown.iter();
+    for tag in order {
+        if !write_track(buf, tag)?
+            && let Some(raw) = raw_tracks.next()
+        {
+            write_raw_sub_chunks(buf, std::slice::from_ref(raw))?;
+        }
+    }
+
+    for (tag, _) in tracks {
+        write_track(buf, tag)?;
+    }
+    write_raw_sub_chunks(buf, raw_tracks.as_slice())
+}
+
+/// Fixed fields at the start of every Node structure
+pub(crate) struct NodeHeader<'a> {
+    pub name: &'a str,
+    pub object_id: u32,
+    pub parent_id: i32,
+    pub flags: u32,
+}
+
+/// Node flags with the type bit back in, unless the loaded file had none
+fn with_type_flag(flags: u32, type_flag: u32, type_flag_missing: bool) -> u32 {
+    if type_flag_missing {
+        flags
+    } else {
+        flags | type_flag
+    }
+}
+
+/// Writes the shared Node structure of bones, helpers and other objects
+pub(crate) fn write_node(
+    buf: &mut Vec<u8>,
+    model: &Model,
+    header: NodeHeader,
+    tracks: &[(&[u8; 4], i32)],
+    unknown_chunks: &[RawChunk],
+    track_order: &[[u8; 4]],
+) -> Result<(), MdlError> {
+    write_inclusive(buf, |buf| {
+        write_name(buf, header.name, 0x50)?;
+        buf.write_u32::<LittleEndian>(header.object_id)?;
+        buf.write_i32::<LittleEndian>(header.parent_id)?;
+        buf.write_u32::<LittleEndian>(header.flags)?;
+        write_tracks(buf, model, tracks, unknown_chunks, track_order)
+    })
+}
+
+pub(crate) fn write_model_info(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    write_name(buf, &model.name, 0x50)?;
+    write_padded_name(
+        buf,
+        &model.animation_file,
+        &model.animation_file_padding,
+        0x104,
+    )?;
+    // Extent: bounds radius, min, max
+    buf.write_f32::<LittleEndian>(model.bounds_radius)?;
+    for value in model.minimum_extent {
+        buf.write_f32::<LittleEndian>(value)?;
+    }
+    for value in model.maximum_extent {
+        buf.write_f32::<LittleEndian>(value)?;
+    }
+    buf.write_u32::<LittleEndian>(model.blend_time)?;
+    Ok(())
+}
+
+pub(crate) fn write_sequences(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for sequence in &model.sequences {
+        write_name(buf, &sequence.name, 0x50)?;
+        buf.write_u32::<LittleEndian>(sequence.start_frame)?;
+        buf.write_u32::<LittleEndian>(sequence.end_frame)?;
+        buf.write_f32::<LittleEndian>(sequence.move_speed)?;
+        buf.write_u32::<LittleEndian>(sequence.non_looping as u32)?;
+        buf.write_f32::<LittleEndian>(sequence.rarity)?;
+        buf.write_u32::<LittleEndian>(sequence.sync_point)?;
+        // Extent: bounds radius, min, max
+        buf.write_f32::<LittleEndian>(sequence.bounds_radius)?;
+        for value in sequence.minimum_extent {
+            buf.write_f32::<LittleEndian>(value)?;
+        }
+        for value in sequence.maximum_extent {
+            buf.write_f32::<LittleEndian>(value)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn write_global_sequences(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for &duration in &model.global_sequences {
+        buf.write_u32::<LittleEndian>(duration)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_textures(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for texture in &model.textures {
+        buf.write_u32::<LittleEndian>(texture.replaceable_id)?;
+        write_name(buf, &texture.filename, 0x104)?;
+        buf.write_u32::<LittleEndian>(texture.flags)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_materials(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for material in &model.materials {
+        write_inclusive(buf, |buf| {
+            buf.write_i32::<LittleEndian>(material.priority_plane)?;
+            buf.write_u32::<LittleEndian>(material.flags)?;
+            buf.write_all(b"LAYS")?;
+
+            // Layers switched off in the Materials window are not saved,
+            // the rest are saved with their overrides applied
+            let layers: Vec<_> = material.layers.iter().filter(|l| l.is_enabled()).collect();
+            buf.write_u32::<LittleEndian>(layers.len() as u32)?;
+
+            for layer in layers {
+                write_inclusive(buf, |buf| {
+                    buf.write_u32::<LittleEndian>(layer.get_filter_mode().to_u32())?;
+                    buf.write_u32::<LittleEndian>(crate::material::ShadingFlags::get_bits(
+                        &layer.get_shading_flags(),
+                    ))?;
+                    buf.write_u32::<LittleEndian>(layer.texture_id.unwrap_or(0) as u32)?;
+                    buf.write_i32::<LittleEndian>(
+                        layer.texture_animation_id.map_or(-1, |id| id as i32),
+                    )?;
+                    buf.write_u32::<LittleEndian>(layer.coord_id)?;
+                    buf.write_f32::<LittleEndian>(layer.get_alpha())?;
+                    write_tracks(
+                        buf,
+                        model,
+                        &[(b"KMTA", layer.alpha_idx), (b"KMTF", layer.texture_id_idx)],
+                        &layer.unknown_chunks,
+                        &layer.track_order,
+                    )
+                })?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_texture_animations(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for texture_animation in &model.texture_animations {
+        write_inclusive(buf, |buf| {
+            write_tracks(
+                buf,
+                model,
+                &[
+                    (b"KTAT", texture_animation.translation_idx),
+                    (b"KTAR", texture_animation.rotation_idx),
+                    (b"KTAS", texture_animation.scaling_idx),
+                ],
+                &texture_animation.unknown_chunks,
+                &texture_animation.track_order,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_geoset_animations(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for geoset_animation in &model.geoset_animations {
+        write_inclusive(buf, |buf| {
+            buf.write_f32::<LittleEndian>(geoset_animation.alpha)?;
+            buf.write_u32::<LittleEndian>(geoset_animation.flags)?;
+            for value in geoset_animation.color {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+            buf.write_u32::<LittleEndian>(geoset_animation.geoset_id)?;
+            write_tracks(
+                buf,
+                model,
+                &[
+                    (b"KGAO", geoset_animation.alpha_idx),
+                    (b"KGAC", geoset_animation.color_idx),
+                ],
+                &geoset_animation.unknown_chunks,
+                &geoset_animation.track_order,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_bones(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for bone in &model.bones {
+        write_node(
+            buf,
+            model,
+            NodeHeader {
+                name: &bone.name,
+                object_id: bone.object_id,
+                parent_id: bone.parent_id,
+                flags: with_type_flag(bone.flags, NODE_FLAG_BONE, bone.type_flag_missing),
+            },
+            &[
+                (b"KGTR", bone.translation_idx),
+                (b"KGRT", bone.rotation_idx),
+                (b"KGSC", bone.scaling_idx),
+                (b"KLAV", bone.visibility_idx),
+            ],
+            &bone.unknown_chunks,
+            &bone.node_track_order,
+        )?;
+
+        // Bone-specific fields (AFTER Node structure)
+        buf.write_i32::<LittleEndian>(bone.geoset_id.map_or(-1, |id| id as i32))?;
+        buf.write_i32::<LittleEndian>(bone.geoset_anim_id.map_or(-1, |id| id as i32))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_helpers(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for helper in &model.helpers {
+        write_node(
+            buf,
+            model,
+            NodeHeader {
+                name: &helper.name,
+                object_id: helper.object_id,
+                parent_id: helper.parent_id,
+                flags: helper.flags | NODE_FLAG_HELPER,
+            },
+            &[
+                (b"KGTR", helper.translation_idx),
+                (b"KGRT", helper.rotation_idx),
+                (b"KGSC", helper.scaling_idx),
+                (b"KLAV", helper.visibility_idx),
+            ],
+            &helper.unknown_chunks,
+            &helper.node_track_order,
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_lights(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for light in &model.lights {
+        write_inclusive(buf, |buf| {
+            write_node(
+                buf,
+                model,
+                NodeHeader {
+                    name: &light.name,
+                    object_id: light.object_id,
+                    parent_id: light.parent_id,
+                    flags: with_type_flag(light.flags, NODE_FLAG_LIGHT, light.type_flag_missing),
+                },
+                &[
+                    (b"KGTR", light.translation_idx),
+                    (b"KGRT", light.rotation_idx),
+                    (b"KGSC", light.scaling_idx),
+                ],
+                &light.unknown_chunks,
+                &light.node_track_order,
+            )?;
+
+            buf.write_u32::<LittleEndian>(light.light_type)?;
+            for value in [light.attenuation_start, light.attenuation_end] {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+            for value in light.color {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+            buf.write_f32::<LittleEndian>(light.intensity)?;
+            for value in light.amb_color {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+            buf.write_f32::<LittleEndian>(light.amb_intensity)?;
+
+            write_tracks(
+                buf,
+                model,
+                &[
+                    (b"KLAS", light.attenuation_start_idx),
+                    (b"KLAE", light.attenuation_end_idx),
+                    (b"KLAC", light.color_idx),
+                    (b"KLAI", light.intensity_idx),
+                    (b"KLBI", light.amb_intensity_idx),
+                    (b"KLBC", light.amb_color_idx),
+                    (b"KLAV", light.visibility_idx),
+                ],
+                &light.unknown_tracks,
+                &light.track_order,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_attachments(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for attachment in &model.attachments {
+        write_inclusive(buf, |buf| {
+            write_node(
+                buf,
+                model,
+                NodeHeader {
+                    name: &attachment.name,
+                    object_id: attachment.object_id,
+                    parent_id: attachment.parent_id,
+                    flags: with_type_flag(
+                        attachment.flags,
+                        NODE_FLAG_ATTACHMENT,
+                        attachment.type_flag_missing,
+                    ),
+                },
+                &[
+                    (b"KGTR", attachment.translation_idx),
+                    (b"KGRT", attachment.rotation_idx),
+                    (b"KGSC", attachment.scaling_idx),
+                ],
+                &attachment.unknown_chunks,
+                &attachment.node_track_order,
+            )?;
+
+            // Attachment-specific fields (AFTER Node structure)
+            write_name(buf, &attachment.path, 0x104)?;
+            buf.write_u32::<LittleEndian>(attachment.attachment_id)?;
+            write_tracks(
+                buf,
+                model,
+                &[(b"KATV", attachment.visibility_idx)],
+                &attachment.unknown_tracks,
+                &attachment.track_order,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_particle_emitters(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for emitter in &model.particle_emitters {
+        write_inclusive(buf, |buf| {
+            write_node(
+                buf,
+                model,
+                NodeHeader {
+                    name: &emitter.name,
+                    object_id: emitter.object_id,
+                    parent_id: emitter.parent_id,
+                    flags: with_type_flag(
+                        emitter.flags,
+                        NODE_FLAG_PARTICLE_EMITTER,
+                        emitter.type_flag_missing,
+                    ),
+                },
+                &[
+                    (b"KGTR", emitter.translation_idx),
+                    (b"KGRT", emitter.rotation_idx),
+                    (b"KGSC", emitter.scaling_idx),
+                ],
+                &emitter.unknown_chunks,
+                &emitter.node_track_order,
+            )?;
+            write_particle_emitter_fields(buf, emitter)?;
+
+            write_tracks(
+                buf,
+                model,
+                &[
+                    (b"KPEE", emitter.emission_rate_idx),
+                    (b"KPEG", emitter.gravity_idx),
+                    (b"KPLN", emitter.longitude_idx),
+                    (b"KPLT", emitter.latitude_idx),
+                    (b"KPEL", emitter.lifespan_idx),
+                    (b"KPES", emitter.speed_idx),
+                    (b"KPEV", emitter.visibility_idx),
+                ],
+                &emitter.unknown_tracks,
+                &emitter.track_order,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+// Fixed fields of a ParticleEmitter, between its Node and its tracks
+fn write_particle_emitter_fields(
+    buf: &mut Vec<u8>,
+    emitter: &ParticleEmitter,
+) -> Result<(), MdlError> {
+    for value in [
+        emitter.emission_rate,
+        emitter.gravity,
+        emitter.longitude,
+        emitter.latitude,
+    ] {
+        buf.write_f32::<LittleEndian>(value)?;
+    }
+    write_name(buf, &emitter.path, 0x104)?;
+    buf.write_f32::<LittleEndian>(emitter.lifespan)?;
+    buf.write_f32::<LittleEndian>(emitter.speed)?;
+    Ok(())
+}
+
+pub(crate) fn write_particle_emitters2(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for emitter in &model.particle_emitters2 {
+        write_inclusive(buf, |buf| {
+            write_node(
+                buf,
+                model,
+                NodeHeader {
+                    name: &emitter.name,
+                    object_id: emitter.object_id,
+                    parent_id: emitter.parent_id,
+                    flags: with_type_flag(
+                        emitter.flags,
+                        NODE_FLAG_PARTICLE_EMITTER,
+                        emitter.type_flag_missing,
+                    ),
+                },
+                &[
+                    (b"KGTR", emitter.translation_idx),
+                    (b"KGRT", emitter.rotation_idx),
+                    (b"KGSC", emitter.scaling_idx),
+                ],
+                &emitter.unknown_chunks,
+                &emitter.node_track_order,
+            )?;
+            write_particle_emitter2_fields(buf, emitter)?;
+
+            write_tracks(
+                buf,
+                model,
+                &[
+                    (b"KP2S", emitter.speed_idx),
+                    (b"KP2R", emitter.variation_idx),
+                    (b"KP2L", emitter.latitude_idx),
+                    (b"KP2G", emitter.gravity_idx),
+                    (b"KP2E", emitter.emission_rate_idx),
+                    (b"KP2N", emitter.length_idx),
+                    (b"KP2W", emitter.width_idx),
+                    (b"KP2V", emitter.visibility_idx),
+                ],
+                &emitter.unknown_tracks,
+                &emitter.track_order,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+// Fixed fields of a ParticleEmitter2, between its Node and its tracks
+fn write_particle_emitter2_fields(
+    buf: &mut Vec<u8>,
+    emitter: &ParticleEmitter2,
+) -> Result<(), MdlError> {
+    for value in [
+        emitter.speed,
+        emitter.variation,
+        emitter.latitude,
+        emitter.gravity,
+        emitter.lifespan,
+        emitter.emission_rate,
+        emitter.length,
+        emitter.width,
+    ] {
+        buf.write_f32::<LittleEndian>(value)?;
+    }
+    for value in [
+        emitter.filter_mode,
+        emitter.rows,
+        emitter.columns,
+        emitter.head_or_tail,
+    ] {
+        buf.write_u32::<LittleEndian>(value)?;
+    }
+    buf.write_f32::<LittleEndian>(emitter.tail_length)?;
+    buf.write_f32::<LittleEndian>(emitter.time)?;
+    for value in emitter.segment_colors.iter().flatten() {
+        buf.write_f32::<LittleEndian>(*value)?;
+    }
+    buf.write_all(&emitter.segment_alpha)?;
+    for value in emitter.segment_scaling {
+        buf.write_f32::<LittleEndian>(value)?;
+    }
+    let intervals = [
+        emitter.head_intervals,
+        emitter.head_decay_intervals,
+        emitter.tail_intervals,
+        emitter.tail_decay_intervals,
+    ];
+    for value in intervals.iter().flatten() {
+        buf.write_u32::<LittleEndian>(*value)?;
+    }
+    buf.write_u32::<LittleEndian>(emitter.texture_id)?;
+    buf.write_u32::<LittleEndian>(emitter.squirt as u32)?;
+    buf.write_i32::<LittleEndian>(emitter.priority_plane)?;
+    buf.write_u32::<LittleEndian>(emitter.replaceable_id)?;
+    Ok(())
+}
+
+pub(crate) fn write_ribbon_emitters(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for emitter in &model.ribbon_emitters {
+        write_inclusive(buf, |buf| {
+            write_node(
+                buf,
+                model,
+                NodeHeader {
+                    name: &emitter.name,
+                    object_id: emitter.object_id,
+                    parent_id: emitter.parent_id,
+                    flags: with_type_flag(
+                        emitter.flags,
+                        NODE_FLAG_RIBBON_EMITTER,
+                        emitter.type_flag_missing,
+                    ),
+                },
+                &[
+                    (b"KGTR", emitter.translation_idx),
+                    (b"KGRT", emitter.rotation_idx),
+                    (b"KGSC", emitter.scaling_idx),
+                ],
+                &emitter.unknown_chunks,
+                &emitter.node_track_order,
+            )?;
+            write_ribbon_emitter_fields(buf, emitter)?;
+
+            write_tracks(
+                buf,
+                model,
+                &[
+                    (b"KRHA", emitter.height_above_idx),
+                    (b"KRHB", emitter.height_below_idx),
+                    (b"KRAL", emitter.alpha_idx),
+                    (b"KRCO", emitter.color_idx),
+                    (b"KRTX", emitter.texture_slot_idx),
+                    (b"KRVS", emitter.visibility_idx),
+                ],
+                &emitter.unknown_tracks,
+                &emitter.track_order,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_cameras(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for camera in &model.cameras {
+        write_inclusive(buf, |buf| {
+            write_name(buf, &camera.name, 0x50)?;
+            for value in camera.position {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+            for value in [camera.field_of_view, camera.far_clip, camera.near_clip] {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+            for value in camera.target_position {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+
+            write_tracks(
+                buf,
+                model,
+                &[
+                    (b"KCTR", camera.translation_idx),
+                    (b"KCRL", camera.rotation_idx),
+                    (b"KTTR", camera.target_translation_idx),
+                ],
+                &camera.unknown_chunks,
+                &camera.track_order,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_event_objects(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for event in &model.event_objects {
+        // No inclusiveSize around the event, only around its Node
+        write_node(
+            buf,
+            model,
+            NodeHeader {
+                name: &event.name,
+                object_id: event.object_id,
+                parent_id: event.parent_id,
+                flags: with_type_flag(event.flags, NODE_FLAG_EVENT, event.type_flag_missing),
+            },
+            &[
+                (b"KGTR", event.translation_idx),
+                (b"KGRT", event.rotation_idx),
+                (b"KGSC", event.scaling_idx),
+            ],
+            &event.unknown_chunks,
+            &event.node_track_order,
+        )?;
+        write_event_track(buf, event)?;
+    }
+    Ok(())
+}
+
+// KEVT: a track of frames without values or interpolation
+fn write_event_track(buf: &mut Vec<u8>, event: &EventObject) -> Result<(), MdlError> {
+    if !event.has_track {
+        return Ok(());
+    }
+    buf.write_all(b"KEVT")?;
+    buf.write_u32::<LittleEndian>(event.frames.len() as u32)?;
+    buf.write_i32::<LittleEndian>(event.global_seq_id)?;
+    for &frame in &event.frames {
+        buf.write_u32::<LittleEndian>(frame)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_collision_shapes(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for shape in &model.collision_shapes {
+        // No inclusiveSize around the shape, only around its Node
+        write_node(
+            buf,
+            model,
+            NodeHeader {
+                name: &shape.name,
+                object_id: shape.object_id,
+                parent_id: shape.parent_id,
+                flags: with_type_flag(
+                    shape.flags,
+                    NODE_FLAG_COLLISION_SHAPE,
+                    shape.type_flag_missing,
+                ),
+            },
+            &[
+                (b"KGTR", shape.translation_idx),
+                (b"KGRT", shape.rotation_idx),
+                (b"KGSC", shape.scaling_idx),
+            ],
+            &shape.unknown_chunks,
+            &shape.node_track_order,
+        )?;
+
+        // The type decides how many values follow, missing vertices are written as zeros
+        buf.write_u32::<LittleEndian>(shape.shape_type)?;
+        for idx in 0..CollisionShape::vertex_count(shape.shape_type) {
+            for value in shape.vertices.get(idx).copied().unwrap_or_default() {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+        }
+        if CollisionShape::has_radius(shape.shape_type) {
+            buf.write_f32::<LittleEndian>(shape.radius)?;
+        }
+    }
+    Ok(())
+}
+
+// Fixed fields of a RibbonEmitter, between its Node and its tracks
+fn write_ribbon_emitter_fields(buf: &mut Vec<u8>, emitter: &RibbonEmitter) -> Result<(), MdlError> {
+    for value in [emitter.height_above, emitter.height_below, emitter.alpha] {
+        buf.write_f32::<LittleEndian>(value)?;
+    }
+    for value in emitter.color {
+        buf.write_f32::<LittleEndian>(value)?;
+    }
+    buf.write_f32::<LittleEndian>(emitter.lifespan)?;
+    for value in [
+        emitter.texture_slot,
+        emitter.emission_rate,
+        emitter.rows,
+        emitter.columns,
+        emitter.material_id,
+    ] {
+        buf.write_u32::<LittleEndian>(value)?;
+    }
+    buf.write_f32::<LittleEndian>(emitter.gravity)?;
+    Ok(())
+}
+
+pub(crate) fn write_pivots(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for pivot in &model.pivot_points {
+        for &value in pivot {
+            buf.write_f32::<LittleEndian>(value)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use crate::error::MdlError;
+use crate::model::model::Model;
+use crate::writer::chunks::{write_inclusive, write_raw_sub_chunks};
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::io::Write;
+
+pub fn geoset_write(buf: &mut Vec<u8>, model: &Model) -> Result<(), MdlError> {
+    for geoset in &model.geosets {
+        write_inclusive(buf, |buf| {
+            buf.write_all(b"VRTX")?;
+            buf.write_u32::<LittleEndian>(geoset.vertices.len() as u32)?;
+            for vertex in &geoset.vertices {
+                for value in vertex.position {
+                    buf.write_f32::<LittleEndian>(value)?;
+                }
+            }
+
+            buf.write_all(b"NRMS")?;
+            buf.write_u32::<LittleEndian>(geoset.normals.len() as u32)?;
+            for normal in &geoset.normals {
+                for value in normal.normal {
+                    buf.write_f32::<LittleEndian>(value)?;
+                }
+            }
+
+            // Primitive groups as loaded, faces are only their triangulation
+            buf.write_all(b"PTYP")?;
+            buf.write_u32::<LittleEndian>(geoset.primitive_types.len() as u32)?;
+            for &primitive_type in &geoset.primitive_types {
+                buf.write_u32::<LittleEndian>(primitive_type)?;
+            }
+
+            buf.write_all(b"PCNT")?;
+            buf.write_u32::<LittleEndian>(geoset.primitive_counts.len() as u32)?;
+            for &count in &geoset.primitive_counts {
+                buf.write_u32::<LittleEndian>(count)?;
+            }
+
+            buf.write_all(b"PVTX")?;
+            buf.write_u32::<LittleEndian>(geoset.primitive_indices.len() as u32)?;
+            for &index in &geoset.primitive_indices {
+                buf.write_u16::<LittleEndian>(index as u16)?;
+            }
+
+            buf.write_all(b"GNDX")?;
+            buf.write_u32::<LittleEndian>(geoset.vertex_groups.len() as u32)?;
+            buf.write_all(&geoset.vertex_groups)?;
+
+            buf.write_all(b"MTGC")?;
+            buf.write_u32::<LittleEndian>(geoset.matrix_groups.len() as u32)?;
+            for group in &geoset.matrix_groups {
+                buf.write_u32::<LittleEndian>(group.len() as u32)?;
+            }
+
+            buf.write_all(b"MATS")?;
+            let total_count: usize = geoset.matrix_groups.iter().map(|g| g.len()).sum();
+            buf.write_u32::<LittleEndian>(total_count as u32)?;
+            for &bone_index in geoset.matrix_groups.iter().flatten() {
+                buf.write_u32::<LittleEndian>(bone_index)?;
+            }
+
+            buf.write_u32::<LittleEndian>(geoset.material_id.unwrap_or(0) as u32)?;
+            buf.write_u32::<LittleEndian>(geoset.selection_group as u32)?;
+            buf.write_u32::<LittleEndian>(geoset.selectable)?;
+
+            buf.write_f32::<LittleEndian>(geoset.bounds_radius)?;
+            for value in geoset.minimum_extent {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+            for value in geoset.maximum_extent {
+                buf.write_f32::<LittleEndian>(value)?;
+            }
+            buf.write_u32::<LittleEndian>(geoset.sequence_extents.len() as u32)?;
+            for extent in &geoset.sequence_extents {
+                buf.write_f32::<LittleEndian>(extent.bounds_radius)?;
+                for value in extent.minimum_extent.iter().chain(&extent.maximum_extent) {
+                    buf.write_f32::<LittleEndian>(*value)?;
+                }
+            }
+
+            buf.write_all(b"UVAS")?;
+            if geoset.tex_coords.is_empty() {
+                buf.write_u32::<LittleEndian>(0)?;
+            } else {
+                let sets = std::iter::once(&geoset.tex_coords).chain(&geoset.secondary_tex_coords);
+                buf.write_u32::<LittleEndian>(1 + geoset.secondary_tex_coords.len() as u32)?;
+                for tex_coords in sets {
+                    buf.write_all(b"UVBS")?;
+                    buf.write_u32::<LittleEndian>(tex_coords.len() as u32)?;
+                    for tex_coord in tex_coords {
+                        for value in tex_coord.uv {
+                            buf.write_f32::<LittleEndian>(value)?;
+                        }
+                    }
+                }
+            }
+
+            write_raw_sub_chunks(buf, &geoset.unknown_chunks)
+        })?;
+    }
+
+    Ok(())
+}
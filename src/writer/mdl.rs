@@ -0,0 +1,903 @@
+use crate::error::MdlError;
+use crate::material::{Layer, ShadingFlags};
+use crate::model::collision::{
+    COLLISION_SHAPE_BOX, COLLISION_SHAPE_CYLINDER, COLLISION_SHAPE_PLANE, CollisionShape,
+};
+use crate::model::emitter::{
+    PARTICLE_FLAG_EMITTER_USES_MDL, PARTICLE_FLAG_EMITTER_USES_TGA, PARTICLE2_BOTH,
+    PARTICLE2_FILTER_ADDITIVE, PARTICLE2_FILTER_ALPHA_KEY, PARTICLE2_FILTER_MODULATE,
+    PARTICLE2_FILTER_MODULATE_2X, PARTICLE2_FLAG_LINE_EMITTER, PARTICLE2_FLAG_MODEL_SPACE,
+    PARTICLE2_FLAG_SORT_PRIMS_FAR_Z, PARTICLE2_FLAG_UNFOGGED, PARTICLE2_FLAG_UNSHADED,
+    PARTICLE2_FLAG_XY_QUAD, PARTICLE2_TAIL,
+};
+use crate::model::geoset::{
+    GEOSET_ANIM_COLOR, GEOSET_ANIM_DROP_SHADOW, PRIMITIVE_LINE_LOOP, PRIMITIVE_LINE_STRIP,
+    PRIMITIVE_LINES, PRIMITIVE_POINTS, PRIMITIVE_POLYGONS, PRIMITIVE_QUAD_STRIP, PRIMITIVE_QUADS,
+    PRIMITIVE_TRIANGLE_FAN, PRIMITIVE_TRIANGLE_STRIP, PRIMITIVE_TRIANGLES,
+};
+use crate::model::light::{LIGHT_TYPE_AMBIENT, LIGHT_TYPE_DIRECTIONAL};
+use crate::model::model::Model;
+use crate::model::skeleton::{
+    AnimationController, NODE_FLAG_BILLBOARDED, NODE_FLAG_BILLBOARDED_LOCK_X,
+    NODE_FLAG_BILLBOARDED_LOCK_Y, NODE_FLAG_BILLBOARDED_LOCK_Z, NODE_FLAG_CAMERA_ANCHORED,
+    NODE_FLAG_DONT_INHERIT_ROTATION, NODE_FLAG_DONT_INHERIT_SCALING,
+    NODE_FLAG_DONT_INHERIT_TRANSLATION,
+};
+use std::io::Write;
+
+/// Serialize a model as MDL text into any writer
+pub fn save_mdl<W: Write>(file: &mut W, model: &Model) -> Result<(), MdlError> {
+    file.write_all(save_mdl_to_string(model).as_bytes())?;
+    Ok(())
+}
+
+/// Serialize a model as MDL text. The output only depends on the model, so
+/// saving an unchanged model twice gives identical files
+pub fn save_mdl_to_string(model: &Model) -> String {
+    let mut w = MdlWriter::default();
+
+    w.line("// Saved by mdlvis-rs");
+    // MDL has no syntax for chunks the parser kept as raw bytes
+    if !model.unknown_chunks.is_empty() {
+        let tags: Vec<String> = model.unknown_chunks.iter().map(|c| c.tag_name()).collect();
+        eprintln!(
+            "[mdlvis-rs] Chunks {} can't be written as MDL and are left out",
+            tags.join(", ")
+        );
+        for chunk in &model.unknown_chunks {
+            w.line(format!(
+                "// Left out: {} chunk ({} bytes)",
+                chunk.tag_name(),
+                chunk.data.len()
+            ));
+        }
+    }
+    w.open("Version");
+    w.line(format!("FormatVersion {},", model.version));
+    w.close();
+
+    w.open(format!("Model {}", quote(&model.name)));
+    if !model.geosets.is_empty() {
+        w.line(format!("NumGeosets {},", model.geosets.len()));
+    }
+    if !model.helpers.is_empty() {
+        w.line(format!("NumHelpers {},", model.helpers.len()));
+    }
+    if !model.bones.is_empty() {
+        w.line(format!("NumBones {},", model.bones.len()));
+    }
+    if !model.animation_file.is_empty() {
+        w.line(format!("AnimationFile {},", quote(&model.animation_file)));
+    }
+    w.line(format!("BlendTime {},", model.blend_time));
+    write_extent(
+        &mut w,
+        model.minimum_extent,
+        model.maximum_extent,
+        model.bounds_radius,
+    );
+    w.close();
+
+    write_sequences(&mut w, model);
+    write_global_sequences(&mut w, model);
+    write_textures(&mut w, model);
+    write_materials(&mut w, model);
+    write_texture_animations(&mut w, model);
+    for geoset in 0..model.geosets.len() {
+        write_geoset(&mut w, model, geoset);
+    }
+    write_geoset_animations(&mut w, model);
+    write_bones(&mut w, model);
+    write_lights(&mut w, model);
+    write_helpers(&mut w, model);
+    write_attachments(&mut w, model);
+    write_pivots(&mut w, model);
+    write_particle_emitters(&mut w, model);
+    write_particle_emitters2(&mut w, model);
+    write_ribbon_emitters(&mut w, model);
+    write_cameras(&mut w, model);
+    write_event_objects(&mut w, model);
+    write_collision_shapes(&mut w, model);
+
+    w.out
+}
+
+/// Floats use the shortest representation that reads back to the same value,
+/// integral values are written without a decimal point (as in Blizzard MDLs)
+fn float(value: f32) -> String {
+    format!("{}", value)
+}
+
+fn floats(values: &[f32]) -> String {
+    let values: Vec<String> = values.iter().map(|&v| float(v)).collect();
+    format!("{{ {} }}", values.join(", "))
+}
+
+/// Quotes `s`, escaping `"` as `\"`. A backslash stays single as in the usual paths,
+/// unless the reader would take it for the start of an escape.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' if matches!(chars.peek(), None | Some('"' | '\\')) => quoted.push_str("\\\\"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Accumulates MDL text with tab indentation that follows `{` / `}` blocks
+#[derive(Default)]
+struct MdlWriter {
+    out: String,
+    indent: usize,
+}
+
+impl MdlWriter {
+    fn line(&mut self, text: impl AsRef<str>) {
+        for _ in 0..self.indent {
+            self.out.push('\t');
+        }
+        self.out.push_str(text.as_ref());
+        self.out.push('\n');
+    }
+
+    fn open(&mut self, header: impl AsRef<str>) {
+        self.line(format!("{} {{", header.as_ref()));
+        self.indent += 1;
+    }
+
+    fn close(&mut self) {
+        self.indent -= 1;
+        self.line("}");
+    }
+}
+
+fn write_sequences(w: &mut MdlWriter, model: &Model) {
+    if model.sequences.is_empty() {
+        return;
+    }
+
+    w.open(format!("Sequences {}", model.sequences.len()));
+    for sequence in &model.sequences {
+        w.open(format!("Anim {}", quote(&sequence.name)));
+        w.line(format!(
+            "Interval {{ {}, {} }},",
+            sequence.start_frame, sequence.end_frame
+        ));
+        if sequence.non_looping {
+            w.line("NonLooping,");
+        }
+        if sequence.move_speed != 0.0 {
+            w.line(format!("MoveSpeed {},", float(sequence.move_speed)));
+        }
+        if sequence.rarity != 0.0 {
+            w.line(format!("Rarity {},", float(sequence.rarity)));
+        }
+        write_extent(
+            w,
+            sequence.minimum_extent,
+            sequence.maximum_extent,
+            sequence.bounds_radius,
+        );
+        w.close();
+    }
+    w.close();
+}
+
+fn write_global_sequences(w: &mut MdlWriter, model: &Model) {
+    if model.global_sequences.is_empty() {
+        return;
+    }
+
+    w.open(format!("GlobalSequences {}", model.global_sequences.len()));
+    for duration in &model.global_sequences {
+        w.line(format!("Duration {},", duration));
+    }
+    w.close();
+}
+
+fn write_textures(w: &mut MdlWriter, model: &Model) {
+    if model.textures.is_empty() {
+        return;
+    }
+
+    w.open(format!("Textures {}", model.textures.len()));
+    for texture in &model.textures {
+        w.open("Bitmap");
+        w.line(format!("Image {},", quote(&texture.filename)));
+        if texture.replaceable_id != 0 {
+            w.line(format!("ReplaceableId {},", texture.replaceable_id));
+        }
+        if texture.wrap_width() {
+            w.line("WrapWidth,");
+        }
+        if texture.wrap_height() {
+            w.line("WrapHeight,");
+        }
+        w.close();
+    }
+    w.close();
+}
+
+fn write_materials(w: &mut MdlWriter, model: &Model) {
+    if model.materials.is_empty() {
+        return;
+    }
+
+    w.open(format!("Materials {}", model.materials.len()));
+    for material in &model.materials {
+        w.open("Material");
+        for flag in material.flag_list() {
+            w.line(format!("{},", flag.name()));
+        }
+        if material.priority_plane != 0 {
+            w.line(format!("PriorityPlane {},", material.priority_plane));
+        }
+        // Same as the MDX writer: disabled layers are dropped, overrides applied
+        for layer in material.layers.iter().filter(|l| l.is_enabled()) {
+            write_layer(w, model, layer);
+        }
+        w.close();
+    }
+    w.close();
+}
+
+fn write_layer(w: &mut MdlWriter, model: &Model, layer: &Layer) {
+    w.open("Layer");
+    w.line(format!("FilterMode {},", layer.get_filter_mode().name()));
+
+    let bits = ShadingFlags::get_bits(&layer.get_shading_flags());
+    for (flag, name) in [
+        (ShadingFlags::Unshaded, "Unshaded"),
+        (ShadingFlags::SphereEnvMap, "SphereEnvMap"),
+        (ShadingFlags::TwoSided, "TwoSided"),
+        (ShadingFlags::Unfogged, "Unfogged"),
+        (ShadingFlags::NoDepthTest, "NoDepthTest"),
+        (ShadingFlags::NoDepthSet, "NoDepthSet"),
+    ] {
+        if bits & flag as u32 != 0 {
+            w.line(format!("{},", name));
+        }
+    }
+
+    if let Some(id) = layer.texture_animation_id {
+        w.line(format!("TVertexAnimId {},", id));
+    }
+    if layer.coord_id != 0 {
+        w.line(format!("CoordId {},", layer.coord_id));
+    }
+
+    // An animated value replaces the static one
+    if layer.texture_id_idx >= 0 {
+        write_track(w, model, "TextureID", layer.texture_id_idx);
+    } else {
+        w.line(format!(
+            "static TextureID {},",
+            layer.texture_id.unwrap_or(0)
+        ));
+    }
+    let alpha = layer.get_alpha();
+    if layer.alpha_idx >= 0 {
+        write_track(w, model, "Alpha", layer.alpha_idx);
+    } else if alpha != 1.0 {
+        w.line(format!("static Alpha {},", float(alpha)));
+    }
+    w.close();
+}
+
+fn write_texture_animations(w: &mut MdlWriter, model: &Model) {
+    if model.texture_animations.is_empty() {
+        return;
+    }
+
+    w.open(format!("TextureAnims {}", model.texture_animations.len()));
+    for texture_animation in &model.texture_animations {
+        w.open("TVertexAnim");
+        write_track(w, model, "Translation", texture_animation.translation_idx);
+        write_track(w, model, "Rotation", texture_animation.rotation_idx);
+        write_track(w, model, "Scaling", texture_animation.scaling_idx);
+        w.close();
+    }
+    w.close();
+}
+
+fn write_geoset(w: &mut MdlWriter, model: &Model, geoset_idx: usize) {
+    let geoset = &model.geosets[geoset_idx];
+    w.open("Geoset");
+
+    w.open(format!("Vertices {}", geoset.vertices.len()));
+    for vertex in &geoset.vertices {
+        w.line(format!("{},", floats(&vertex.position)));
+    }
+    w.close();
+
+    if !geoset.normals.is_empty() {
+        w.open(format!("Normals {}", geoset.normals.len()));
+        for normal in &geoset.normals {
+            w.line(format!("{},", floats(&normal.normal)));
+        }
+        w.close();
+    }
+
+    // One block per UV set, in CoordId order
+    if !geoset.tex_coords.is_empty() {
+        for tex_coords in std::iter::once(&geoset.tex_coords).chain(&geoset.secondary_tex_coords) {
+            w.open(format!("TVertices {}", tex_coords.len()));
+            for tex_coord in tex_coords {
+                w.line(format!("{},", floats(&tex_coord.uv)));
+            }
+            w.close();
+        }
+    }
+
+    w.open("VertexGroup");
+    for group in &geoset.vertex_groups {
+        w.line(format!("{},", group));
+    }
+    w.close();
+
+    // One block per primitive group, named after its type
+    let mut groups = Vec::new();
+    if geoset.primitive_types.is_empty() && geoset.primitive_counts.is_empty() {
+        // Without PTYP/PCNT the indices are a plain triangle list
+        if !geoset.primitive_indices.is_empty() {
+            groups.push(("Triangles", geoset.primitive_indices.as_slice()));
+        }
+    }
+    let mut start = 0;
+    for (&primitive_type, &count) in geoset.primitive_types.iter().zip(&geoset.primitive_counts) {
+        let end = start + count as usize;
+        match (
+            primitive_type_name(primitive_type),
+            geoset.primitive_indices.get(start..end),
+        ) {
+            (Some(name), Some(indices)) => groups.push((name, indices)),
+            _ => eprintln!(
+                "[mdlvis-rs] Geoset {}: primitive group of type {} can't be written as MDL and is left out",
+                geoset_idx, primitive_type
+            ),
+        }
+        start = end;
+    }
+    let total: usize = groups.iter().map(|(_, indices)| indices.len()).sum();
+    w.open(format!("Faces {} {}", groups.len(), total));
+    for (name, indices) in groups {
+        let indices: Vec<String> = indices.iter().map(|i| i.to_string()).collect();
+        w.open(name);
+        w.line(format!("{{ {} }},", indices.join(", ")));
+        w.close();
+    }
+    w.close();
+
+    let total_count: usize = geoset.matrix_groups.iter().map(|g| g.len()).sum();
+    w.open(format!(
+        "Groups {} {}",
+        geoset.matrix_groups.len(),
+        total_count
+    ));
+    for group in &geoset.matrix_groups {
+        let bones: Vec<String> = group.iter().map(|b| b.to_string()).collect();
+        w.line(format!("Matrices {{ {} }},", bones.join(", ")));
+    }
+    w.close();
+
+    write_extent(
+        w,
+        geoset.minimum_extent,
+        geoset.maximum_extent,
+        geoset.bounds_radius,
+    );
+    for extent in &geoset.sequence_extents {
+        w.open("Anim");
+        write_extent(
+            w,
+            extent.minimum_extent,
+            extent.maximum_extent,
+            extent.bounds_radius,
+        );
+        w.close();
+    }
+
+    w.line(format!("MaterialID {},", geoset.material_id.unwrap_or(0)));
+    w.line(format!("SelectionGroup {},", geoset.selection_group));
+    if geoset.is_unselectable() {
+        w.line("Unselectable,");
+    }
+    w.close();
+}
+
+/// MDL block name of a PTYP primitive type
+fn primitive_type_name(primitive_type: u32) -> Option<&'static str> {
+    Some(match primitive_type {
+        PRIMITIVE_POINTS => "Points",
+        PRIMITIVE_LINES => "Lines",
+        PRIMITIVE_LINE_LOOP => "LineLoop",
+        PRIMITIVE_LINE_STRIP => "LineStrip",
+        PRIMITIVE_TRIANGLES => "Triangles",
+        PRIMITIVE_TRIANGLE_STRIP => "TriangleStrip",
+        PRIMITIVE_TRIANGLE_FAN => "TriangleFan",
+        PRIMITIVE_QUADS => "Quads",
+        PRIMITIVE_QUAD_STRIP => "QuadStrip",
+        PRIMITIVE_POLYGONS => "Polygons",
+        _ => return None,
+    })
+}
+
+fn write_geoset_animations(w: &mut MdlWriter, model: &Model) {
+    for geoset_animation in &model.geoset_animations {
+        w.open("GeosetAnim");
+        if geoset_animation.flags & GEOSET_ANIM_DROP_SHADOW != 0 {
+            w.line("DropShadow,");
+        }
+        // An animated value replaces the static one
+        if geoset_animation.alpha_idx >= 0 {
+            write_track(w, model, "Alpha", geoset_animation.alpha_idx);
+        } else if geoset_animation.alpha != 1.0 {
+            w.line(format!("static Alpha {},", float(geoset_animation.alpha)));
+        }
+        if geoset_animation.color_idx >= 0 {
+            write_track(w, model, "Color", geoset_animation.color_idx);
+        } else if geoset_animation.flags & GEOSET_ANIM_COLOR != 0 {
+            w.line(format!("static Color {},", floats(&geoset_animation.color)));
+        }
+        w.line(format!("GeosetId {},", geoset_animation.geoset_id));
+        w.close();
+    }
+}
+
+/// Writes the extent fields shared by models, sequences and geosets, zeros are left out
+fn write_extent(w: &mut MdlWriter, minimum: [f32; 3], maximum: [f32; 3], bounds_radius: f32) {
+    if minimum != [0.0; 3] {
+        w.line(format!("MinimumExtent {},", floats(&minimum)));
+    }
+    if maximum != [0.0; 3] {
+        w.line(format!("MaximumExtent {},", floats(&maximum)));
+    }
+    if bounds_radius != 0.0 {
+        w.line(format!("BoundsRadius {},", float(bounds_radius)));
+    }
+}
+
+/// Writes `Tag <count> { ... }` for an animated value, nothing for -1 (static)
+fn write_track(w: &mut MdlWriter, model: &Model, tag: &str, controller_idx: i32) {
+    let Some(controller) = usize::try_from(controller_idx)
+        .ok()
+        .and_then(|idx| model.controllers.get(idx))
+    else {
+        return;
+    };
+    write_controller(w, tag, controller);
+}
+
+fn write_controller(w: &mut MdlWriter, tag: &str, controller: &AnimationController) {
+    w.open(format!("{} {}", tag, controller.keyframes.len()));
+    w.line(match controller.interpolation_type {
+        0 => "DontInterp,",
+        2 => "Hermite,",
+        3 => "Bezier,",
+        _ => "Linear,",
+    });
+    if controller.global_seq_id >= 0 {
+        w.line(format!("GlobalSeqId {},", controller.global_seq_id));
+    }
+
+    // Tangents are present only for Hermite (2) and Bezier (3)
+    let has_tangents = controller.interpolation_type == 2 || controller.interpolation_type == 3;
+    let values = |v: &[f32]| {
+        if v.len() == 1 { float(v[0]) } else { floats(v) }
+    };
+
+    for keyframe in &controller.keyframes {
+        w.line(format!("{}: {},", keyframe.frame, values(&keyframe.data)));
+        if has_tangents {
+            w.indent += 1;
+            w.line(format!("InTan {},", values(&keyframe.in_tan)));
+            w.line(format!("OutTan {},", values(&keyframe.out_tan)));
+            w.indent -= 1;
+        }
+    }
+    w.close();
+}
+
+/// Writes the fields shared by every node type
+fn write_node(w: &mut MdlWriter, object_id: u32, parent_id: i32, flags: u32) {
+    w.line(format!("ObjectId {},", object_id));
+    if parent_id >= 0 {
+        w.line(format!("Parent {},", parent_id));
+    }
+    let dont_inherit: Vec<&str> = [
+        (NODE_FLAG_DONT_INHERIT_TRANSLATION, "Translation"),
+        (NODE_FLAG_DONT_INHERIT_ROTATION, "Rotation"),
+        (NODE_FLAG_DONT_INHERIT_SCALING, "Scaling"),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| flags & flag != 0)
+    .map(|(_, name)| name)
+    .collect();
+    if !dont_inherit.is_empty() {
+        w.line(format!("DontInherit {{ {} }},", dont_inherit.join(", ")));
+    }
+    for (flag, name) in [
+        (NODE_FLAG_BILLBOARDED, "Billboarded"),
+        (NODE_FLAG_BILLBOARDED_LOCK_X, "BillboardedLockX"),
+        (NODE_FLAG_BILLBOARDED_LOCK_Y, "BillboardedLockY"),
+        (NODE_FLAG_BILLBOARDED_LOCK_Z, "BillboardedLockZ"),
+        (NODE_FLAG_CAMERA_ANCHORED, "CameraAnchored"),
+    ] {
+        if flags & flag != 0 {
+            w.line(format!("{},", name));
+        }
+    }
+}
+
+fn write_bones(w: &mut MdlWriter, model: &Model) {
+    for bone in &model.bones {
+        w.open(format!("Bone {}", quote(&bone.name)));
+        write_node(w, bone.object_id, bone.parent_id, bone.flags);
+        match bone.geoset_id {
+            Some(id) => w.line(format!("GeosetId {},", id)),
+            None => w.line("GeosetId Multiple,"),
+        }
+        match bone.geoset_anim_id {
+            Some(id) => w.line(format!("GeosetAnimId {},", id)),
+            None => w.line("GeosetAnimId None,"),
+        }
+        write_track(w, model, "Translation", bone.translation_idx);
+        write_track(w, model, "Rotation", bone.rotation_idx);
+        write_track(w, model, "Scaling", bone.scaling_idx);
+        write_track(w, model, "Visibility", bone.visibility_idx);
+        w.close();
+    }
+}
+
+fn write_helpers(w: &mut MdlWriter, model: &Model) {
+    for helper in &model.helpers {
+        w.open(format!("Helper {}", quote(&helper.name)));
+        write_node(w, helper.object_id, helper.parent_id, helper.flags);
+        write_track(w, model, "Translation", helper.translation_idx);
+        write_track(w, model, "Rotation", helper.rotation_idx);
+        write_track(w, model, "Scaling", helper.scaling_idx);
+        write_track(w, model, "Visibility", helper.visibility_idx);
+        w.close();
+    }
+}
+
+fn write_attachments(w: &mut MdlWriter, model: &Model) {
+    for attachment in &model.attachments {
+        w.open(format!("Attachment {}", quote(&attachment.name)));
+        write_node(
+            w,
+            attachment.object_id,
+            attachment.parent_id,
+            attachment.flags,
+        );
+        w.line(format!("AttachmentID {},", attachment.attachment_id));
+        if !attachment.path.is_empty() {
+            w.line(format!("Path {},", quote(&attachment.path)));
+        }
+        write_track(w, model, "Translation", attachment.translation_idx);
+        write_track(w, model, "Rotation", attachment.rotation_idx);
+        write_track(w, model, "Scaling", attachment.scaling_idx);
+        write_track(w, model, "Visibility", attachment.visibility_idx);
+        w.close();
+    }
+}
+
+fn write_pivots(w: &mut MdlWriter, model: &Model) {
+    if model.pivot_points.is_empty() {
+        return;
+    }
+
+    w.open(format!("PivotPoints {}", model.pivot_points.len()));
+    for pivot in &model.pivot_points {
+        w.line(format!("{},", floats(pivot)));
+    }
+    w.close();
+}
+
+/// Writes `static Tag <value>,` or the track that replaces it
+fn write_static_or_track(w: &mut MdlWriter, model: &Model, tag: &str, value: f32, idx: i32) {
+    if idx >= 0 {
+        write_track(w, model, tag, idx);
+    } else {
+        w.line(format!("static {} {},", tag, float(value)));
+    }
+}
+
+/// Same as `write_static_or_track` for a color
+fn write_static_or_color(w: &mut MdlWriter, model: &Model, tag: &str, color: [f32; 3], idx: i32) {
+    if idx >= 0 {
+        write_track(w, model, tag, idx);
+    } else {
+        w.line(format!("static {} {},", tag, floats(&color)));
+    }
+}
+
+fn write_lights(w: &mut MdlWriter, model: &Model) {
+    for light in &model.lights {
+        w.open(format!("Light {}", quote(&light.name)));
+        write_node(w, light.object_id, light.parent_id, light.flags);
+        w.line(match light.light_type {
+            LIGHT_TYPE_DIRECTIONAL => "Directional,",
+            LIGHT_TYPE_AMBIENT => "Ambient,",
+            _ => "Omnidirectional,",
+        });
+        write_static_or_track(
+            w,
+            model,
+            "AttenuationStart",
+            light.attenuation_start,
+            light.attenuation_start_idx,
+        );
+        write_static_or_track(
+            w,
+            model,
+            "AttenuationEnd",
+            light.attenuation_end,
+            light.attenuation_end_idx,
+        );
+        write_static_or_color(w, model, "Color", light.color, light.color_idx);
+        write_static_or_track(w, model, "Intensity", light.intensity, light.intensity_idx);
+        write_static_or_color(w, model, "AmbColor", light.amb_color, light.amb_color_idx);
+        write_static_or_track(
+            w,
+            model,
+            "AmbIntensity",
+            light.amb_intensity,
+            light.amb_intensity_idx,
+        );
+        write_track(w, model, "Translation", light.translation_idx);
+        write_track(w, model, "Rotation", light.rotation_idx);
+        write_track(w, model, "Scaling", light.scaling_idx);
+        write_track(w, model, "Visibility", light.visibility_idx);
+        w.close();
+    }
+}
+
+fn write_particle_emitters(w: &mut MdlWriter, model: &Model) {
+    for emitter in &model.particle_emitters {
+        w.open(format!("ParticleEmitter {}", quote(&emitter.name)));
+        write_node(w, emitter.object_id, emitter.parent_id, emitter.flags);
+        for (flag, name) in [
+            (PARTICLE_FLAG_EMITTER_USES_MDL, "EmitterUsesMDL"),
+            (PARTICLE_FLAG_EMITTER_USES_TGA, "EmitterUsesTGA"),
+        ] {
+            if emitter.has_flag(flag) {
+                w.line(format!("{},", name));
+            }
+        }
+        write_static_or_track(
+            w,
+            model,
+            "EmissionRate",
+            emitter.emission_rate,
+            emitter.emission_rate_idx,
+        );
+        write_static_or_track(w, model, "Gravity", emitter.gravity, emitter.gravity_idx);
+        write_static_or_track(
+            w,
+            model,
+            "Longitude",
+            emitter.longitude,
+            emitter.longitude_idx,
+        );
+        write_static_or_track(w, model, "Latitude", emitter.latitude, emitter.latitude_idx);
+
+        w.open("Particle");
+        write_static_or_track(w, model, "LifeSpan", emitter.lifespan, emitter.lifespan_idx);
+        write_static_or_track(w, model, "InitVelocity", emitter.speed, emitter.speed_idx);
+        w.line(format!("Path {},", quote(&emitter.path)));
+        w.close();
+
+        write_track(w, model, "Translation", emitter.translation_idx);
+        write_track(w, model, "Rotation", emitter.rotation_idx);
+        write_track(w, model, "Scaling", emitter.scaling_idx);
+        write_track(w, model, "Visibility", emitter.visibility_idx);
+        w.close();
+    }
+}
+
+fn write_particle_emitters2(w: &mut MdlWriter, model: &Model) {
+    for emitter in &model.particle_emitters2 {
+        w.open(format!("ParticleEmitter2 {}", quote(&emitter.name)));
+        write_node(w, emitter.object_id, emitter.parent_id, emitter.flags);
+        for (flag, name) in [
+            (PARTICLE2_FLAG_SORT_PRIMS_FAR_Z, "SortPrimsFarZ"),
+            (PARTICLE2_FLAG_UNSHADED, "Unshaded"),
+            (PARTICLE2_FLAG_LINE_EMITTER, "LineEmitter"),
+            (PARTICLE2_FLAG_UNFOGGED, "Unfogged"),
+            (PARTICLE2_FLAG_MODEL_SPACE, "ModelSpace"),
+            (PARTICLE2_FLAG_XY_QUAD, "XYQuad"),
+        ] {
+            if emitter.has_flag(flag) {
+                w.line(format!("{},", name));
+            }
+        }
+        write_static_or_track(w, model, "Speed", emitter.speed, emitter.speed_idx);
+        write_static_or_track(
+            w,
+            model,
+            "Variation",
+            emitter.variation,
+            emitter.variation_idx,
+        );
+        write_static_or_track(w, model, "Latitude", emitter.latitude, emitter.latitude_idx);
+        write_static_or_track(w, model, "Gravity", emitter.gravity, emitter.gravity_idx);
+        if emitter.squirt {
+            w.line("Squirt,");
+        }
+        w.line(format!("LifeSpan {},", float(emitter.lifespan)));
+        write_static_or_track(
+            w,
+            model,
+            "EmissionRate",
+            emitter.emission_rate,
+            emitter.emission_rate_idx,
+        );
+        write_static_or_track(w, model, "Width", emitter.width, emitter.width_idx);
+        write_static_or_track(w, model, "Length", emitter.length, emitter.length_idx);
+        w.line(match emitter.filter_mode {
+            PARTICLE2_FILTER_ADDITIVE => "Additive,",
+            PARTICLE2_FILTER_MODULATE => "Modulate,",
+            PARTICLE2_FILTER_MODULATE_2X => "Modulate2x,",
+            PARTICLE2_FILTER_ALPHA_KEY => "AlphaKey,",
+            _ => "Blend,",
+        });
+        w.line(format!("Rows {},", emitter.rows));
+        w.line(format!("Columns {},", emitter.columns));
+        w.line(match emitter.head_or_tail {
+            PARTICLE2_TAIL => "Tail,",
+            PARTICLE2_BOTH => "Both,",
+            _ => "Head,",
+        });
+        w.line(format!("TailLength {},", float(emitter.tail_length)));
+        w.line(format!("Time {},", float(emitter.time)));
+
+        w.open("SegmentColor");
+        for color in emitter.segment_colors {
+            w.line(format!("Color {},", floats(&color)));
+        }
+        w.close();
+        w.line(format!(
+            "Alpha {{ {}, {}, {} }},",
+            emitter.segment_alpha[0], emitter.segment_alpha[1], emitter.segment_alpha[2]
+        ));
+        w.line(format!(
+            "ParticleScaling {},",
+            floats(&emitter.segment_scaling)
+        ));
+        for (tag, interval) in [
+            ("LifeSpanUVAnim", emitter.head_intervals),
+            ("DecayUVAnim", emitter.head_decay_intervals),
+            ("TailUVAnim", emitter.tail_intervals),
+            ("TailDecayUVAnim", emitter.tail_decay_intervals),
+        ] {
+            w.line(format!(
+                "{} {{ {}, {}, {} }},",
+                tag, interval[0], interval[1], interval[2]
+            ));
+        }
+        w.line(format!("TextureID {},", emitter.texture_id));
+        if emitter.replaceable_id != 0 {
+            w.line(format!("ReplaceableId {},", emitter.replaceable_id));
+        }
+        if emitter.priority_plane != 0 {
+            w.line(format!("PriorityPlane {},", emitter.priority_plane));
+        }
+        write_track(w, model, "Translation", emitter.translation_idx);
+        write_track(w, model, "Rotation", emitter.rotation_idx);
+        write_track(w, model, "Scaling", emitter.scaling_idx);
+        write_track(w, model, "Visibility", emitter.visibility_idx);
+        w.close();
+    }
+}
+
+fn write_ribbon_emitters(w: &mut MdlWriter, model: &Model) {
+    for emitter in &model.ribbon_emitters {
+        w.open(format!("RibbonEmitter {}", quote(&emitter.name)));
+        write_node(w, emitter.object_id, emitter.parent_id, emitter.flags);
+        write_static_or_track(
+            w,
+            model,
+            "HeightAbove",
+            emitter.height_above,
+            emitter.height_above_idx,
+        );
+        write_static_or_track(
+            w,
+            model,
+            "HeightBelow",
+            emitter.height_below,
+            emitter.height_below_idx,
+        );
+        write_static_or_track(w, model, "Alpha", emitter.alpha, emitter.alpha_idx);
+
+        write_static_or_color(w, model, "Color", emitter.color, emitter.color_idx);
+        if emitter.texture_slot_idx >= 0 {
+            write_track(w, model, "TextureSlot", emitter.texture_slot_idx);
+        } else {
+            w.line(format!("static TextureSlot {},", emitter.texture_slot));
+        }
+        w.line(format!("EmissionRate {},", emitter.emission_rate));
+        w.line(format!("LifeSpan {},", float(emitter.lifespan)));
+        if emitter.gravity != 0.0 {
+            w.line(format!("Gravity {},", float(emitter.gravity)));
+        }
+        w.line(format!("Rows {},", emitter.rows));
+        w.line(format!("Columns {},", emitter.columns));
+        w.line(format!("MaterialID {},", emitter.material_id));
+        write_track(w, model, "Translation", emitter.translation_idx);
+        write_track(w, model, "Rotation", emitter.rotation_idx);
+        write_track(w, model, "Scaling", emitter.scaling_idx);
+        write_track(w, model, "Visibility", emitter.visibility_idx);
+        w.close();
+    }
+}
+
+fn write_cameras(w: &mut MdlWriter, model: &Model) {
+    for camera in &model.cameras {
+        w.open(format!("Camera {}", quote(&camera.name)));
+        w.line(format!("Position {},", floats(&camera.position)));
+        write_track(w, model, "Translation", camera.translation_idx);
+        write_track(w, model, "Rotation", camera.rotation_idx);
+        w.line(format!("FieldOfView {},", float(camera.field_of_view)));
+        w.line(format!("FarClip {},", float(camera.far_clip)));
+        w.line(format!("NearClip {},", float(camera.near_clip)));
+        w.open("Target");
+        w.line(format!("Position {},", floats(&camera.target_position)));
+        write_track(w, model, "Translation", camera.target_translation_idx);
+        w.close();
+        w.close();
+    }
+}
+
+fn write_event_objects(w: &mut MdlWriter, model: &Model) {
+    for event in &model.event_objects {
+        w.open(format!("EventObject {}", quote(&event.name)));
+        write_node(w, event.object_id, event.parent_id, event.flags);
+        if event.has_track {
+            w.open(format!("EventTrack {}", event.frames.len()));
+            if event.global_seq_id >= 0 {
+                w.line(format!("GlobalSeqId {},", event.global_seq_id));
+            }
+            for frame in &event.frames {
+                w.line(format!("{},", frame));
+            }
+            w.close();
+        }
+        write_track(w, model, "Translation", event.translation_idx);
+        write_track(w, model, "Rotation", event.rotation_idx);
+        write_track(w, model, "Scaling", event.scaling_idx);
+        w.close();
+    }
+}
+
+fn write_collision_shapes(w: &mut MdlWriter, model: &Model) {
+    for shape in &model.collision_shapes {
+        w.open(format!("CollisionShape {}", quote(&shape.name)));
+        write_node(w, shape.object_id, shape.parent_id, shape.flags);
+        w.line(match shape.shape_type {
+            COLLISION_SHAPE_BOX => "Box,",
+            COLLISION_SHAPE_PLANE => "Plane,",
+            COLLISION_SHAPE_CYLINDER => "Cylinder,",
+            _ => "Sphere,",
+        });
+        w.open(format!("Vertices {}", shape.vertices.len()));
+        for vertex in &shape.vertices {
+            w.line(format!("{},", floats(vertex)));
+        }
+        w.close();
+        if CollisionShape::has_radius(shape.shape_type) {
+            w.line(format!("BoundsRadius {},", float(shape.radius)));
+        }
+        write_track(w, model, "Translation", shape.translation_idx);
+        write_track(w, model, "Rotation", shape.rotation_idx);
+        write_track(w, model, "Scaling", shape.scaling_idx);
+        w.close();
+    }
+}
@@ -0,0 +1,4 @@
+mod chunks;
+mod geoset;
+pub mod mdl;
+pub mod save;
@@ -0,0 +1,137 @@
+use crate::error::MdlError;
+use crate::model::model::Model;
+use crate::writer::chunks;
+use crate::writer::geoset::geoset_write;
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::io::Write;
+
+// Chunks the writer produces from Model fields, in the canonical order
+const KNOWN_CHUNKS: [&[u8; 4]; 20] = [
+    b"VERS", b"MODL", b"SEQS", b"GLBS", b"MTLS", b"TEXS", b"TXAN", b"GEOS", b"GEOA", b"BONE",
+    b"LITE", b"HELP", b"ATCH", b"PIVT", b"PREM", b"PRE2", b"RIBB", b"CAMS", b"EVTS", b"CLID",
+];
+
+/// Serialize a model as MDX into any writer (file, buffer)
+pub fn save<W: Write>(file: &mut W, model: &Model) -> Result<(), MdlError> {
+    file.write_all(b"MDLX")?;
+
+    // Follow the layout of the loaded file, putting unparsed chunks back where they were
+    let mut raw_chunks = model.unknown_chunks.iter();
+    let mut written: Vec<[u8; 4]> = Vec::new();
+    for tag in &model.chunk_order {
+        if KNOWN_CHUNKS.contains(&tag) {
+            if !written.contains(tag) {
+                write_known_chunk(file, tag, model)?;
+                written.push(*tag);
+            }
+        } else if let Some(raw) = raw_chunks.next() {
+            write_chunk(file, &raw.tag, |buf| {
+                buf.extend_from_slice(&raw.data);
+                Ok(())
+            })?;
+        }
+    }
+
+    // Everything the original file did not have (or all chunks for a model not loaded from MDX)
+    for tag in KNOWN_CHUNKS {
+        if !written.contains(tag) {
+            write_known_chunk(file, tag, model)?;
+        }
+    }
+    for raw in raw_chunks {
+        write_chunk(file, &raw.tag, |buf| {
+            buf.extend_from_slice(&raw.data);
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
+
+// Writes one of KNOWN_CHUNKS, skipping chunks that would be empty
+fn write_known_chunk<W: Write>(file: &mut W, tag: &[u8; 4], model: &Model) -> Result<(), MdlError> {
+    match tag {
+        b"VERS" => write_chunk(file, b"VERS", |buf| {
+            buf.write_u32::<LittleEndian>(model.version)?;
+            Ok(())
+        }),
+        b"MODL" => write_chunk(file, b"MODL", |buf| chunks::write_model_info(buf, model)),
+        b"SEQS" if !model.sequences.is_empty() => {
+            write_chunk(file, b"SEQS", |buf| chunks::write_sequences(buf, model))
+        }
+        b"GLBS" if !model.global_sequences.is_empty() => write_chunk(file, b"GLBS", |buf| {
+            chunks::write_global_sequences(buf, model)
+        }),
+        b"MTLS" if !model.materials.is_empty() => {
+            write_chunk(file, b"MTLS", |buf| chunks::write_materials(buf, model))
+        }
+        b"TEXS" if !model.textures.is_empty() => {
+            write_chunk(file, b"TEXS", |buf| chunks::write_textures(buf, model))
+        }
+        b"TXAN" if !model.texture_animations.is_empty() => write_chunk(file, b"TXAN", |buf| {
+            chunks::write_texture_animations(buf, model)
+        }),
+        b"GEOS" if !model.geosets.is_empty() => {
+            write_chunk(file, b"GEOS", |buf| geoset_write(buf, model))
+        }
+        b"GEOA" if !model.geoset_animations.is_empty() => write_chunk(file, b"GEOA", |buf| {
+            chunks::write_geoset_animations(buf, model)
+        }),
+        b"BONE" if !model.bones.is_empty() => {
+            write_chunk(file, b"BONE", |buf| chunks::write_bones(buf, model))
+        }
+        b"LITE" if !model.lights.is_empty() => {
+            write_chunk(file, b"LITE", |buf| chunks::write_lights(buf, model))
+        }
+        b"HELP" if !model.helpers.is_empty() => {
+            write_chunk(file, b"HELP", |buf| chunks::write_helpers(buf, model))
+        }
+        b"ATCH" if !model.attachments.is_empty() => {
+            write_chunk(file, b"ATCH", |buf| chunks::write_attachments(buf, model))
+        }
+        b"PIVT" if !model.pivot_points.is_empty() => {
+            write_chunk(file, b"PIVT", |buf| chunks::write_pivots(buf, model))
+        }
+        b"PREM" if !model.particle_emitters.is_empty() => write_chunk(file, b"PREM", |buf| {
+            chunks::write_particle_emitters(buf, model)
+        }),
+        b"PRE2" if !model.particle_emitters2.is_empty() => write_chunk(file, b"PRE2", |buf| {
+            chunks::write_particle_emitters2(buf, model)
+        }),
+        b"RIBB" if !model.ribbon_emitters.is_empty() => write_chunk(file, b"RIBB", |buf| {
+            chunks::write_ribbon_emitters(buf, model)
+        }),
+        b"CAMS" if !model.cameras.is_empty() => {
+            write_chunk(file, b"CAMS", |buf| chunks::write_cameras(buf, model))
+        }
+        b"EVTS" if !model.event_objects.is_empty() => {
+            write_chunk(file, b"EVTS", |buf| chunks::write_event_objects(buf, model))
+        }
+        b"CLID" if !model.collision_shapes.is_empty() => write_chunk(file, b"CLID", |buf| {
+            chunks::write_collision_shapes(buf, model)
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Serialize a model as MDX into an in-memory buffer
+pub fn save_to_bytes(model: &Model) -> Result<Vec<u8>, MdlError> {
+    let mut bytes = Vec::new();
+    save(&mut bytes, model)?;
+    Ok(bytes)
+}
+
+// Chunk body is built in memory first so the size header is known up front
+fn write_chunk<W: Write>(
+    file: &mut W,
+    tag: &[u8; 4],
+    body: impl FnOnce(&mut Vec<u8>) -> Result<(), MdlError>,
+) -> Result<(), MdlError> {
+    let mut buf = Vec::new();
+    body(&mut buf)?;
+
+    file.write_all(tag)?;
+    file.write_u32::<LittleEndian>(buf.len() as u32)?;
+    file.write_all(&buf)?;
+    Ok(())
+}
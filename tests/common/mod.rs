@@ -0,0 +1,24 @@
+use std::path::{Path, PathBuf};
+
+/// Every .mdx model under test-data, sorted so failures are reported in a stable order
+pub fn test_models() -> Vec<PathBuf> {
+    fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+        for entry in std::fs::read_dir(dir).expect("test-data is readable") {
+            let path = entry.expect("test-data entry").path();
+            if path.is_dir() {
+                walk(&path, out);
+            } else if path.extension().is_some_and(|ext| ext == "mdx") {
+                out.push(path);
+            }
+        }
+    }
+
+    let mut models = Vec::new();
+    walk(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data"),
+        &mut models,
+    );
+    models.sort();
+    assert!(!models.is_empty(), "no .mdx models found in test-data");
+    models
+}
@@ -0,0 +1,84 @@
+mod common;
+
+/// Loading and saving an MDX gives back the exact bytes of the file
+#[test]
+fn mdx_save_is_byte_identical() {
+    for path in common::test_models() {
+        let bytes = std::fs::read(&path).unwrap();
+        let model = mdlvis_rs::load_from_bytes(&bytes).unwrap();
+        let saved = mdlvis_rs::save_to_bytes(&model).unwrap();
+        if let Some(offset) = bytes.iter().zip(&saved).position(|(a, b)| a != b) {
+            panic!("{} differs at byte {}", path.display(), offset);
+        }
+        assert_eq!(bytes.len(), saved.len(), "{}", path.display());
+    }
+}
+
+/// Converting an MDX to MDL and back keeps everything the MDL format can express
+#[test]
+fn mdl_conversion_keeps_model() {
+    for path in common::test_models() {
+        let original = mdlvis_rs::load_from_bytes(&std::fs::read(&path).unwrap()).unwrap();
+        let text = mdlvis_rs::save_mdl_to_string(&original);
+        let from_mdl = mdlvis_rs::load_mdl_from_str(&text).unwrap();
+        let converted =
+            mdlvis_rs::load_from_bytes(&mdlvis_rs::save_to_bytes(&from_mdl).unwrap()).unwrap();
+
+        let (expected, actual) = (
+            format!("{:#?}", without_mdx_only_data(original)),
+            format!("{:#?}", without_mdx_only_data(converted)),
+        );
+        if let Some((line, (a, b))) = expected
+            .lines()
+            .zip(actual.lines())
+            .enumerate()
+            .find(|(_, (a, b))| a != b)
+        {
+            panic!(
+                "{} differs at line {}: {} | {}",
+                path.display(),
+                line,
+                a.trim(),
+                b.trim()
+            );
+        }
+        assert_eq!(expected.len(), actual.len(), "{}", path.display());
+    }
+}
+
+/// Clears what only MDX files hold: raw chunks, chunk order, padding and missing
+/// or replaced node type bits
+fn without_mdx_only_data(mut model: mdlvis_rs::Model) -> mdlvis_rs::Model {
+    model.unknown_chunks.clear();
+    model.chunk_order.clear();
+    model.animation_file_padding.clear();
+    for sequence in &mut model.sequences {
+        sequence.sync_point = 0;
+    }
+    for bone in &mut model.bones {
+        bone.type_flag_missing = false;
+    }
+    for light in &mut model.lights {
+        light.type_flag_missing = false;
+    }
+    for attachment in &mut model.attachments {
+        attachment.type_flag_missing = false;
+    }
+    for emitter in &mut model.particle_emitters {
+        emitter.type_flag_missing = false;
+    }
+    for emitter in &mut model.particle_emitters2 {
+        emitter.type_flag_missing = false;
+    }
+    for emitter in &mut model.ribbon_emitters {
+        emitter.flags &= !mdlvis_rs::model::emitter::RIBBON_FLAG_ALT_TYPE;
+        emitter.type_flag_missing = false;
+    }
+    for event in &mut model.event_objects {
+        event.type_flag_missing = false;
+    }
+    for shape in &mut model.collision_shapes {
+        shape.type_flag_missing = false;
+    }
+    model
+}